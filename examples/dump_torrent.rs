@@ -1,17 +1,71 @@
 use clap::Parser;
 use expanded_pathbuf::ExpandedPathBuf;
 use rbtorrent::{ffi::AnnounceEntry, TorrentInfo};
+use std::time::UNIX_EPOCH;
 
-/// Generates a torrent file from the specified file or directory and writes it to standard out
+/// Prints the metadata of a torrent file
 #[derive(Debug, Parser)]
 struct Opt {
     file: ExpandedPathBuf,
 }
 
-fn main() {
+fn main() -> anyhow::Result<()> {
     let opt = Opt::parse();
 
-    let torrent_info = TorrentInfo::open(&opt.file.to_string_lossy());
+    let torrent_info = TorrentInfo::from_bytes(&std::fs::read(&opt.file)?)?;
+
+    println!("name: {}", torrent_info.name());
+    let info_hashes = torrent_info.info_hashes();
+    if let Some(hash) = info_hashes.v1 {
+        println!("info hash v1: {hash}");
+    }
+    if let Some(hash) = info_hashes.v2 {
+        println!("info hash v2: {hash}");
+    }
+    println!("total size: {}", torrent_info.total_size());
+    println!(
+        "pieces: {} x {}",
+        torrent_info.num_pieces(),
+        torrent_info.piece_length()
+    );
+    println!("private: {}", torrent_info.is_private());
+    println!("i2p: {}", torrent_info.is_i2p());
+    if let Some(comment) = torrent_info.comment() {
+        println!("comment: {comment}");
+    }
+    if let Some(creator) = torrent_info.creator() {
+        println!("created by: {creator}");
+    }
+    if let Some(date) = torrent_info.creation_date() {
+        let secs = date.duration_since(UNIX_EPOCH)?.as_secs();
+        println!("creation date: {secs}");
+    }
+    println!("info section: {} bytes", torrent_info.info_section().len());
+    if torrent_info.ssl_cert().is_some() {
+        println!("ssl torrent");
+    }
+
+    println!("files:");
+    for file in torrent_info.files().iter() {
+        if file.is_pad_file() {
+            continue;
+        }
+
+        let mut attributes = String::new();
+        for (set, c) in [
+            (file.is_executable(), 'x'),
+            (file.is_hidden(), 'h'),
+            (file.is_symlink(), 'l'),
+        ] {
+            attributes.push(if set { c } else { '-' });
+        }
+
+        print!("{:>12} {attributes} {}", file.size, file.path.display());
+        if let Some(target) = &file.symlink {
+            print!(" -> {}", target.display());
+        }
+        println!();
+    }
 
     let mut nodes_printed = false;
     for (hostname, port) in torrent_info.nodes() {
@@ -32,4 +86,30 @@ fn main() {
 
         println!("{tier:02}: {url}");
     }
+
+    let web_seeds = torrent_info.web_seeds();
+    if !web_seeds.is_empty() {
+        println!("web seeds:");
+    }
+    for seed in web_seeds {
+        println!("{:?}: {}", seed.kind, seed.url);
+    }
+
+    let similar = torrent_info.similar_torrents();
+    if !similar.is_empty() {
+        println!("similar torrents:");
+    }
+    for hash in similar {
+        println!("{hash}");
+    }
+
+    let collections = torrent_info.collections();
+    if !collections.is_empty() {
+        println!("collections:");
+    }
+    for collection in collections {
+        println!("{collection}");
+    }
+
+    Ok(())
 }
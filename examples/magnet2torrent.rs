@@ -1,84 +1,64 @@
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 
+use anyhow::{bail, Context};
 use clap::Parser;
-use rbtorrent::{AlertCategory, SessionBuilder, TorrentFlags};
-use rbtorrent_sys::ffi;
+use rbtorrent::{
+    AddTorrentParams, AlertCategory, AlertKind, MagnetUri, SessionBuilder, TorrentFlags,
+    WriteTorrentFlags,
+};
 
+/// Downloads the metadata of a magnet link and writes it to a .torrent file
 #[derive(Parser)]
 struct Opt {
-    magnet_uri: String,
+    magnet_uri: MagnetUri,
+    output: PathBuf,
 }
 
-fn main() {
+fn main() -> anyhow::Result<()> {
     let opt = Opt::parse();
 
     let session = SessionBuilder::new()
         .with_alert_mask(AlertCategory::STATUS | AlertCategory::ERROR)
         .set_disable_disk(true)
-        .build();
+        .build()?;
 
-    let mut atp = rbtorrent::AddTorrentParams::from_magnet_uri(opt.magnet_uri);
-    atp.save_path = Some(".".parse().unwrap());
-    atp.torrent_flags = Some(TorrentFlags::DEFAULT_DONT_DOWNLOAD);
+    let alerts = session.alerts()?;
 
-    session.add_torrent(atp);
+    let atp = AddTorrentParams::from_magnet(&opt.magnet_uri)
+        .with_save_path(".".into())
+        .with_torrent_flags(TorrentFlags::DEFAULT_DONT_DOWNLOAD);
 
-    loop {
-        session.handle_alerts(|alerts| {
-            for mut alert in alerts {
-                println!("{}", alert.message());
+    let handle = session.add_torrent(atp)?;
 
-                if let Some(alert) = alert.as_metadata_received() {
-                    let handle = alert.torrent_handle();
-                } else if let Some(alert) = alert.as_save_resume_data() {
-                } else if let Some(alert) = alert.as_save_resume_data_failed() {
-                }
-            }
-        });
+    for alert in alerts.into_blocking_iter() {
+        println!("{}", alert.message);
+
+        match alert.kind {
+            AlertKind::MetadataReceived => {
+                eprintln!("metadata received");
 
-        // 	for (lt::alert* a : alerts)
-        // 	{
-        // 		std::cout << a->message() << std::endl;
-        // 		if (auto const* mra = lt::alert_cast<lt::metadata_received_alert>(a))
-        // 		{
-        // 			std::cerr << "metadata received" << std::endl;
-        // 			auto const handle = mra->handle;
-        // 			std::shared_ptr<lt::torrent_info const> ti = handle.torrent_file();
-        // 			if (!ti)
-        // 			{
-        // 				std::cerr << "unexpected missing torrent info" << std::endl;
-        // 				goto done;
-        // 			}
+                let ti = handle
+                    .torrent_file()?
+                    .context("unexpected missing torrent info")?;
 
-        // 			// in order to create valid v2 torrents, we need to download the
-        // 			// piece hashes. libtorrent currently only downloads the hashes
-        // 			// on-demand, so we would have to download all the content.
-        // 			// Instead, produce an invalid v2 torrent that's missing piece
-        // 			// layers
-        // 			if (ti->v2())
-        // 			{
-        // 				std::cerr << "found v2 torrent, generating a torrent missing piece hashes" << std::endl;
-        // 			}
-        // 			handle.save_resume_data(lt::torrent_handle::save_info_dict);
-        // 			handle.set_flags(lt::torrent_flags::paused);
-        // 		}
-        // 		else if (auto* rda = lt::alert_cast<lt::save_resume_data_alert>(a))
-        // 		{
-        // 			// don't include piece layers
-        // 			rda->params.merkle_trees.clear();
-        // 			lt::entry e = lt::write_torrent_file(rda->params, lt::write_flags::allow_missing_piece_layer);
-        // 			std::vector<char> torrent;
-        // 			lt::bencode(std::back_inserter(torrent), e);
-        // 			std::ofstream out(argv[2]);
-        // 			out.write(torrent.data(), int(torrent.size()));
-        // 			goto done;
-        // 		}
-        // 		else if (auto const* rdf = lt::alert_cast<lt::save_resume_data_failed_alert>(a))
-        // 		{
-        // 			std::cerr << "failed to save resume data: " << rdf->message() << std::endl;
-        // 			goto done;
-        // 		}
-        // 	}
-        session.wait_for_alert(Duration::from_millis(200));
+                // in order to create valid v2 torrents, we need to download the
+                // piece hashes. libtorrent currently only downloads the hashes
+                // on-demand, so we would have to download all the content.
+                // Instead, produce an invalid v2 torrent that's missing piece
+                // layers
+                let torrent =
+                    ti.to_bencoded_torrent(WriteTorrentFlags::ALLOW_MISSING_PIECE_LAYER)?;
+                std::fs::write(&opt.output, torrent)?;
+                break;
+            }
+            AlertKind::MetadataFailed { error } => {
+                bail!("failed to receive metadata: {error}");
+            }
+            _ => {}
+        }
     }
+
+    session.shutdown(Duration::from_secs(5))?;
+
+    Ok(())
 }
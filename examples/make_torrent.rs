@@ -1,6 +1,10 @@
 use clap::Parser;
 use expanded_pathbuf::ExpandedPathBuf;
-use std::io::Write;
+use rbtorrent::{CancelToken, CreateTorrent, PadFiles, PathFilter, Sha1Hash, TorrentFormat};
+use std::{
+    io::{stderr, Write},
+    time::{Duration, UNIX_EPOCH},
+};
 
 /// Generates a torrent file from the specified file or directory and writes it to standard out
 #[derive(Debug, Parser)]
@@ -9,16 +13,148 @@ struct Opt {
     /// Specifies the output filename of the torrent file. If this is not specified, the torrent file is printed to the standard out.
     #[clap(short, long)]
     output: Option<ExpandedPathBuf>,
+    /// Piece size in bytes, picked from the total size if not specified
+    #[clap(short, long)]
+    piece_size: Option<u32>,
+    /// Creates a v1 torrent without the v2 info
+    #[clap(long, conflicts_with = "v2_only")]
+    v1_only: bool,
+    /// Creates a v2 torrent without the v1 info
+    #[clap(long)]
+    v2_only: bool,
+    /// Adds a tier of trackers, as comma separated urls. May be repeated.
+    #[clap(short, long = "tracker")]
+    trackers: Vec<String>,
+    /// Adds a url seed. May be repeated.
+    #[clap(short, long = "web-seed")]
+    web_seeds: Vec<String>,
+    /// Adds a DHT node, as host:port. May be repeated.
+    #[clap(long = "dht-node", parse(try_from_str = parse_node))]
+    dht_nodes: Vec<(String, u16)>,
+    #[clap(short, long)]
+    comment: Option<String>,
+    /// Name of the program creating the torrent
+    #[clap(long)]
+    creator: Option<String>,
+    /// Creation date in seconds since the epoch, the current time if not specified
+    #[clap(long, conflicts_with = "no_creation_date")]
+    creation_date: Option<u64>,
+    /// Leaves out the creation date
+    #[clap(long)]
+    no_creation_date: bool,
+    /// Marks the torrent as private, disabling DHT, PEX and local peer discovery
+    #[clap(long)]
+    private: bool,
+    /// Source tag, used by private trackers
+    #[clap(short, long)]
+    source: Option<String>,
+    /// Adds the torrent to a collection. May be repeated.
+    #[clap(long = "collection")]
+    collections: Vec<String>,
+    /// Adds the info-hash of a similar torrent. May be repeated.
+    #[clap(long = "similar")]
+    similar_torrents: Vec<Sha1Hash>,
+    /// Only adds the files whose name matches the pattern, `*` and `?` are wildcards. May be repeated.
+    #[clap(long)]
+    include: Vec<String>,
+    /// Leaves out the files and directories whose name matches the pattern. May be repeated.
+    #[clap(long)]
+    exclude: Vec<String>,
+    /// Adds symlinks as symlinks instead of the files they point to
+    #[clap(long)]
+    symlinks: bool,
+    /// Records the modification time of the files
+    #[clap(long)]
+    mtime: bool,
+    /// Leaves out the executable and hidden attributes of the files
+    #[clap(long)]
+    no_attributes: bool,
+    /// Aligns the files to pieces with pad files, also for v1 torrents
+    #[clap(long)]
+    align_files: bool,
+    /// Number of threads hashing the files, one if not specified
+    #[clap(short = 'j', long)]
+    threads: Option<u32>,
+    /// Prints the hashing progress to standard error
+    #[clap(long)]
+    progress: bool,
 }
 
-fn main() {
+fn parse_node(s: &str) -> anyhow::Result<(String, u16)> {
+    let (host, port) = s
+        .rsplit_once(':')
+        .ok_or_else(|| anyhow::anyhow!("expected host:port"))?;
+    Ok((host.to_string(), port.parse()?))
+}
+
+fn main() -> anyhow::Result<()> {
     let opt = Opt::parse();
 
-    let encoded = rbtorrent::ffi::create_torrent_for_path(&opt.file.0.to_string_lossy());
+    let format = if opt.v1_only {
+        TorrentFormat::V1Only
+    } else if opt.v2_only {
+        TorrentFormat::V2Only
+    } else {
+        TorrentFormat::Hybrid
+    };
+
+    let mut trackers = Vec::new();
+    for (tier, urls) in opt.trackers.iter().enumerate() {
+        let tier = u8::try_from(tier).map_err(|_| anyhow::anyhow!("at most 256 tracker tiers"))?;
+        trackers.extend(urls.split(',').map(|url| (url.to_string(), tier)));
+    }
+
+    let filter = !opt.include.is_empty() || !opt.exclude.is_empty();
+    let create = CreateTorrent {
+        piece_size: opt.piece_size,
+        format,
+        trackers,
+        web_seeds: opt.web_seeds,
+        dht_nodes: opt.dht_nodes,
+        comment: opt.comment,
+        creator: opt.creator,
+        creation_date: if opt.no_creation_date {
+            Some(UNIX_EPOCH)
+        } else {
+            opt.creation_date
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+        },
+        private: opt.private,
+        source: opt.source,
+        collections: opt.collections,
+        similar_torrents: opt.similar_torrents,
+        filter: filter.then(|| PathFilter::globs(opt.include, opt.exclude)),
+        symlinks: opt.symlinks,
+        modification_times: opt.mtime,
+        attributes: !opt.no_attributes,
+        pad_files: if opt.align_files {
+            PadFiles::Aligned
+        } else {
+            PadFiles::default()
+        },
+        hashing_threads: opt.threads,
+        ..CreateTorrent::new(opt.file.0)
+    };
+
+    let encoded = if opt.progress {
+        let encoded = create.generate_with_progress(
+            |done, total| {
+                eprint!("\rhashing {done}/{total} pieces");
+                let _ = stderr().flush();
+            },
+            &CancelToken::new(),
+        )?;
+        eprintln!();
+        encoded
+    } else {
+        create.generate()?
+    };
 
     if let Some(output) = opt.output {
-        std::fs::write(output.0, encoded).unwrap();
+        std::fs::write(output.0, encoded)?;
     } else {
-        std::io::stdout().write_all(&encoded).unwrap();
+        std::io::stdout().write_all(&encoded)?;
     }
+
+    Ok(())
 }
@@ -0,0 +1,1170 @@
+use crate::{
+    AddTorrentParams, AlertCategory, ErrorCode, InfoHash, SessionToken, Sha1Hash, TorrentHandle,
+    TorrentStatus,
+};
+use futures::{channel::mpsc::UnboundedReceiver, executor::block_on, Stream, StreamExt};
+use rbtorrent_sys::ffi::{self, torrent_state};
+use std::{
+    marker::PhantomData,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+
+/// An alert popped from the session, along with the session it came from
+pub struct Alert(pub(crate) ffi::Alert, pub(crate) SessionToken);
+
+pub struct MetadataReceivedAlert<'alert> {
+    inner: *mut ffi::metadata_received_alert,
+    session: &'alert SessionToken,
+}
+
+impl<'alert> MetadataReceivedAlert<'alert> {
+    pub fn torrent_handle(&self) -> TorrentHandle {
+        let hdl = unsafe { &*ffi::metadata_received_alert_get_torrent_handle(self.inner) };
+        TorrentHandle::new(ffi::torrent_handle_clone(hdl), self.session.clone())
+    }
+}
+
+pub struct SaveResumeDataAlert<'alert> {
+    inner: *mut ffi::save_resume_data_alert,
+    _marker: PhantomData<&'alert mut ()>,
+}
+
+impl<'alert> SaveResumeDataAlert<'alert> {
+    /// Bencoded resume data of the torrent
+    pub fn resume_data(&self) -> Vec<u8> {
+        unsafe { ffi::save_resume_data_alert_get_resume_data(self.inner) }
+    }
+
+    /// Parameters to add the torrent back from its resume data
+    pub fn add_torrent_params(&self) -> AddTorrentParams {
+        AddTorrentParams::from_resume_data(self.resume_data())
+    }
+}
+
+pub struct SaveResumeDataFailedAlert<'alert> {
+    inner: *mut ffi::save_resume_data_failed_alert,
+    _marker: PhantomData<&'alert mut ()>,
+}
+
+impl<'alert> SaveResumeDataFailedAlert<'alert> {
+    pub fn error(&self) -> ErrorCode {
+        unsafe { ffi::save_resume_data_failed_alert_get_error(self.inner) }
+    }
+}
+
+impl Alert {
+    pub fn message(&self) -> String {
+        ffi::alert_message(&self.0)
+    }
+
+    /// Copies the alert into its typed representation
+    pub fn kind(&self) -> AlertKind {
+        AlertKind::from(ffi::alert_data(&self.0))
+    }
+
+    /// Copies the alert into plain Rust data that stays valid after the
+    /// session pops alerts again and can be sent to other threads
+    pub fn to_owned(&self) -> OwnedAlert {
+        OwnedAlert {
+            handle: self.torrent_handle(),
+            ..OwnedAlert::from(ffi::alert_data(&self.0))
+        }
+    }
+
+    /// Handle of the torrent the alert refers to, for alerts deriving from
+    /// `lt::torrent_alert`
+    pub fn torrent_handle(&self) -> Option<TorrentHandle> {
+        let hdl = ffi::alert_get_torrent_handle(&self.0);
+
+        (!hdl.is_null()).then(|| TorrentHandle::new(hdl, self.1.clone()))
+    }
+
+    pub fn as_metadata_received(&mut self) -> Option<MetadataReceivedAlert<'_>> {
+        let v = ffi::alert_cast_metadata_received(self.0);
+
+        if v.is_null() {
+            None
+        } else {
+            Some(MetadataReceivedAlert {
+                inner: v,
+                session: &self.1,
+            })
+        }
+    }
+
+    pub fn as_save_resume_data(&mut self) -> Option<SaveResumeDataAlert<'_>> {
+        let v = ffi::alert_cast_save_resume_data(self.0);
+
+        if v.is_null() {
+            None
+        } else {
+            Some(SaveResumeDataAlert {
+                inner: v,
+                _marker: PhantomData,
+            })
+        }
+    }
+
+    pub fn as_save_resume_data_failed(&mut self) -> Option<SaveResumeDataFailedAlert<'_>> {
+        let v = ffi::alert_cast_save_resume_data_failed(self.0);
+
+        if v.is_null() {
+            None
+        } else {
+            Some(SaveResumeDataFailedAlert {
+                inner: v,
+                _marker: PhantomData,
+            })
+        }
+    }
+}
+
+/// Snapshot of an alert that does not borrow from the session
+#[derive(Clone, Debug)]
+pub struct OwnedAlert {
+    /// value of `alert::type()`
+    pub alert_type: i32,
+    /// value of `alert::what()`, e.g. ``torrent_finished``
+    pub what: String,
+    pub category: AlertCategory,
+    /// time at which the alert was posted
+    pub timestamp: SystemTime,
+    pub message: String,
+    /// name of the torrent the alert refers to, if any
+    pub torrent_name: Option<String>,
+    /// handle of the torrent the alert refers to, if any
+    pub handle: Option<TorrentHandle>,
+    pub kind: AlertKind,
+}
+
+impl From<ffi::AlertData> for OwnedAlert {
+    fn from(mut d: ffi::AlertData) -> Self {
+        let timestamp = if d.timestamp_us >= 0 {
+            SystemTime::UNIX_EPOCH + Duration::from_micros(d.timestamp_us as u64)
+        } else {
+            SystemTime::UNIX_EPOCH - Duration::from_micros(d.timestamp_us.unsigned_abs())
+        };
+
+        Self {
+            alert_type: d.alert_type,
+            what: d.what.clone(),
+            category: AlertCategory::from(d.category),
+            timestamp,
+            message: std::mem::take(&mut d.message),
+            torrent_name: Some(std::mem::take(&mut d.torrent_name)).filter(|v| !v.is_empty()),
+            handle: None,
+            kind: AlertKind::from(d),
+        }
+    }
+}
+
+/// Stream of alerts delivered by the session as they are posted.
+///
+/// Ends when the session shuts down.
+pub struct AlertStream {
+    rx: UnboundedReceiver<OwnedAlert>,
+}
+
+impl AlertStream {
+    pub(crate) fn new(rx: UnboundedReceiver<OwnedAlert>) -> Self {
+        Self { rx }
+    }
+
+    /// Turns the stream into an iterator that blocks the calling thread
+    /// until the next alert arrives
+    pub fn into_blocking_iter(self) -> BlockingAlertIter {
+        BlockingAlertIter { inner: self }
+    }
+}
+
+impl Stream for AlertStream {
+    type Item = OwnedAlert;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_next_unpin(cx)
+    }
+}
+
+/// Blocking iterator over an [`AlertStream`]
+pub struct BlockingAlertIter {
+    inner: AlertStream,
+}
+
+impl Iterator for BlockingAlertIter {
+    type Item = OwnedAlert;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        block_on(self.inner.next())
+    }
+}
+
+/// Transport a socket uses, mirroring `lt::socket_type_t`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SocketType {
+    Tcp,
+    Socks5,
+    Http,
+    Utp,
+    I2p,
+    TcpSsl,
+    Socks5Ssl,
+    HttpSsl,
+    UtpSsl,
+    Other(i32),
+}
+
+impl From<i32> for SocketType {
+    fn from(v: i32) -> Self {
+        match v {
+            0 => Self::Tcp,
+            1 => Self::Socks5,
+            2 => Self::Http,
+            3 => Self::Utp,
+            4 => Self::I2p,
+            5 => Self::TcpSsl,
+            6 => Self::Socks5Ssl,
+            7 => Self::HttpSsl,
+            8 => Self::UtpSsl,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// Event sent in a tracker announce, mirroring `lt::event_t`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TrackerEvent {
+    None,
+    Completed,
+    Started,
+    Stopped,
+    Paused,
+    Other(i32),
+}
+
+impl From<i32> for TrackerEvent {
+    fn from(v: i32) -> Self {
+        match v {
+            0 => Self::None,
+            1 => Self::Completed,
+            2 => Self::Started,
+            3 => Self::Stopped,
+            4 => Self::Paused,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// Port mapping mechanism, mirroring `lt::portmap_transport`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PortmapTransport {
+    NatPmp,
+    Upnp,
+    Other(i32),
+}
+
+impl From<i32> for PortmapTransport {
+    fn from(v: i32) -> Self {
+        match v {
+            0 => Self::NatPmp,
+            1 => Self::Upnp,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// Protocol of a port mapping, mirroring `lt::portmap_protocol`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PortmapProtocol {
+    None,
+    Tcp,
+    Udp,
+    Other(i32),
+}
+
+impl From<i32> for PortmapProtocol {
+    fn from(v: i32) -> Self {
+        match v {
+            0 => Self::None,
+            1 => Self::Tcp,
+            2 => Self::Udp,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// Direction of a logged message or packet
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Incoming,
+    Outgoing,
+}
+
+impl From<i32> for Direction {
+    fn from(v: i32) -> Self {
+        if v == 0 {
+            Self::Incoming
+        } else {
+            Self::Outgoing
+        }
+    }
+}
+
+/// Values of `alert::type()` for the libtorrent alert classes, named after
+/// the class without the `_alert` suffix
+pub mod alert_types {
+    pub const TORRENT_ADDED: i32 = 3;
+    pub const TORRENT_REMOVED: i32 = 4;
+    pub const READ_PIECE: i32 = 5;
+    pub const FILE_COMPLETED: i32 = 6;
+    pub const FILE_RENAMED: i32 = 7;
+    pub const FILE_RENAME_FAILED: i32 = 8;
+    pub const PERFORMANCE: i32 = 9;
+    pub const STATE_CHANGED: i32 = 10;
+    pub const TRACKER_ERROR: i32 = 11;
+    pub const TRACKER_WARNING: i32 = 12;
+    pub const SCRAPE_REPLY: i32 = 13;
+    pub const SCRAPE_FAILED: i32 = 14;
+    pub const TRACKER_REPLY: i32 = 15;
+    pub const DHT_REPLY: i32 = 16;
+    pub const TRACKER_ANNOUNCE: i32 = 17;
+    pub const HASH_FAILED: i32 = 18;
+    pub const PEER_BAN: i32 = 19;
+    pub const PEER_UNSNUBBED: i32 = 20;
+    pub const PEER_SNUBBED: i32 = 21;
+    pub const PEER_ERROR: i32 = 22;
+    pub const PEER_CONNECT: i32 = 23;
+    pub const PEER_DISCONNECTED: i32 = 24;
+    pub const INVALID_REQUEST: i32 = 25;
+    pub const TORRENT_FINISHED: i32 = 26;
+    pub const PIECE_FINISHED: i32 = 27;
+    pub const REQUEST_DROPPED: i32 = 28;
+    pub const BLOCK_TIMEOUT: i32 = 29;
+    pub const BLOCK_FINISHED: i32 = 30;
+    pub const BLOCK_DOWNLOADING: i32 = 31;
+    pub const UNWANTED_BLOCK: i32 = 32;
+    pub const STORAGE_MOVED: i32 = 33;
+    pub const STORAGE_MOVED_FAILED: i32 = 34;
+    pub const TORRENT_DELETED: i32 = 35;
+    pub const TORRENT_DELETE_FAILED: i32 = 36;
+    pub const SAVE_RESUME_DATA: i32 = 37;
+    pub const SAVE_RESUME_DATA_FAILED: i32 = 38;
+    pub const TORRENT_PAUSED: i32 = 39;
+    pub const TORRENT_RESUMED: i32 = 40;
+    pub const TORRENT_CHECKED: i32 = 41;
+    pub const URL_SEED: i32 = 42;
+    pub const FILE_ERROR: i32 = 43;
+    pub const METADATA_FAILED: i32 = 44;
+    pub const METADATA_RECEIVED: i32 = 45;
+    pub const UDP_ERROR: i32 = 46;
+    pub const EXTERNAL_IP: i32 = 47;
+    pub const LISTEN_FAILED: i32 = 48;
+    pub const LISTEN_SUCCEEDED: i32 = 49;
+    pub const PORTMAP_ERROR: i32 = 50;
+    pub const PORTMAP: i32 = 51;
+    pub const PORTMAP_LOG: i32 = 52;
+    pub const FASTRESUME_REJECTED: i32 = 53;
+    pub const PEER_BLOCKED: i32 = 54;
+    pub const DHT_ANNOUNCE: i32 = 55;
+    pub const DHT_GET_PEERS: i32 = 56;
+    pub const CACHE_FLUSHED: i32 = 58;
+    pub const LSD_PEER: i32 = 60;
+    pub const TRACKERID: i32 = 61;
+    pub const DHT_BOOTSTRAP: i32 = 62;
+    pub const TORRENT_ERROR: i32 = 64;
+    pub const TORRENT_NEED_CERT: i32 = 65;
+    pub const INCOMING_CONNECTION: i32 = 66;
+    pub const ADD_TORRENT: i32 = 67;
+    pub const STATE_UPDATE: i32 = 68;
+    pub const SESSION_STATS: i32 = 70;
+    pub const DHT_ERROR: i32 = 73;
+    pub const DHT_IMMUTABLE_ITEM: i32 = 74;
+    pub const DHT_MUTABLE_ITEM: i32 = 75;
+    pub const DHT_PUT: i32 = 76;
+    pub const I2P: i32 = 77;
+    pub const DHT_OUTGOING_GET_PEERS: i32 = 78;
+    pub const LOG: i32 = 79;
+    pub const TORRENT_LOG: i32 = 80;
+    pub const PEER_LOG: i32 = 81;
+    pub const LSD_ERROR: i32 = 82;
+    pub const DHT_STATS: i32 = 83;
+    pub const INCOMING_REQUEST: i32 = 84;
+    pub const DHT_LOG: i32 = 85;
+    pub const DHT_PKT: i32 = 86;
+    pub const DHT_GET_PEERS_REPLY: i32 = 87;
+    pub const DHT_DIRECT_RESPONSE: i32 = 88;
+    pub const PICKER_LOG: i32 = 89;
+    pub const SESSION_ERROR: i32 = 90;
+    pub const DHT_LIVE_NODES: i32 = 91;
+    pub const SESSION_STATS_HEADER: i32 = 92;
+    pub const DHT_SAMPLE_INFOHASHES: i32 = 93;
+    pub const BLOCK_UPLOADED: i32 = 94;
+    pub const ALERTS_DROPPED: i32 = 95;
+    pub const SOCKS5: i32 = 96;
+    pub const FILE_PRIO: i32 = 97;
+}
+
+/// Typed payload of an alert, one variant per libtorrent alert type.
+///
+/// Variants are named after the libtorrent class without the `_alert`
+/// suffix. Fields common to every torrent alert, such as the torrent name,
+/// are not repeated here. Piece, block and file indices are `None` when
+/// libtorrent reports a negative index, i.e. none applies.
+#[derive(Clone, Debug)]
+pub enum AlertKind {
+    /// Deprecated in libtorrent 2.0 in favour of [`AlertKind::AddTorrent`]
+    TorrentAdded,
+    TorrentRemoved {
+        info_hash: InfoHash,
+    },
+    ReadPiece {
+        error: ErrorCode,
+        piece: Option<u32>,
+        buffer: Vec<u8>,
+    },
+    FileCompleted {
+        index: Option<u32>,
+    },
+    FileRenamed {
+        index: Option<u32>,
+        old_name: String,
+        new_name: String,
+    },
+    FileRenameFailed {
+        index: Option<u32>,
+        error: ErrorCode,
+    },
+    Performance {
+        /// value of `lt::performance_alert::performance_warning_t`
+        warning_code: i32,
+    },
+    StateChanged {
+        state: torrent_state,
+        prev_state: torrent_state,
+    },
+    TrackerError {
+        url: String,
+        local_endpoint: SocketAddr,
+        times_in_row: i32,
+        error: ErrorCode,
+        operation: String,
+        failure_reason: String,
+    },
+    TrackerWarning {
+        url: String,
+        local_endpoint: SocketAddr,
+        message: String,
+    },
+    ScrapeReply {
+        url: String,
+        local_endpoint: SocketAddr,
+        incomplete: i32,
+        complete: i32,
+    },
+    ScrapeFailed {
+        url: String,
+        local_endpoint: SocketAddr,
+        error: ErrorCode,
+        message: String,
+    },
+    TrackerReply {
+        url: String,
+        local_endpoint: SocketAddr,
+        num_peers: i32,
+    },
+    DhtReply {
+        url: String,
+        local_endpoint: SocketAddr,
+        num_peers: i32,
+    },
+    TrackerAnnounce {
+        url: String,
+        local_endpoint: SocketAddr,
+        event: TrackerEvent,
+    },
+    HashFailed {
+        piece: Option<u32>,
+    },
+    PeerBan {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+    },
+    PeerUnsnubbed {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+    },
+    PeerSnubbed {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+    },
+    PeerError {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+        operation: String,
+        error: ErrorCode,
+    },
+    PeerConnect {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+        socket_type: SocketType,
+    },
+    PeerDisconnected {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+        socket_type: SocketType,
+        operation: String,
+        error: ErrorCode,
+        /// value of `lt::close_reason_t`
+        reason: i32,
+    },
+    InvalidRequest {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+        piece: Option<u32>,
+        start: i32,
+        length: i32,
+        we_have: bool,
+        peer_interested: bool,
+        withheld: bool,
+    },
+    TorrentFinished,
+    PieceFinished {
+        piece: Option<u32>,
+    },
+    RequestDropped {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+        block: Option<u32>,
+        piece: Option<u32>,
+    },
+    BlockTimeout {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+        block: Option<u32>,
+        piece: Option<u32>,
+    },
+    BlockFinished {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+        block: Option<u32>,
+        piece: Option<u32>,
+    },
+    BlockDownloading {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+        block: Option<u32>,
+        piece: Option<u32>,
+    },
+    UnwantedBlock {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+        block: Option<u32>,
+        piece: Option<u32>,
+    },
+    BlockUploaded {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+        block: Option<u32>,
+        piece: Option<u32>,
+    },
+    StorageMoved {
+        storage_path: String,
+        old_path: String,
+    },
+    StorageMovedFailed {
+        error: ErrorCode,
+        file_path: String,
+        operation: String,
+    },
+    TorrentDeleted {
+        info_hash: InfoHash,
+    },
+    TorrentDeleteFailed {
+        error: ErrorCode,
+        info_hash: InfoHash,
+    },
+    SaveResumeData {
+        info_hash: InfoHash,
+        /// Bencoded resume data, see [`AddTorrentParams::from_resume_data`]
+        resume_data: Vec<u8>,
+    },
+    SaveResumeDataFailed {
+        error: ErrorCode,
+    },
+    TorrentPaused,
+    TorrentResumed,
+    TorrentChecked,
+    UrlSeed {
+        server_url: String,
+        error: ErrorCode,
+        message: String,
+    },
+    FileError {
+        error: ErrorCode,
+        operation: String,
+        filename: String,
+    },
+    MetadataFailed {
+        error: ErrorCode,
+    },
+    MetadataReceived,
+    UdpError {
+        endpoint: SocketAddr,
+        operation: String,
+        error: ErrorCode,
+    },
+    ExternalIp {
+        external_address: IpAddr,
+    },
+    ListenFailed {
+        listen_interface: String,
+        endpoint: SocketAddr,
+        error: ErrorCode,
+        operation: String,
+        socket_type: SocketType,
+    },
+    ListenSucceeded {
+        endpoint: SocketAddr,
+        socket_type: SocketType,
+    },
+    PortmapError {
+        mapping: i32,
+        transport: PortmapTransport,
+        error: ErrorCode,
+    },
+    Portmap {
+        mapping: i32,
+        external_port: u16,
+        protocol: PortmapProtocol,
+        transport: PortmapTransport,
+    },
+    PortmapLog {
+        transport: PortmapTransport,
+        message: String,
+    },
+    FastresumeRejected {
+        error: ErrorCode,
+        file_path: String,
+        operation: String,
+    },
+    PeerBlocked {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+        /// value of `lt::peer_blocked_alert::reason_t`
+        reason: i32,
+    },
+    DhtAnnounce {
+        endpoint: SocketAddr,
+        info_hash: Sha1Hash,
+    },
+    DhtGetPeers {
+        info_hash: Sha1Hash,
+    },
+    CacheFlushed,
+    LsdPeer {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+    },
+    Trackerid {
+        url: String,
+        local_endpoint: SocketAddr,
+        tracker_id: String,
+    },
+    DhtBootstrap,
+    TorrentError {
+        error: ErrorCode,
+        filename: String,
+    },
+    TorrentNeedCert,
+    IncomingConnection {
+        endpoint: SocketAddr,
+        socket_type: SocketType,
+    },
+    AddTorrent {
+        error: ErrorCode,
+    },
+    StateUpdate {
+        status: Vec<TorrentStatus>,
+    },
+    SessionStats {
+        counters: Vec<i64>,
+    },
+    DhtError {
+        error: ErrorCode,
+        operation: String,
+    },
+    DhtImmutableItem {
+        target: Sha1Hash,
+        /// bencoded item
+        item: Vec<u8>,
+    },
+    DhtMutableItem {
+        key: [u8; 32],
+        salt: Vec<u8>,
+        seq: i64,
+        /// bencoded item
+        item: Vec<u8>,
+        authoritative: bool,
+    },
+    DhtPut {
+        target: Sha1Hash,
+        public_key: [u8; 32],
+        salt: Vec<u8>,
+        seq: i64,
+        num_success: i32,
+    },
+    I2p {
+        error: ErrorCode,
+    },
+    DhtOutgoingGetPeers {
+        info_hash: Sha1Hash,
+        endpoint: SocketAddr,
+    },
+    Log {
+        message: String,
+    },
+    TorrentLog {
+        message: String,
+    },
+    PeerLog {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+        direction: Direction,
+        event_type: String,
+        message: String,
+    },
+    LsdError {
+        error: ErrorCode,
+    },
+    DhtStats,
+    IncomingRequest {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+        piece: Option<u32>,
+        start: i32,
+        length: i32,
+    },
+    DhtLog {
+        /// value of `lt::dht_log_alert::dht_module_t`
+        module: i32,
+        message: String,
+    },
+    DhtPkt {
+        direction: Direction,
+        endpoint: SocketAddr,
+        packet: Vec<u8>,
+    },
+    DhtGetPeersReply {
+        info_hash: Sha1Hash,
+        peers: Vec<SocketAddr>,
+    },
+    DhtDirectResponse {
+        endpoint: SocketAddr,
+        /// bencoded response
+        response: Vec<u8>,
+    },
+    PickerLog {
+        endpoint: SocketAddr,
+        pid: [u8; 20],
+        picker_flags: u32,
+    },
+    SessionError {
+        error: ErrorCode,
+    },
+    DhtLiveNodes {
+        node_id: Sha1Hash,
+        nodes: Vec<SocketAddr>,
+    },
+    SessionStatsHeader,
+    DhtSampleInfohashes {
+        endpoint: SocketAddr,
+        interval: Duration,
+        num_infohashes: i32,
+        samples: Vec<Sha1Hash>,
+    },
+    AlertsDropped,
+    Socks5 {
+        endpoint: SocketAddr,
+        error: ErrorCode,
+        operation: String,
+    },
+    FilePrio {
+        error: ErrorCode,
+        operation: String,
+    },
+    /// An alert type not known to this version of the bindings
+    Unknown {
+        alert_type: i32,
+        what: String,
+    },
+}
+
+fn socket_addr(ep: &ffi::Endpoint) -> SocketAddr {
+    ep.to_socket_addr()
+        .unwrap_or_else(|| SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), ep.port))
+}
+
+fn info_hash(v1: [u8; 20], v2: [u8; 32]) -> InfoHash {
+    InfoHash::from(ffi::InfoHashes { v1, v2 })
+}
+
+impl From<ffi::AlertData> for AlertKind {
+    fn from(d: ffi::AlertData) -> Self {
+        let endpoint = socket_addr(&d.endpoint);
+        let local_endpoint = socket_addr(&d.local_endpoint);
+        let pid = d.peer_id;
+        let piece = u32::try_from(d.piece).ok();
+        let block = u32::try_from(d.block).ok();
+        let index = u32::try_from(d.file).ok();
+
+        match d.alert_type {
+            alert_types::TORRENT_ADDED => Self::TorrentAdded,
+            alert_types::TORRENT_REMOVED => Self::TorrentRemoved {
+                info_hash: info_hash(d.hash_v1, d.hash_v2),
+            },
+            alert_types::READ_PIECE => Self::ReadPiece {
+                error: d.error,
+                piece,
+                buffer: d.buffer,
+            },
+            alert_types::FILE_COMPLETED => Self::FileCompleted { index },
+            alert_types::FILE_RENAMED => Self::FileRenamed {
+                index,
+                old_name: d.old_path,
+                new_name: d.path,
+            },
+            alert_types::FILE_RENAME_FAILED => Self::FileRenameFailed {
+                index,
+                error: d.error,
+            },
+            alert_types::PERFORMANCE => Self::Performance {
+                warning_code: d.reason,
+            },
+            alert_types::STATE_CHANGED => Self::StateChanged {
+                state: d.state,
+                prev_state: d.prev_state,
+            },
+            alert_types::TRACKER_ERROR => Self::TrackerError {
+                url: d.url,
+                local_endpoint,
+                times_in_row: d.times_in_row,
+                error: d.error,
+                operation: d.operation,
+                failure_reason: d.text,
+            },
+            alert_types::TRACKER_WARNING => Self::TrackerWarning {
+                url: d.url,
+                local_endpoint,
+                message: d.text,
+            },
+            alert_types::SCRAPE_REPLY => Self::ScrapeReply {
+                url: d.url,
+                local_endpoint,
+                incomplete: d.incomplete,
+                complete: d.complete,
+            },
+            alert_types::SCRAPE_FAILED => Self::ScrapeFailed {
+                url: d.url,
+                local_endpoint,
+                error: d.error,
+                message: d.text,
+            },
+            alert_types::TRACKER_REPLY => Self::TrackerReply {
+                url: d.url,
+                local_endpoint,
+                num_peers: d.num_peers,
+            },
+            alert_types::DHT_REPLY => Self::DhtReply {
+                url: d.url,
+                local_endpoint,
+                num_peers: d.num_peers,
+            },
+            alert_types::TRACKER_ANNOUNCE => Self::TrackerAnnounce {
+                url: d.url,
+                local_endpoint,
+                event: d.event.into(),
+            },
+            alert_types::HASH_FAILED => Self::HashFailed { piece },
+            alert_types::PEER_BAN => Self::PeerBan { endpoint, pid },
+            alert_types::PEER_UNSNUBBED => Self::PeerUnsnubbed { endpoint, pid },
+            alert_types::PEER_SNUBBED => Self::PeerSnubbed { endpoint, pid },
+            alert_types::PEER_ERROR => Self::PeerError {
+                endpoint,
+                pid,
+                operation: d.operation,
+                error: d.error,
+            },
+            alert_types::PEER_CONNECT => Self::PeerConnect {
+                endpoint,
+                pid,
+                socket_type: d.socket_type.into(),
+            },
+            alert_types::PEER_DISCONNECTED => Self::PeerDisconnected {
+                endpoint,
+                pid,
+                socket_type: d.socket_type.into(),
+                operation: d.operation,
+                error: d.error,
+                reason: d.reason,
+            },
+            alert_types::INVALID_REQUEST => Self::InvalidRequest {
+                endpoint,
+                pid,
+                piece,
+                start: d.start,
+                length: d.length,
+                we_have: d.we_have,
+                peer_interested: d.peer_interested,
+                withheld: d.withheld,
+            },
+            alert_types::TORRENT_FINISHED => Self::TorrentFinished,
+            alert_types::PIECE_FINISHED => Self::PieceFinished { piece },
+            alert_types::REQUEST_DROPPED => Self::RequestDropped {
+                endpoint,
+                pid,
+                block,
+                piece,
+            },
+            alert_types::BLOCK_TIMEOUT => Self::BlockTimeout {
+                endpoint,
+                pid,
+                block,
+                piece,
+            },
+            alert_types::BLOCK_FINISHED => Self::BlockFinished {
+                endpoint,
+                pid,
+                block,
+                piece,
+            },
+            alert_types::BLOCK_DOWNLOADING => Self::BlockDownloading {
+                endpoint,
+                pid,
+                block,
+                piece,
+            },
+            alert_types::UNWANTED_BLOCK => Self::UnwantedBlock {
+                endpoint,
+                pid,
+                block,
+                piece,
+            },
+            alert_types::BLOCK_UPLOADED => Self::BlockUploaded {
+                endpoint,
+                pid,
+                block,
+                piece,
+            },
+            alert_types::STORAGE_MOVED => Self::StorageMoved {
+                storage_path: d.path,
+                old_path: d.old_path,
+            },
+            alert_types::STORAGE_MOVED_FAILED => Self::StorageMovedFailed {
+                error: d.error,
+                file_path: d.path,
+                operation: d.operation,
+            },
+            alert_types::TORRENT_DELETED => Self::TorrentDeleted {
+                info_hash: info_hash(d.hash_v1, d.hash_v2),
+            },
+            alert_types::TORRENT_DELETE_FAILED => Self::TorrentDeleteFailed {
+                error: d.error,
+                info_hash: info_hash(d.hash_v1, d.hash_v2),
+            },
+            alert_types::SAVE_RESUME_DATA => Self::SaveResumeData {
+                info_hash: info_hash(d.hash_v1, d.hash_v2),
+                resume_data: d.buffer,
+            },
+            alert_types::SAVE_RESUME_DATA_FAILED => Self::SaveResumeDataFailed { error: d.error },
+            alert_types::TORRENT_PAUSED => Self::TorrentPaused,
+            alert_types::TORRENT_RESUMED => Self::TorrentResumed,
+            alert_types::TORRENT_CHECKED => Self::TorrentChecked,
+            alert_types::URL_SEED => Self::UrlSeed {
+                server_url: d.url,
+                error: d.error,
+                message: d.text,
+            },
+            alert_types::FILE_ERROR => Self::FileError {
+                error: d.error,
+                operation: d.operation,
+                filename: d.path,
+            },
+            alert_types::METADATA_FAILED => Self::MetadataFailed { error: d.error },
+            alert_types::METADATA_RECEIVED => Self::MetadataReceived,
+            alert_types::UDP_ERROR => Self::UdpError {
+                endpoint,
+                operation: d.operation,
+                error: d.error,
+            },
+            alert_types::EXTERNAL_IP => Self::ExternalIp {
+                external_address: endpoint.ip(),
+            },
+            alert_types::LISTEN_FAILED => Self::ListenFailed {
+                listen_interface: d.text,
+                endpoint,
+                error: d.error,
+                operation: d.operation,
+                socket_type: d.socket_type.into(),
+            },
+            alert_types::LISTEN_SUCCEEDED => Self::ListenSucceeded {
+                endpoint,
+                socket_type: d.socket_type.into(),
+            },
+            alert_types::PORTMAP_ERROR => Self::PortmapError {
+                mapping: d.mapping,
+                transport: d.transport.into(),
+                error: d.error,
+            },
+            alert_types::PORTMAP => Self::Portmap {
+                mapping: d.mapping,
+                external_port: d.endpoint.port,
+                protocol: d.protocol.into(),
+                transport: d.transport.into(),
+            },
+            alert_types::PORTMAP_LOG => Self::PortmapLog {
+                transport: d.transport.into(),
+                message: d.text,
+            },
+            alert_types::FASTRESUME_REJECTED => Self::FastresumeRejected {
+                error: d.error,
+                file_path: d.path,
+                operation: d.operation,
+            },
+            alert_types::PEER_BLOCKED => Self::PeerBlocked {
+                endpoint,
+                pid,
+                reason: d.reason,
+            },
+            alert_types::DHT_ANNOUNCE => Self::DhtAnnounce {
+                endpoint,
+                info_hash: Sha1Hash(d.hash_v1),
+            },
+            alert_types::DHT_GET_PEERS => Self::DhtGetPeers {
+                info_hash: Sha1Hash(d.hash_v1),
+            },
+            alert_types::CACHE_FLUSHED => Self::CacheFlushed,
+            alert_types::LSD_PEER => Self::LsdPeer { endpoint, pid },
+            alert_types::TRACKERID => Self::Trackerid {
+                url: d.url,
+                local_endpoint,
+                tracker_id: d.text,
+            },
+            alert_types::DHT_BOOTSTRAP => Self::DhtBootstrap,
+            alert_types::TORRENT_ERROR => Self::TorrentError {
+                error: d.error,
+                filename: d.path,
+            },
+            alert_types::TORRENT_NEED_CERT => Self::TorrentNeedCert,
+            alert_types::INCOMING_CONNECTION => Self::IncomingConnection {
+                endpoint,
+                socket_type: d.socket_type.into(),
+            },
+            alert_types::ADD_TORRENT => Self::AddTorrent { error: d.error },
+            alert_types::STATE_UPDATE => Self::StateUpdate {
+                status: d.statuses.into_iter().map(TorrentStatus::from).collect(),
+            },
+            alert_types::SESSION_STATS => Self::SessionStats {
+                counters: d.counters,
+            },
+            alert_types::DHT_ERROR => Self::DhtError {
+                error: d.error,
+                operation: d.operation,
+            },
+            alert_types::DHT_IMMUTABLE_ITEM => Self::DhtImmutableItem {
+                target: Sha1Hash(d.hash_v1),
+                item: d.buffer,
+            },
+            alert_types::DHT_MUTABLE_ITEM => Self::DhtMutableItem {
+                key: d.hash_v2,
+                salt: d.salt,
+                seq: d.seq,
+                item: d.buffer,
+                authoritative: d.authoritative,
+            },
+            alert_types::DHT_PUT => Self::DhtPut {
+                target: Sha1Hash(d.hash_v1),
+                public_key: d.hash_v2,
+                salt: d.salt,
+                seq: d.seq,
+                num_success: d.num_peers,
+            },
+            alert_types::I2P => Self::I2p { error: d.error },
+            alert_types::DHT_OUTGOING_GET_PEERS => Self::DhtOutgoingGetPeers {
+                info_hash: Sha1Hash(d.hash_v1),
+                endpoint,
+            },
+            alert_types::LOG => Self::Log { message: d.text },
+            alert_types::TORRENT_LOG => Self::TorrentLog { message: d.text },
+            alert_types::PEER_LOG => Self::PeerLog {
+                endpoint,
+                pid,
+                direction: d.direction.into(),
+                event_type: d.path,
+                message: d.text,
+            },
+            alert_types::LSD_ERROR => Self::LsdError { error: d.error },
+            alert_types::DHT_STATS => Self::DhtStats,
+            alert_types::INCOMING_REQUEST => Self::IncomingRequest {
+                endpoint,
+                pid,
+                piece,
+                start: d.start,
+                length: d.length,
+            },
+            alert_types::DHT_LOG => Self::DhtLog {
+                module: d.module,
+                message: d.text,
+            },
+            alert_types::DHT_PKT => Self::DhtPkt {
+                direction: d.direction.into(),
+                endpoint,
+                packet: d.buffer,
+            },
+            alert_types::DHT_GET_PEERS_REPLY => Self::DhtGetPeersReply {
+                info_hash: Sha1Hash(d.hash_v1),
+                peers: d.endpoints.iter().map(socket_addr).collect(),
+            },
+            alert_types::DHT_DIRECT_RESPONSE => Self::DhtDirectResponse {
+                endpoint,
+                response: d.buffer,
+            },
+            alert_types::PICKER_LOG => Self::PickerLog {
+                endpoint,
+                pid,
+                picker_flags: d.reason as u32,
+            },
+            alert_types::SESSION_ERROR => Self::SessionError { error: d.error },
+            alert_types::DHT_LIVE_NODES => Self::DhtLiveNodes {
+                node_id: Sha1Hash(d.hash_v1),
+                nodes: d.endpoints.iter().map(socket_addr).collect(),
+            },
+            alert_types::SESSION_STATS_HEADER => Self::SessionStatsHeader,
+            alert_types::DHT_SAMPLE_INFOHASHES => Self::DhtSampleInfohashes {
+                endpoint,
+                interval: Duration::from_secs(d.interval_s.max(0) as u64),
+                num_infohashes: d.num_peers,
+                samples: d
+                    .buffer
+                    .chunks_exact(20)
+                    .map(|c| Sha1Hash(c.try_into().unwrap()))
+                    .collect(),
+            },
+            alert_types::ALERTS_DROPPED => Self::AlertsDropped,
+            alert_types::SOCKS5 => Self::Socks5 {
+                endpoint,
+                error: d.error,
+                operation: d.operation,
+            },
+            alert_types::FILE_PRIO => Self::FilePrio {
+                error: d.error,
+                operation: d.operation,
+            },
+            _ => Self::Unknown {
+                alert_type: d.alert_type,
+                what: d.what,
+            },
+        }
+    }
+}
@@ -0,0 +1,596 @@
+use crate::{CreateFlags, Error, FileFlags, Sha1Hash};
+use rbtorrent_sys::{
+    ffi::{self, AnnounceEntry, TorrentInfoNode},
+    FileFilter, FileReader, HashProgress,
+};
+use std::{
+    collections::HashSet,
+    fmt, io, panic,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Which info sections a created torrent has
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TorrentFormat {
+    /// Both the v1 and v2 info sections, usable by all clients
+    #[default]
+    Hybrid,
+    V1Only,
+    V2Only,
+}
+
+impl From<TorrentFormat> for CreateFlags {
+    fn from(format: TorrentFormat) -> Self {
+        match format {
+            TorrentFormat::Hybrid => CreateFlags::empty(),
+            TorrentFormat::V1Only => CreateFlags::V1_ONLY,
+            TorrentFormat::V2Only => CreateFlags::V2_ONLY,
+        }
+    }
+}
+
+/// Alignment of the files to piece boundaries, through pad files
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PadFiles {
+    /// No padding in v1 torrents. v2 and hybrid torrents always align the
+    /// files.
+    #[default]
+    Default,
+    /// Sort the files and align each of them, v1 torrents included
+    Aligned,
+    /// Like `Aligned`, without padding after the last file as libtorrent
+    /// did up to 2.0.7
+    AlignedNoTailPadding,
+}
+
+impl From<PadFiles> for CreateFlags {
+    fn from(pad_files: PadFiles) -> Self {
+        match pad_files {
+            PadFiles::Default => CreateFlags::empty(),
+            PadFiles::Aligned => CreateFlags::CANONICAL_FILES,
+            PadFiles::AlignedNoTailPadding => CreateFlags::CANONICAL_FILES_NO_TAIL_PADDING,
+        }
+    }
+}
+
+/// Selects the files added by [`CreateTorrent`]. Called with the path of each
+/// file and directory under [`CreateTorrentContent::Path`], the content of a
+/// directory that is left out is not visited.
+#[derive(Clone)]
+pub struct PathFilter(Arc<dyn Fn(&Path) -> bool + Send + Sync>);
+
+impl PathFilter {
+    pub fn new(f: impl Fn(&Path) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    /// Matches file names against patterns where `*` stands for any
+    /// sequence of characters and `?` for any single one. Files and
+    /// directories matching an `exclude` pattern are left out. If `include`
+    /// is not empty, files also have to match one of its patterns,
+    /// directories are always visited.
+    pub fn globs(include: Vec<String>, exclude: Vec<String>) -> Self {
+        Self::new(move |path| {
+            let name = match path.file_name() {
+                Some(name) => name.to_string_lossy(),
+                None => return true,
+            };
+
+            if exclude.iter().any(|p| glob_match(p, &name)) {
+                return false;
+            }
+
+            include.is_empty() || path.is_dir() || include.iter().any(|p| glob_match(p, &name))
+        })
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        (self.0)(path)
+    }
+}
+
+impl fmt::Debug for PathFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PathFilter").finish_non_exhaustive()
+    }
+}
+
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` and the name position it was tried at
+    let mut star = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                // let the last `*` match one more character
+                Some((sp, sn)) => {
+                    star = Some((sp, sn + 1));
+                    p = sp + 1;
+                    n = sn + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// A file that doesn't need to exist on disk, see [`CreateTorrent::from_files`]
+#[derive(Clone)]
+pub struct VirtualFile {
+    /// Path in the torrent, starting with the name of the torrent. The
+    /// name alone for single file torrents.
+    pub path: PathBuf,
+    pub size: u64,
+    /// Only `HIDDEN` and `EXECUTABLE` are meaningful
+    pub flags: FileFlags,
+    /// Recorded with [`CreateTorrent::modification_times`]
+    pub mtime: Option<SystemTime>,
+    read_at: Arc<ReadAt>,
+}
+
+/// Fills a buffer with the content of a [`VirtualFile`] at an offset
+type ReadAt = dyn Fn(u64, &mut [u8]) -> io::Result<()> + Send + Sync;
+
+impl VirtualFile {
+    /// `read_at` fills the buffer with the content of the file at an offset,
+    /// never past `size`. It's called in any order, from several hashing
+    /// threads at once.
+    pub fn new(
+        path: PathBuf,
+        size: u64,
+        read_at: impl Fn(u64, &mut [u8]) -> io::Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            path,
+            size,
+            flags: FileFlags::empty(),
+            mtime: None,
+            read_at: Arc::new(read_at),
+        }
+    }
+
+    pub fn from_bytes(path: PathBuf, content: Vec<u8>) -> Self {
+        let size = content.len() as u64;
+        Self::new(path, size, move |offset, buf| {
+            let start = usize::try_from(offset).unwrap_or(usize::MAX);
+            match content.get(start..).and_then(|rest| rest.get(..buf.len())) {
+                Some(src) => {
+                    buf.copy_from_slice(src);
+                    Ok(())
+                }
+                None => Err(io::ErrorKind::UnexpectedEof.into()),
+            }
+        })
+    }
+
+    pub fn with_flags(mut self, flags: FileFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn with_mtime(mut self, mtime: SystemTime) -> Self {
+        self.mtime = Some(mtime);
+        self
+    }
+
+    /// Fails if the path leaves the torrent, with `..`, a root or a prefix
+    fn to_ffi(&self) -> Result<ffi::VirtualFileEntry, Error> {
+        // libtorrent splits paths on both separators, normalize them
+        let mut parts = Vec::new();
+        for c in self.path.components() {
+            match c {
+                Component::Normal(c) => parts.push(c.to_string_lossy()),
+                Component::CurDir => {}
+                _ => {
+                    return Err(Error::InvalidArgument(format!(
+                        "invalid virtual file path: {}",
+                        self.path.display()
+                    )))
+                }
+            }
+        }
+        let path = parts.join("/");
+
+        Ok(ffi::VirtualFileEntry {
+            path,
+            size: self.size as i64,
+            flags: self.flags.into(),
+            mtime: self.mtime.map_or(0, unix_secs),
+        })
+    }
+}
+
+impl fmt::Debug for VirtualFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VirtualFile")
+            .field("path", &self.path)
+            .field("size", &self.size)
+            .field("flags", &self.flags)
+            .field("mtime", &self.mtime)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reads the content of virtual files for the hashing threads
+struct FileContents<'a> {
+    files: &'a [VirtualFile],
+    /// First error returned to libtorrent, which only gets its message
+    error: Mutex<Option<Error>>,
+}
+
+impl<'a> FileContents<'a> {
+    fn new(files: &'a [VirtualFile]) -> Self {
+        Self {
+            files,
+            error: Mutex::new(None),
+        }
+    }
+
+    fn read(&self, index: usize, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let res = (self.files[index].read_at)(offset, buf);
+        if let Err(e) = &res {
+            self.error.lock().unwrap().get_or_insert(Error::Io {
+                kind: e.kind(),
+                message: e.to_string(),
+            });
+        }
+        res
+    }
+}
+
+/// Content of a torrent to create
+#[derive(Clone, Debug)]
+pub enum CreateTorrentContent {
+    /// A file or directory on disk. Files are stored under their path
+    /// relative to its parent directory.
+    Path(PathBuf),
+    /// Files read through their [`VirtualFile`] readers, in any order. v2
+    /// torrents sort them by path.
+    Files(Vec<VirtualFile>),
+}
+
+/// Stops a running operation from another thread, the operation fails with
+/// [`Error::Cancelled`]. Clones share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Builder of a .torrent file, over `lt::create_torrent`
+#[derive(Clone, Debug)]
+pub struct CreateTorrent {
+    pub content: CreateTorrentContent,
+    /// Size of the pieces in bytes, a power of two of at least 16 KiB.
+    /// libtorrent picks one from the total size when unset.
+    pub piece_size: Option<u32>,
+    pub format: TorrentFormat,
+    /// Tracker urls and their tier, trackers of lower tiers are tried first
+    pub trackers: Vec<(String, u8)>,
+    /// BEP 19 url seeds
+    pub web_seeds: Vec<String>,
+    /// DHT nodes, as hostname and port
+    pub dht_nodes: Vec<(String, u16)>,
+    pub comment: Option<String>,
+    /// Program the torrent is created with
+    pub creator: Option<String>,
+    /// The current time when unset, the epoch leaves the date out
+    pub creation_date: Option<SystemTime>,
+    /// Private torrents are only shared through their trackers
+    pub private: bool,
+    /// `source` key of the info section. Private trackers use it to give
+    /// cross-seeded torrents distinct info-hashes.
+    pub source: Option<String>,
+    /// Collections the torrent belongs to, see BEP 38
+    pub collections: Vec<String>,
+    /// Torrents sharing files with this one, see BEP 38
+    pub similar_torrents: Vec<Sha1Hash>,
+    /// Files and directories to add, all of them when unset. Only used for
+    /// [`CreateTorrentContent::Path`].
+    pub filter: Option<PathFilter>,
+    /// Add symlinks as symlinks, instead of the files they point to
+    pub symlinks: bool,
+    /// Record the modification time of the files
+    pub modification_times: bool,
+    /// Record the executable and hidden attributes of the files
+    pub attributes: bool,
+    pub pad_files: PadFiles,
+    /// Number of threads reading and hashing the pieces, one when unset. The
+    /// torrent is the same whatever the number.
+    pub hashing_threads: Option<u32>,
+}
+
+impl CreateTorrent {
+    fn with_content(content: CreateTorrentContent) -> Self {
+        Self {
+            content,
+            piece_size: None,
+            format: TorrentFormat::default(),
+            trackers: Vec::new(),
+            web_seeds: Vec::new(),
+            dht_nodes: Vec::new(),
+            comment: None,
+            creator: None,
+            creation_date: None,
+            private: false,
+            source: None,
+            collections: Vec::new(),
+            similar_torrents: Vec::new(),
+            filter: None,
+            symlinks: false,
+            modification_times: false,
+            attributes: true,
+            pad_files: PadFiles::default(),
+            hashing_threads: None,
+        }
+    }
+
+    /// Creates a torrent of a file or directory
+    pub fn new(path: PathBuf) -> Self {
+        Self::with_content(CreateTorrentContent::Path(path))
+    }
+
+    /// Creates a torrent of files that don't need to exist on disk, all
+    /// `files` have to start with the same directory, the name of the
+    /// torrent
+    pub fn from_files(files: Vec<VirtualFile>) -> Self {
+        Self::with_content(CreateTorrentContent::Files(files))
+    }
+
+    pub fn with_piece_size(mut self, piece_size: u32) -> Self {
+        self.piece_size = Some(piece_size);
+        self
+    }
+
+    pub fn with_format(mut self, format: TorrentFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_trackers(mut self, trackers: Vec<(String, u8)>) -> Self {
+        self.trackers = trackers;
+        self
+    }
+
+    pub fn with_web_seeds(mut self, web_seeds: Vec<String>) -> Self {
+        self.web_seeds = web_seeds;
+        self
+    }
+
+    pub fn with_dht_nodes(mut self, dht_nodes: Vec<(String, u16)>) -> Self {
+        self.dht_nodes = dht_nodes;
+        self
+    }
+
+    pub fn with_comment(mut self, comment: String) -> Self {
+        self.comment = Some(comment);
+        self
+    }
+
+    pub fn with_creator(mut self, creator: String) -> Self {
+        self.creator = Some(creator);
+        self
+    }
+
+    pub fn with_creation_date(mut self, creation_date: SystemTime) -> Self {
+        self.creation_date = Some(creation_date);
+        self
+    }
+
+    pub fn with_private(mut self, private: bool) -> Self {
+        self.private = private;
+        self
+    }
+
+    pub fn with_source(mut self, source: String) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn with_collections(mut self, collections: Vec<String>) -> Self {
+        self.collections = collections;
+        self
+    }
+
+    pub fn with_similar_torrents(mut self, similar_torrents: Vec<Sha1Hash>) -> Self {
+        self.similar_torrents = similar_torrents;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: PathFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    pub fn with_symlinks(mut self, symlinks: bool) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    pub fn with_modification_times(mut self, modification_times: bool) -> Self {
+        self.modification_times = modification_times;
+        self
+    }
+
+    pub fn with_attributes(mut self, attributes: bool) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    pub fn with_pad_files(mut self, pad_files: PadFiles) -> Self {
+        self.pad_files = pad_files;
+        self
+    }
+
+    pub fn with_hashing_threads(mut self, hashing_threads: u32) -> Self {
+        self.hashing_threads = Some(hashing_threads);
+        self
+    }
+
+    /// Hashes the files and returns the bencoded .torrent file
+    pub fn generate(&self) -> Result<Vec<u8>, Error> {
+        self.generate_with_progress(|_, _| {}, &CancelToken::new())
+    }
+
+    /// Like [`generate`](Self::generate), calling `progress` with the number
+    /// of pieces hashed and the total after each piece. Hashing stops at the
+    /// next piece once `cancel` is cancelled. A panic of `progress` or of a
+    /// virtual file reader stops the hashing and is resumed on this thread.
+    pub fn generate_with_progress(
+        &self,
+        mut progress: impl FnMut(u32, u32),
+        cancel: &CancelToken,
+    ) -> Result<Vec<u8>, Error> {
+        if cancel.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+
+        let params = self.to_ffi();
+        let mut progress = HashProgress::new(|done, total| {
+            progress(done as u32, total as u32);
+            !cancel.is_cancelled()
+        });
+
+        let (res, panic) = match &self.content {
+            CreateTorrentContent::Path(_) => {
+                let mut filter = FileFilter::new(|path| {
+                    !cancel.is_cancelled()
+                        && match &self.filter {
+                            Some(filter) => filter.matches(Path::new(&path)),
+                            None => true,
+                        }
+                });
+                let res = ffi::create_torrent_generate(&params, &mut filter, &mut progress)
+                    .map_err(Error::from);
+                (res, filter.take_panic())
+            }
+            CreateTorrentContent::Files(files) => {
+                let entries = files
+                    .iter()
+                    .map(VirtualFile::to_ffi)
+                    .collect::<Result<Vec<_>, _>>()?;
+                // the reader is called with the index of the file of a path
+                let mut paths = HashSet::new();
+                if let Some(dup) = entries.iter().find(|e| !paths.insert(&e.path)) {
+                    return Err(Error::InvalidArgument(format!(
+                        "duplicate virtual file path: {}",
+                        dup.path
+                    )));
+                }
+                let contents = FileContents::new(files);
+                let mut reader =
+                    FileReader::new(|index, offset, buf| contents.read(index, offset, buf));
+
+                let res = ffi::create_torrent_generate_from_files(
+                    &params,
+                    &entries,
+                    &reader,
+                    &mut progress,
+                );
+                let panic = reader.take_panic();
+                drop(reader);
+                let error = contents.error.into_inner().unwrap();
+                let res = res.map_err(|e| error.unwrap_or_else(|| e.into()));
+                (res, panic)
+            }
+        };
+
+        // the callbacks stop libtorrent when they panic, the panic carries on
+        // once it returned
+        if let Some(payload) = panic.or_else(|| progress.take_panic()) {
+            panic::resume_unwind(payload);
+        }
+
+        match res {
+            Err(_) if cancel.is_cancelled() => Err(Error::Cancelled),
+            res => res,
+        }
+    }
+
+    fn flags(&self) -> CreateFlags {
+        let mut flags = CreateFlags::from(self.format) | CreateFlags::from(self.pad_files);
+        flags.set(CreateFlags::SYMLINKS, self.symlinks);
+        flags.set(CreateFlags::MODIFICATION_TIME, self.modification_times);
+        flags.set(CreateFlags::NO_ATTRIBUTES, !self.attributes);
+        flags
+    }
+
+    fn to_ffi(&self) -> ffi::CreateTorrentParams {
+        let path = match &self.content {
+            CreateTorrentContent::Path(path) => path.to_string_lossy().into_owned(),
+            CreateTorrentContent::Files(_) => String::new(),
+        };
+
+        ffi::CreateTorrentParams {
+            path,
+            piece_size: self.piece_size.unwrap_or_default() as i32,
+            flags: self.flags().into(),
+            trackers: self
+                .trackers
+                .iter()
+                .map(|(url, tier)| AnnounceEntry {
+                    url: url.clone(),
+                    trackerid: String::new(),
+                    tier: *tier,
+                })
+                .collect(),
+            web_seeds: self.web_seeds.clone(),
+            nodes: self
+                .dht_nodes
+                .iter()
+                .map(|(hostname, port)| TorrentInfoNode {
+                    hostname: hostname.clone(),
+                    port: *port,
+                })
+                .collect(),
+            comment: self.comment.clone().unwrap_or_default(),
+            creator: self.creator.clone().unwrap_or_default(),
+            creation_date: unix_secs(self.creation_date.unwrap_or_else(SystemTime::now)),
+            is_private: self.private,
+            source: self.source.clone().unwrap_or_default(),
+            collections: self.collections.clone(),
+            similar_torrents: self
+                .similar_torrents
+                .iter()
+                .flat_map(|hash| hash.0)
+                .collect(),
+            hashing_threads: self.hashing_threads.unwrap_or_default() as i32,
+        }
+    }
+}
+
+/// Seconds since the epoch, 0 for earlier times
+fn unix_secs(t: SystemTime) -> i64 {
+    t.duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
@@ -0,0 +1,104 @@
+use crate::ParseMagnetError;
+use rbtorrent_sys::ffi::ErrorCode;
+use std::{fmt, io};
+
+/// Errors returned by the session and by libtorrent itself
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// libtorrent reported an `error_code`
+    Libtorrent(ErrorCode),
+    /// A C++ exception that did not carry an `error_code`
+    Exception(String),
+    /// The session thread has exited and no longer accepts commands
+    SessionClosed,
+    /// An I/O error, e.g. while accessing a resume data store
+    Io {
+        kind: io::ErrorKind,
+        message: String,
+    },
+    /// A magnet link could not be parsed
+    InvalidMagnetUri(ParseMagnetError),
+    /// The operation was stopped through a [`CancelToken`](crate::CancelToken)
+    Cancelled,
+    /// An argument was rejected before it was passed to libtorrent
+    InvalidArgument(String),
+}
+
+impl Error {
+    /// The underlying libtorrent `error_code`, if any
+    pub fn code(&self) -> Option<&ErrorCode> {
+        match self {
+            Self::Libtorrent(ec) => Some(ec),
+            _ => None,
+        }
+    }
+
+    /// Name of the `error_category` the error belongs to, if any
+    pub fn category(&self) -> Option<&str> {
+        self.code().map(|ec| ec.category.as_str())
+    }
+
+    /// Numeric value of the error within its category, if any
+    pub fn value(&self) -> Option<i32> {
+        self.code().map(|ec| ec.value)
+    }
+
+    /// Human readable description of the error
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Libtorrent(ec) => &ec.message,
+            Self::Exception(msg) => msg,
+            Self::SessionClosed => "session closed",
+            Self::Io { message, .. } => message,
+            Self::InvalidMagnetUri(_) => "invalid magnet uri",
+            Self::Cancelled => "cancelled",
+            Self::InvalidArgument(msg) => msg,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Libtorrent(ec) => write!(f, "{ec}"),
+            Self::Exception(msg) => write!(f, "{msg}"),
+            Self::SessionClosed => write!(f, "session closed"),
+            Self::Io { message, .. } => write!(f, "{message}"),
+            Self::InvalidMagnetUri(e) => write!(f, "{e}"),
+            Self::Cancelled => write!(f, "cancelled"),
+            Self::InvalidArgument(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<cxx::Exception> for Error {
+    fn from(e: cxx::Exception) -> Self {
+        match ErrorCode::from_exception(&e) {
+            Some(ec) => Self::Libtorrent(ec),
+            None => Self::Exception(e.what().to_string()),
+        }
+    }
+}
+
+impl From<ErrorCode> for Error {
+    fn from(ec: ErrorCode) -> Self {
+        Self::Libtorrent(ec)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io {
+            kind: e.kind(),
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<ParseMagnetError> for Error {
+    fn from(e: ParseMagnetError) -> Self {
+        Self::InvalidMagnetUri(e)
+    }
+}
@@ -0,0 +1,334 @@
+use rbtorrent_sys::ffi;
+use std::{fmt, str::FromStr};
+
+/// SHA-1 hash, mirroring `lt::sha1_hash`. Used as v1 info-hash and DHT id.
+///
+/// Displayed as lowercase hex, parsed from hex or base32 as found in `btih`
+/// magnet links.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Sha1Hash(pub [u8; 20]);
+
+/// SHA-256 hash, mirroring `lt::sha256_hash`. Used as v2 info-hash and file
+/// merkle root.
+///
+/// Displayed as lowercase hex, parsed from hex, base32 or a hex encoded
+/// SHA-256 multihash as found in `btmh` magnet links.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Sha256Hash(pub [u8; 32]);
+
+/// Info-hashes of a torrent, mirroring `lt::info_hash_t`. v1 torrents only
+/// have a SHA-1 hash, v2 torrents only a SHA-256 hash, hybrid torrents both.
+///
+/// Displayed as the `btih` hex hash, the `btmh` multihash, or both separated
+/// by a comma for hybrid torrents. Parsed from the same formats, `btih`
+/// hashes may also be in base32.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InfoHash {
+    pub v1: Option<Sha1Hash>,
+    pub v2: Option<Sha256Hash>,
+}
+
+/// Error parsing a hash from a string
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseHashError {
+    /// The length matches neither the hex nor the base32 encoding of the hash
+    InvalidLength(usize),
+    InvalidCharacter(char),
+    /// A multihash of a function other than SHA-256
+    UnsupportedMultihash,
+    /// An info-hash with two hashes of the same version
+    DuplicateHash,
+}
+
+impl fmt::Display for ParseHashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength(len) => write!(f, "invalid hash length {len}"),
+            Self::InvalidCharacter(c) => write!(f, "invalid character {c:?} in hash"),
+            Self::UnsupportedMultihash => write!(f, "unsupported multihash"),
+            Self::DuplicateHash => write!(f, "duplicate hash in info-hash"),
+        }
+    }
+}
+
+impl std::error::Error for ParseHashError {}
+
+/// Prefix of SHA-256 multihashes: the function code and the digest length
+const SHA256_MULTIHASH_PREFIX: &str = "1220";
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+macro_rules! hash_impl {
+    ($name:ident, $len:literal) => {
+        impl $name {
+            pub fn as_bytes(&self) -> &[u8; $len] {
+                &self.0
+            }
+
+            /// Unpadded uppercase base32 encoding
+            pub fn to_base32(&self) -> String {
+                base32_encode(&self.0)
+            }
+        }
+
+        impl From<[u8; $len]> for $name {
+            fn from(bytes: [u8; $len]) -> Self {
+                Self(bytes)
+            }
+        }
+
+        impl From<$name> for [u8; $len] {
+            fn from(hash: $name) -> Self {
+                hash.0
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                for b in &self.0 {
+                    write!(f, "{b:02x}")?;
+                }
+                Ok(())
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, concat!(stringify!($name), "({})"), self)
+            }
+        }
+    };
+}
+
+hash_impl!(Sha1Hash, 20);
+hash_impl!(Sha256Hash, 32);
+
+impl FromStr for Sha1Hash {
+    type Err = ParseHashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        decode(s).map(Self)
+    }
+}
+
+impl FromStr for Sha256Hash {
+    type Err = ParseHashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() == SHA256_MULTIHASH_PREFIX.len() + 64 {
+            return match s.strip_prefix(SHA256_MULTIHASH_PREFIX) {
+                Some(hex) => decode(hex).map(Self),
+                None => Err(ParseHashError::UnsupportedMultihash),
+            };
+        }
+
+        decode(s).map(Self)
+    }
+}
+
+impl InfoHash {
+    pub fn v1(hash: Sha1Hash) -> Self {
+        Self {
+            v1: Some(hash),
+            v2: None,
+        }
+    }
+
+    pub fn v2(hash: Sha256Hash) -> Self {
+        Self {
+            v1: None,
+            v2: Some(hash),
+        }
+    }
+
+    pub fn hybrid(v1: Sha1Hash, v2: Sha256Hash) -> Self {
+        Self {
+            v1: Some(v1),
+            v2: Some(v2),
+        }
+    }
+
+    pub fn has_v1(&self) -> bool {
+        self.v1.is_some()
+    }
+
+    pub fn has_v2(&self) -> bool {
+        self.v2.is_some()
+    }
+
+    pub fn is_hybrid(&self) -> bool {
+        self.has_v1() && self.has_v2()
+    }
+
+    /// Whether neither hash is known, as for handles of removed torrents
+    pub fn is_empty(&self) -> bool {
+        !self.has_v1() && !self.has_v2()
+    }
+}
+
+impl fmt::Display for InfoHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(v1) = &self.v1 {
+            write!(f, "{v1}")?;
+        }
+        if self.is_hybrid() {
+            write!(f, ",")?;
+        }
+        if let Some(v2) = &self.v2 {
+            write!(f, "{SHA256_MULTIHASH_PREFIX}{v2}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for InfoHash {
+    type Err = ParseHashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut hash = Self::default();
+        // the empty hash is displayed as an empty string
+        if s.is_empty() {
+            return Ok(hash);
+        }
+
+        for part in s.split(',') {
+            // SHA-1 hashes are 40 characters in hex, 32 in base32
+            let replaced = if part.len() == 40 || part.len() == 32 {
+                hash.v1.replace(part.parse()?).is_some()
+            } else {
+                hash.v2.replace(part.parse()?).is_some()
+            };
+            if replaced {
+                return Err(ParseHashError::DuplicateHash);
+            }
+        }
+
+        Ok(hash)
+    }
+}
+
+impl From<Sha1Hash> for InfoHash {
+    fn from(hash: Sha1Hash) -> Self {
+        Self::v1(hash)
+    }
+}
+
+impl From<Sha256Hash> for InfoHash {
+    fn from(hash: Sha256Hash) -> Self {
+        Self::v2(hash)
+    }
+}
+
+impl From<ffi::InfoHashes> for InfoHash {
+    fn from(ih: ffi::InfoHashes) -> Self {
+        Self {
+            v1: non_zero(ih.v1).map(Sha1Hash),
+            v2: non_zero(ih.v2).map(Sha256Hash),
+        }
+    }
+}
+
+impl From<InfoHash> for ffi::InfoHashes {
+    fn from(ih: InfoHash) -> Self {
+        Self {
+            v1: ih.v1.unwrap_or_default().0,
+            v2: ih.v2.unwrap_or_default().0,
+        }
+    }
+}
+
+/// libtorrent uses all zeros for hashes that are not set
+pub(crate) fn non_zero<const N: usize>(hash: [u8; N]) -> Option<[u8; N]> {
+    if hash.iter().all(|&b| b == 0) {
+        None
+    } else {
+        Some(hash)
+    }
+}
+
+/// Decodes hex or unpadded base32, depending on the length of `s`
+fn decode<const N: usize>(s: &str) -> Result<[u8; N], ParseHashError> {
+    let mut out = [0; N];
+
+    if s.len() == N * 2 {
+        for (i, c) in s.chars().enumerate() {
+            let v = c.to_digit(16).ok_or(ParseHashError::InvalidCharacter(c))?;
+            out[i / 2] |= (v as u8) << if i % 2 == 0 { 4 } else { 0 };
+        }
+    } else if s.len() == (N * 8).div_ceil(5) {
+        let mut acc = 0u32;
+        let mut bits = 0;
+        let mut i = 0;
+        for c in s.chars() {
+            let v = BASE32_ALPHABET
+                .iter()
+                .position(|&a| a as char == c.to_ascii_uppercase())
+                .ok_or(ParseHashError::InvalidCharacter(c))?;
+            acc = (acc << 5) | v as u32;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                out[i] = (acc >> bits) as u8;
+                i += 1;
+            }
+        }
+    } else {
+        return Err(ParseHashError::InvalidLength(s.len()));
+    }
+
+    Ok(out)
+}
+
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let mut acc = 0u32;
+    let mut bits = 0;
+
+    for &b in bytes {
+        acc = (acc << 8) | b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[(acc >> bits) as usize & 31] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[(acc << (5 - bits)) as usize & 31] as char);
+    }
+
+    out
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use super::{InfoHash, Sha1Hash, Sha256Hash};
+    use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+    use std::borrow::Cow;
+
+    macro_rules! serde_as_str {
+        ($name:ident) => {
+            impl Serialize for $name {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.collect_str(self)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $name {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    let s = Cow::<str>::deserialize(deserializer)?;
+                    s.parse().map_err(D::Error::custom)
+                }
+            }
+        };
+    }
+
+    serde_as_str!(Sha1Hash);
+    serde_as_str!(Sha256Hash);
+    serde_as_str!(InfoHash);
+}
@@ -0,0 +1,194 @@
+use crate::{
+    status::unix_time, Error, FileStorage, InfoHash, LoadTorrentLimits, MagnetUri, Sha1Hash,
+    WriteTorrentFlags,
+};
+use cxx::UniquePtr;
+pub use rbtorrent_sys::ffi::WebSeedKind;
+use rbtorrent_sys::ffi::{self, torrent_info, AnnounceEntry};
+use std::{
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+/// Metadata of a torrent, mirroring `lt::torrent_info`
+pub struct TorrentInfo {
+    pub(crate) inner: UniquePtr<torrent_info>,
+}
+
+impl TorrentInfo {
+    pub fn open(path: &str) -> Result<Self, Error> {
+        Self::open_with_limits(path, &LoadTorrentLimits::default())
+    }
+
+    pub fn open_with_limits(path: &str, limits: &LoadTorrentLimits) -> Result<Self, Error> {
+        Ok(Self {
+            inner: ffi::open_torrent_info(path, limits)?,
+        })
+    }
+
+    /// Parses the content of a .torrent file
+    pub fn from_bytes(torrent: &[u8]) -> Result<Self, Error> {
+        Self::from_bytes_with_limits(torrent, &LoadTorrentLimits::default())
+    }
+
+    /// Parses the content of a .torrent file, failing if it exceeds
+    /// `limits`. Use this for torrents from untrusted sources.
+    pub fn from_bytes_with_limits(
+        torrent: &[u8],
+        limits: &LoadTorrentLimits,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            inner: ffi::new_torrent_info_from_buffer(torrent, limits)?,
+        })
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (String, u16)> {
+        ffi::torrent_info_nodes(&self.inner)
+            .into_iter()
+            .map(|inner| (inner.hostname, inner.port))
+    }
+
+    pub fn trackers(&self) -> Vec<AnnounceEntry> {
+        ffi::torrent_info_trackers(&self.inner)
+    }
+
+    /// Name of the torrent, the name of the file or top level directory
+    pub fn name(&self) -> String {
+        ffi::torrent_info_name(&self.inner)
+    }
+
+    pub fn comment(&self) -> Option<String> {
+        Some(ffi::torrent_info_comment(&self.inner)).filter(|v| !v.is_empty())
+    }
+
+    /// Program the torrent was created with
+    pub fn creator(&self) -> Option<String> {
+        Some(ffi::torrent_info_creator(&self.inner)).filter(|v| !v.is_empty())
+    }
+
+    pub fn creation_date(&self) -> Option<SystemTime> {
+        unix_time(
+            Duration::from_secs,
+            ffi::torrent_info_creation_date(&self.inner),
+        )
+    }
+
+    /// Size of the torrent in bytes, including pad files
+    pub fn total_size(&self) -> u64 {
+        ffi::torrent_info_total_size(&self.inner) as u64
+    }
+
+    /// Size of every piece in bytes, except the last one which may be
+    /// smaller
+    pub fn piece_length(&self) -> u32 {
+        ffi::torrent_info_piece_length(&self.inner) as u32
+    }
+
+    pub fn num_pieces(&self) -> u32 {
+        ffi::torrent_info_num_pieces(&self.inner) as u32
+    }
+
+    /// Whether peers may only be found through the trackers of the torrent,
+    /// not through the DHT, local service discovery or peer exchange
+    pub fn is_private(&self) -> bool {
+        ffi::torrent_info_priv(&self.inner)
+    }
+
+    /// Whether the torrent is only available on the i2p network
+    pub fn is_i2p(&self) -> bool {
+        ffi::torrent_info_is_i2p(&self.inner)
+    }
+
+    pub fn info_hashes(&self) -> InfoHash {
+        ffi::torrent_info_info_hashes(&self.inner).into()
+    }
+
+    pub fn web_seeds(&self) -> Vec<WebSeed> {
+        ffi::torrent_info_web_seeds(&self.inner)
+            .into_iter()
+            .map(WebSeed::from)
+            .collect()
+    }
+
+    /// Info-hashes of torrents the content of this torrent may be shared
+    /// with, see BEP 38
+    pub fn similar_torrents(&self) -> Vec<Sha1Hash> {
+        ffi::torrent_info_similar_torrents(&self.inner)
+            .chunks_exact(20)
+            .map(|hash| Sha1Hash(hash.try_into().unwrap()))
+            .collect()
+    }
+
+    /// Names of the collections the torrent belongs to, see BEP 38
+    pub fn collections(&self) -> Vec<String> {
+        ffi::torrent_info_collections(&self.inner)
+    }
+
+    /// PEM encoded root certificate of SSL torrents
+    pub fn ssl_cert(&self) -> Option<String> {
+        Some(ffi::torrent_info_ssl_cert(&self.inner)).filter(|v| !v.is_empty())
+    }
+
+    /// Files of the torrent
+    pub fn files(&self) -> FileStorage<'_> {
+        FileStorage::new(ffi::torrent_info_files(&self.inner))
+    }
+
+    /// Bencoded info dictionary, the info-hashes are computed from it
+    pub fn info_section(&self) -> Vec<u8> {
+        ffi::torrent_info_info_section(&self.inner)
+    }
+
+    /// Generates a magnet link of the torrent, with its name, trackers and web
+    /// seeds
+    pub fn to_magnet(&self) -> Result<MagnetUri, Error> {
+        Ok(ffi::torrent_info_make_magnet_uri(&self.inner).parse()?)
+    }
+
+    /// Generates the .torrent file of the torrent.
+    ///
+    /// v2 torrents whose metadata was received from peers lack the piece
+    /// layers, which are only downloaded along with the content. Writing them
+    /// fails unless [`WriteTorrentFlags::ALLOW_MISSING_PIECE_LAYER`] is set.
+    pub fn to_bencoded_torrent(&self, flags: WriteTorrentFlags) -> Result<Vec<u8>, Error> {
+        Ok(ffi::torrent_info_write_torrent_file(
+            &self.inner,
+            flags.bits(),
+        )?)
+    }
+}
+
+impl fmt::Debug for TorrentInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TorrentInfo")
+            .field("name", &self.name())
+            .field("info_hashes", &self.info_hashes())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Web seed of a torrent, mirroring `lt::web_seed_entry`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WebSeed {
+    pub url: String,
+    pub kind: WebSeedKind,
+    /// Credentials sent to the web seed
+    pub auth: Option<String>,
+    /// Headers sent along with every request, as name and value
+    pub extra_headers: Vec<(String, String)>,
+}
+
+impl From<ffi::WebSeedEntry> for WebSeed {
+    fn from(e: ffi::WebSeedEntry) -> Self {
+        Self {
+            url: e.url,
+            kind: e.kind,
+            auth: Some(e.auth).filter(|v| !v.is_empty()),
+            extra_headers: e
+                .extra_headers
+                .into_iter()
+                .map(|h| (h.name, h.value))
+                .collect(),
+        }
+    }
+}
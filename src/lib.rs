@@ -1,14 +1,44 @@
 #![doc = include_str!("../README.md")]
 
+mod alert;
+mod create;
+mod error;
+mod hash;
+mod info;
+mod magnet;
+mod resume;
+mod settings;
+mod status;
+mod storage;
+
+pub use crate::{
+    alert::*, create::*, error::Error, hash::*, info::*, magnet::*, resume::*, settings::*,
+    status::*, storage::*,
+};
 use bitflags::bitflags;
 use cxx::UniquePtr;
-pub use rbtorrent_sys::ffi::{torrent_info, AnnounceEntry, TorrentStatus};
+use futures::{
+    channel::{
+        mpsc::{unbounded, UnboundedSender},
+        oneshot,
+    },
+    executor::block_on,
+    Future,
+};
+pub use rbtorrent_sys::ffi::{torrent_info, AnnounceEntry, ErrorCode, LoadTorrentLimits};
 pub use rbtorrent_sys::*;
 use std::{
-    collections::HashMap,
-    marker::PhantomData,
+    collections::BTreeMap,
+    fmt,
+    hash::{Hash, Hasher},
+    net::SocketAddr,
     path::PathBuf,
-    sync::mpsc::{channel, Sender},
+    pin::Pin,
+    sync::{
+        mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+        Arc, Weak,
+    },
+    thread::JoinHandle,
     time::Duration,
 };
 
@@ -16,6 +46,32 @@ use std::{
 pub enum AddTorrentParamsSource {
     Magnet(String),
     Torrent(PathBuf),
+    /// Content of a .torrent file
+    TorrentBytes(Vec<u8>),
+    /// Bencoded resume data, as returned by [`SaveResumeDataAlert::resume_data`]
+    ResumeData(Vec<u8>),
+}
+
+/// Priority of a file or piece, mirroring `lt::download_priority_t`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DownloadPriority(pub u8);
+
+impl DownloadPriority {
+    /// Don't download the file or piece. Partial pieces may still be
+    /// downloaded when setting file priorities.
+    pub const DONT_DOWNLOAD: Self = Self(0);
+    /// The lowest priority for files and pieces.
+    pub const LOW: Self = Self(1);
+    /// The default priority for files and pieces.
+    pub const DEFAULT: Self = Self(4);
+    /// The highest priority for files and pieces.
+    pub const TOP: Self = Self(7);
+}
+
+impl Default for DownloadPriority {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
 }
 
 #[derive(Debug)]
@@ -23,149 +79,578 @@ pub struct AddTorrentParams {
     pub source: AddTorrentParamsSource,
     pub save_path: Option<PathBuf>,
     pub trackers: Option<Vec<String>>,
+    /// Replaces libtorrent's default flags when set, see [`TorrentFlags::DEFAULT_FLAGS`]
     pub torrent_flags: Option<TorrentFlags>,
+    /// Name of the torrent, shown until the metadata has been received
+    pub name: Option<String>,
+    pub file_priorities: Option<Vec<DownloadPriority>>,
+    pub piece_priorities: Option<Vec<DownloadPriority>>,
+    pub url_seeds: Option<Vec<String>>,
+    /// DHT nodes to add to the routing table, as hostname and port
+    pub dht_nodes: Option<Vec<(String, u16)>>,
+    /// Peers to connect to once the torrent is started
+    pub peers: Option<Vec<SocketAddr>>,
+    /// Peers that are never connected to
+    pub banned_peers: Option<Vec<SocketAddr>>,
+    pub max_connections: Option<i32>,
+    pub max_uploads: Option<i32>,
+    /// Upload rate limit in bytes per second, -1 means unlimited
+    pub upload_limit: Option<i32>,
+    /// Download rate limit in bytes per second, -1 means unlimited
+    pub download_limit: Option<i32>,
+    pub storage_mode: Option<ffi::storage_mode_t>,
+    /// File index to new path, relative to the save path
+    pub renamed_files: Option<BTreeMap<u32, PathBuf>>,
+    /// Limits applied when loading a .torrent file, libtorrent's defaults
+    /// when unset
+    pub load_limits: Option<LoadTorrentLimits>,
 }
 
 impl AddTorrentParams {
-    pub fn from_magnet_uri(magnet_uri: String) -> Self {
+    fn new(source: AddTorrentParamsSource) -> Self {
         Self {
-            source: AddTorrentParamsSource::Magnet(magnet_uri),
+            source,
             save_path: None,
             trackers: None,
             torrent_flags: None,
+            name: None,
+            file_priorities: None,
+            piece_priorities: None,
+            url_seeds: None,
+            dht_nodes: None,
+            peers: None,
+            banned_peers: None,
+            max_connections: None,
+            max_uploads: None,
+            upload_limit: None,
+            download_limit: None,
+            storage_mode: None,
+            renamed_files: None,
+            load_limits: None,
+        }
+    }
+
+    pub fn from_magnet_uri(magnet_uri: String) -> Self {
+        Self::new(AddTorrentParamsSource::Magnet(magnet_uri))
+    }
+
+    pub fn from_magnet(magnet: &MagnetUri) -> Self {
+        Self::from_magnet_uri(magnet.to_string())
+    }
+
+    pub fn from_torrent_file(path: PathBuf) -> Self {
+        Self::new(AddTorrentParamsSource::Torrent(path))
+    }
+
+    /// Adds a torrent from the content of a .torrent file, e.g. downloaded
+    /// over HTTP
+    pub fn from_torrent_bytes(torrent: Vec<u8>) -> Self {
+        Self::new(AddTorrentParamsSource::TorrentBytes(torrent))
+    }
+
+    /// Restores a torrent from resume data. The other fields, when set,
+    /// override what was saved in the resume data.
+    pub fn from_resume_data(resume_data: Vec<u8>) -> Self {
+        Self::new(AddTorrentParamsSource::ResumeData(resume_data))
+    }
+
+    pub fn with_save_path(mut self, save_path: PathBuf) -> Self {
+        self.save_path = Some(save_path);
+        self
+    }
+
+    pub fn with_trackers(mut self, trackers: Vec<String>) -> Self {
+        self.trackers = Some(trackers);
+        self
+    }
+
+    pub fn with_torrent_flags(mut self, torrent_flags: TorrentFlags) -> Self {
+        self.torrent_flags = Some(torrent_flags);
+        self
+    }
+
+    pub fn with_name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    pub fn with_file_priorities(mut self, file_priorities: Vec<DownloadPriority>) -> Self {
+        self.file_priorities = Some(file_priorities);
+        self
+    }
+
+    pub fn with_piece_priorities(mut self, piece_priorities: Vec<DownloadPriority>) -> Self {
+        self.piece_priorities = Some(piece_priorities);
+        self
+    }
+
+    pub fn with_url_seeds(mut self, url_seeds: Vec<String>) -> Self {
+        self.url_seeds = Some(url_seeds);
+        self
+    }
+
+    pub fn with_dht_nodes(mut self, dht_nodes: Vec<(String, u16)>) -> Self {
+        self.dht_nodes = Some(dht_nodes);
+        self
+    }
+
+    pub fn with_peers(mut self, peers: Vec<SocketAddr>) -> Self {
+        self.peers = Some(peers);
+        self
+    }
+
+    pub fn with_banned_peers(mut self, banned_peers: Vec<SocketAddr>) -> Self {
+        self.banned_peers = Some(banned_peers);
+        self
+    }
+
+    pub fn with_max_connections(mut self, max_connections: i32) -> Self {
+        self.max_connections = Some(max_connections);
+        self
+    }
+
+    pub fn with_max_uploads(mut self, max_uploads: i32) -> Self {
+        self.max_uploads = Some(max_uploads);
+        self
+    }
+
+    pub fn with_upload_limit(mut self, upload_limit: i32) -> Self {
+        self.upload_limit = Some(upload_limit);
+        self
+    }
+
+    pub fn with_download_limit(mut self, download_limit: i32) -> Self {
+        self.download_limit = Some(download_limit);
+        self
+    }
+
+    pub fn with_storage_mode(mut self, storage_mode: ffi::storage_mode_t) -> Self {
+        self.storage_mode = Some(storage_mode);
+        self
+    }
+
+    pub fn with_renamed_files(mut self, renamed_files: BTreeMap<u32, PathBuf>) -> Self {
+        self.renamed_files = Some(renamed_files);
+        self
+    }
+
+    pub fn with_load_limits(mut self, load_limits: LoadTorrentLimits) -> Self {
+        self.load_limits = Some(load_limits);
+        self
+    }
+
+    /// Encodes the params as resume data, the way libtorrent will see them
+    /// when the torrent is added
+    pub fn write_resume_data(self) -> Result<Vec<u8>, Error> {
+        let p = self.into_ffi()?;
+        Ok(ffi::add_torrent_params_write_resume_data(&p)?)
+    }
+
+    fn into_ffi(self) -> Result<UniquePtr<ffi::add_torrent_params>, Error> {
+        let limits = self.load_limits.unwrap_or_default();
+
+        let mut p = match self.source {
+            AddTorrentParamsSource::Magnet(magnet) => {
+                ffi::new_add_torrent_params_from_magnet_uri(&magnet)?
+            }
+            AddTorrentParamsSource::Torrent(path) => {
+                let path = path.to_string_lossy();
+                ffi::new_add_torrent_params_from_torrent_file(&path, &limits)?
+            }
+            AddTorrentParamsSource::TorrentBytes(buf) => {
+                ffi::new_add_torrent_params_from_torrent_buffer(&buf, &limits)?
+            }
+            AddTorrentParamsSource::ResumeData(buf) => {
+                ffi::new_add_torrent_params_from_resume_data(&buf)?
+            }
+        };
+
+        if let Some(v) = self.save_path {
+            let v = v.to_string_lossy();
+            ffi::add_torrent_params_set_save_path(p.pin_mut(), &v);
+        }
+
+        if let Some(v) = self.trackers {
+            ffi::add_torrent_params_set_trackers(
+                p.pin_mut(),
+                &v.iter().map(|v| v.as_str()).collect::<Vec<_>>(),
+            );
+        }
+
+        if let Some(v) = self.torrent_flags {
+            ffi::add_torrent_params_set_torrent_flags(p.pin_mut(), v.bits());
+        }
+
+        if let Some(v) = self.name {
+            ffi::add_torrent_params_set_name(p.pin_mut(), &v);
+        }
+
+        if let Some(v) = self.file_priorities {
+            ffi::add_torrent_params_set_file_priorities(
+                p.pin_mut(),
+                &v.iter().map(|v| v.0).collect::<Vec<_>>(),
+            );
+        }
+
+        if let Some(v) = self.piece_priorities {
+            ffi::add_torrent_params_set_piece_priorities(
+                p.pin_mut(),
+                &v.iter().map(|v| v.0).collect::<Vec<_>>(),
+            );
+        }
+
+        if let Some(v) = self.url_seeds {
+            ffi::add_torrent_params_set_url_seeds(
+                p.pin_mut(),
+                &v.iter().map(|v| v.as_str()).collect::<Vec<_>>(),
+            );
+        }
+
+        if let Some(v) = self.dht_nodes {
+            ffi::add_torrent_params_set_dht_nodes(
+                p.pin_mut(),
+                &v.into_iter()
+                    .map(|(hostname, port)| ffi::TorrentInfoNode { hostname, port })
+                    .collect::<Vec<_>>(),
+            );
         }
+
+        if let Some(v) = self.peers {
+            ffi::add_torrent_params_set_peers(
+                p.pin_mut(),
+                &v.iter().map(ffi::Endpoint::from).collect::<Vec<_>>(),
+            )?;
+        }
+
+        if let Some(v) = self.banned_peers {
+            ffi::add_torrent_params_set_banned_peers(
+                p.pin_mut(),
+                &v.iter().map(ffi::Endpoint::from).collect::<Vec<_>>(),
+            )?;
+        }
+
+        if let Some(v) = self.max_connections {
+            ffi::add_torrent_params_set_max_connections(p.pin_mut(), v);
+        }
+
+        if let Some(v) = self.max_uploads {
+            ffi::add_torrent_params_set_max_uploads(p.pin_mut(), v);
+        }
+
+        if let Some(v) = self.upload_limit {
+            ffi::add_torrent_params_set_upload_limit(p.pin_mut(), v);
+        }
+
+        if let Some(v) = self.download_limit {
+            ffi::add_torrent_params_set_download_limit(p.pin_mut(), v);
+        }
+
+        if let Some(v) = self.storage_mode {
+            ffi::add_torrent_params_set_storage_mode(p.pin_mut(), v);
+        }
+
+        if let Some(v) = self.renamed_files {
+            let renamed_files = v
+                .into_iter()
+                .map(|(index, path)| {
+                    let index = i32::try_from(index).map_err(|_| {
+                        Error::InvalidArgument(format!("file index {index} out of range"))
+                    })?;
+                    Ok(ffi::RenamedFile {
+                        index,
+                        path: path.to_string_lossy().into_owned(),
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            ffi::add_torrent_params_set_renamed_files(p.pin_mut(), &renamed_files);
+        }
+
+        Ok(p)
     }
 }
 
-#[derive(Debug)]
+/// Handle to a torrent in a session.
+///
+/// The handle owns its own copy of the underlying `lt::torrent_handle` and may
+/// be cloned and shared between threads freely. Handles compare and hash by
+/// the v1 info-hash of the torrent they refer to, or its v2 info-hash for
+/// v2-only torrents, so a handle taken before the metadata of a v1 magnet link
+/// was received still matches one taken after.
+///
+/// Queries block the calling thread until libtorrent's network thread has
+/// answered. With the `async` feature, their `_async` variants are run on the
+/// session thread instead.
 pub struct TorrentHandle {
-    cmd_tx: Sender<SessionCommand>,
-    i: usize,
+    inner: UniquePtr<ffi::torrent_handle>,
+    info_hashes: InfoHash,
+    session: SessionToken,
 }
 
-struct DirectTorrentHandle<'a> {
-    inner: *mut ffi::torrent_handle,
-    _marker: PhantomData<&'a ()>,
+/// Link from torrent handles back to the session thread that created them
+#[derive(Clone)]
+pub(crate) struct SessionToken {
+    /// Alive for as long as the `lt::session` of the session thread exists
+    alive: Weak<()>,
+    cmd_tx: Weak<Sender<SessionCommand>>,
+}
+
+impl TorrentHandle {
+    pub(crate) fn new(inner: UniquePtr<ffi::torrent_handle>, session: SessionToken) -> Self {
+        let info_hashes = ffi::torrent_get_info_hashes(&inner).into();
+
+        Self {
+            inner,
+            info_hashes,
+            session,
+        }
+    }
+
+    /// The underlying handle, or [`Error::SessionClosed`] once the session
+    /// has shut down
+    fn inner(&self) -> Result<&ffi::torrent_handle, Error> {
+        if self.session.alive.strong_count() == 0 {
+            return Err(Error::SessionClosed);
+        }
+        Ok(&self.inner)
+    }
+
+    /// Info-hashes of the torrent, captured when the handle was created so
+    /// they remain available after the torrent has been removed
+    pub fn info_hashes(&self) -> InfoHash {
+        self.info_hashes
+    }
+
+    /// Returns `false` once the torrent has been removed from the session.
+    ///
+    /// Removal happens asynchronously, the handle may still be valid right
+    /// after [`Session::remove_torrent`] returns. Any query made on an invalid
+    /// handle fails with an error.
+    pub fn is_valid(&self) -> bool {
+        self.inner().is_ok_and(ffi::torrent_is_valid)
+    }
+}
+
+impl TorrentHandle {
+    /// Queries the status of the torrent, `flags` select the fields that are
+    /// expensive to compute
+    pub fn get_status_with(&self, flags: StatusFlags) -> Result<TorrentStatus, Error> {
+        let mut status = TorrentStatus::from(ffi::torrent_get_status(self.inner()?, flags.bits())?);
+
+        // the bitfields are empty until the metadata is known, which is still
+        // an answer to the query
+        if flags.contains(StatusFlags::PIECES) {
+            status.pieces.get_or_insert_with(Vec::new);
+        }
+        if flags.contains(StatusFlags::VERIFIED_PIECES) {
+            status.verified_pieces.get_or_insert_with(Vec::new);
+        }
+
+        Ok(status)
+    }
+
+    pub fn has_metadata(&self) -> Result<bool, Error> {
+        Ok(ffi::torrent_has_metadata(self.inner()?)?)
+    }
+
+    /// Generates a magnet link of the torrent, with its trackers, web seeds
+    /// and name if known
+    pub fn make_magnet_uri(&self) -> Result<MagnetUri, Error> {
+        Ok(ffi::torrent_make_magnet_uri(self.inner()?)?.parse()?)
+    }
+
+    /// Returns the metadata of the torrent, or `None` if it has not been
+    /// received yet. For v2 torrents it includes the piece layers that are
+    /// known.
+    pub fn torrent_file(&self) -> Result<Option<TorrentInfo>, Error> {
+        let inner = ffi::torrent_get_torrent_file(self.inner()?)?;
+
+        Ok((!inner.is_null()).then(|| TorrentInfo { inner }))
+    }
+
+    /// Asks libtorrent to generate resume data for the torrent. It is
+    /// delivered in a [`SaveResumeDataAlert`], or a
+    /// [`SaveResumeDataFailedAlert`] if it could not be generated.
+    pub fn save_resume_data(&self, flags: ResumeDataFlags) -> Result<(), Error> {
+        Ok(ffi::torrent_save_resume_data(self.inner()?, flags.bits())?)
+    }
+}
+
+impl Clone for TorrentHandle {
+    fn clone(&self) -> Self {
+        Self {
+            inner: ffi::torrent_handle_clone(&self.inner),
+            info_hashes: self.info_hashes,
+            session: self.session.clone(),
+        }
+    }
+}
+
+impl TorrentHandle {
+    /// Identity of the torrent used to compare and hash handles
+    fn key(&self) -> InfoHash {
+        match self.info_hashes.v1 {
+            Some(v1) => InfoHash::v1(v1),
+            None => self.info_hashes,
+        }
+    }
+}
+
+impl PartialEq for TorrentHandle {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for TorrentHandle {}
+
+impl Hash for TorrentHandle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl fmt::Debug for TorrentHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TorrentHandle")
+            .field("info_hashes", &self.info_hashes)
+            .finish_non_exhaustive()
+    }
 }
 
 pub trait TorrentHandleTrait {
-    fn get_name(&self) -> Option<String>;
-    fn get_status(&self) -> Option<TorrentStatus>;
+    fn get_name(&self) -> Result<String, Error>;
+    fn get_status(&self) -> Result<TorrentStatus, Error>;
 }
 
-impl<'a> TorrentHandleTrait for DirectTorrentHandle<'a> {
-    fn get_name(&self) -> Option<String> {
-        Some(ffi::torrent_get_name(unsafe { &*self.inner }).to_string())
+impl TorrentHandleTrait for TorrentHandle {
+    fn get_name(&self) -> Result<String, Error> {
+        Ok(ffi::torrent_get_name(self.inner()?)?)
     }
 
-    fn get_status(&self) -> Option<TorrentStatus> {
-        Some(ffi::torrent_get_status(unsafe { &*self.inner }))
+    /// Queries every field of the status, see [`TorrentHandle::get_status_with`]
+    fn get_status(&self) -> Result<TorrentStatus, Error> {
+        self.get_status_with(StatusFlags::all())
     }
 }
 
 pub enum SessionCommand {
     AddTorrent {
-        params: AddTorrentParams,
-        cb: Sender<TorrentHandle>,
+        params: Box<AddTorrentParams>,
+        cb: oneshot::Sender<Result<TorrentHandle, Error>>,
     },
     RemoveTorrent {
         handle: TorrentHandle,
-        cb: Sender<()>,
+        cb: oneshot::Sender<Result<(), Error>>,
     },
     HandleAlerts {
         f: Box<dyn FnOnce(Vec<Alert>) + Send + Sync + 'static>,
-        cb: Sender<()>,
+        cb: oneshot::Sender<()>,
+    },
+    PopAlerts {
+        cb: oneshot::Sender<Vec<OwnedAlert>>,
+    },
+    /// Sent by libtorrent's alert notification callback
+    AlertsAvailable,
+    SubscribeAlerts {
+        tx: UnboundedSender<OwnedAlert>,
+        cb: oneshot::Sender<()>,
     },
+    Pause {
+        cb: oneshot::Sender<()>,
+    },
+    /// Runs a query of a torrent handle on the session thread
+    QueryTorrent {
+        f: Box<dyn FnOnce() + Send + 'static>,
+    },
+    /// Stops processing commands and shuts the session down
+    Abort,
     WaitForAlert {
         max_duration: Duration,
-        cb: Sender<()>,
+        cb: oneshot::Sender<()>,
     },
-    GetTorrentName {
-        i: usize,
-        cb: Sender<String>,
+    ApplySettings {
+        settings: SettingsPack,
+        cb: oneshot::Sender<Result<(), Error>>,
     },
-    GetTorrentStatus {
-        i: usize,
-        cb: Sender<TorrentStatus>,
+    GetSettings {
+        cb: oneshot::Sender<SettingsPack>,
+    },
+    SaveState {
+        flags: SaveStateFlags,
+        cb: oneshot::Sender<Result<Vec<u8>, Error>>,
+    },
+    GetTorrents {
+        cb: oneshot::Sender<Result<Vec<TorrentHandle>, Error>>,
     },
 }
 
-pub struct Alert(ffi::Alert);
-
-pub struct MetadataReceivedAlert<'alert> {
-    inner: *mut ffi::metadata_received_alert,
-    _marker: PhantomData<&'alert mut ()>,
-}
-
-impl<'alert> MetadataReceivedAlert<'alert> {
-    pub fn torrent_handle(&self) -> impl TorrentHandleTrait + '_ {
-        DirectTorrentHandle {
-            inner: unsafe { ffi::metadata_received_alert_get_torrent_handle(self.inner) },
-            _marker: PhantomData,
-        }
-    }
+/// A libtorrent session running on its own thread.
+///
+/// Dropping the session starts shutting it down without waiting for the
+/// shutdown to complete, use [`Session::shutdown`] or [`Session::abort`] to
+/// wait for trackers to be notified before the process exits.
+pub struct Session {
+    // the alert notification only holds a weak reference, so the session
+    // thread sees the channel disconnect once the session and its helpers
+    // are gone
+    cmd_tx: Arc<Sender<SessionCommand>>,
+    thread: Option<SessionThread>,
 }
 
-pub struct SaveResumeDataAlert<'alert> {
-    inner: *mut ffi::save_resume_data_alert,
-    _marker: PhantomData<&'alert mut ()>,
+struct SessionThread {
+    handle: JoinHandle<()>,
+    done_rx: Receiver<()>,
 }
 
-pub struct SaveResumeDataFailedAlert<'alert> {
-    inner: *mut ffi::save_resume_data_failed_alert,
-    _marker: PhantomData<&'alert mut ()>,
+/// Guard over a session that is shutting down, mirroring `lt::session_proxy`.
+///
+/// Unlike `lt::session_proxy`, dropping the guard does not block, the
+/// shutdown carries on in the background.
+pub struct SessionProxy {
+    thread: Option<SessionThread>,
 }
 
-impl Alert {
-    pub fn message(&self) -> String {
-        ffi::alert_message(&self.0)
-    }
-
-    pub fn as_metadata_received(&mut self) -> Option<MetadataReceivedAlert<'_>> {
-        let v = ffi::alert_cast_metadata_received(self.0);
-
-        if v.is_null() {
-            None
-        } else {
-            Some(MetadataReceivedAlert {
-                inner: v,
-                _marker: PhantomData,
-            })
+impl SessionProxy {
+    /// Returns `true` once the session has shut down
+    pub fn is_finished(&self) -> bool {
+        match &self.thread {
+            Some(thread) => thread.handle.is_finished(),
+            None => true,
         }
     }
 
-    pub fn as_save_resume_data(&mut self) -> Option<SaveResumeDataAlert<'_>> {
-        let v = ffi::alert_cast_save_resume_data(self.0);
-
-        if v.is_null() {
-            None
-        } else {
-            Some(SaveResumeDataAlert {
-                inner: v,
-                _marker: PhantomData,
-            })
+    /// Blocks until the session has shut down and joins its thread
+    pub fn wait(mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.handle.join();
         }
     }
 
-    pub fn as_save_resume_data_failed(&mut self) -> Option<SaveResumeDataFailedAlert<'_>> {
-        let v = ffi::alert_cast_save_resume_data_failed(self.0);
-
-        if v.is_null() {
-            None
-        } else {
-            Some(SaveResumeDataFailedAlert {
-                inner: v,
-                _marker: PhantomData,
-            })
+    /// Blocks until the session has shut down or `timeout` has elapsed.
+    /// Returns `true` if the session has shut down, in which case its thread
+    /// has been joined.
+    pub fn wait_timeout(&mut self, timeout: Duration) -> bool {
+        let thread = match &self.thread {
+            Some(thread) => thread,
+            None => return true,
+        };
+
+        match thread.done_rx.recv_timeout(timeout) {
+            Err(RecvTimeoutError::Timeout) => false,
+            // disconnected if the session thread panicked
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => {
+                if let Some(thread) = self.thread.take() {
+                    let _ = thread.handle.join();
+                }
+                true
+            }
         }
     }
 }
 
-pub struct Session {
-    cmd_tx: Sender<SessionCommand>,
-}
-
 bitflags! {
+    /// Mirror of `lt::alert_category_t`. Each constant has the same bit
+    /// position as its libtorrent counterpart.
     pub struct AlertCategory: u32 {
         /// Enables alerts that report an error. This includes:
         ///
@@ -177,295 +662,514 @@ bitflags! {
         /// * .torrent files errors
         /// * listen socket errors
         /// * port mapping errors
-        const ERROR = 1;
+        const ERROR = 1 << 0;
+
+        /// Enables alerts when peers send invalid requests, get banned or
+        /// snubbed.
+        const PEER = 1 << 1;
+
+        /// Enables alerts for port mapping events. For NAT-PMP and UPnP.
+        const PORT_MAPPING = 1 << 2;
+
+        /// Enables alerts for events related to the storage. File errors and
+        /// synchronization events for moving the storage, renaming files etc.
+        const STORAGE = 1 << 3;
+
+        /// Enables all tracker events. Includes announcing to trackers,
+        /// receiving responses, warnings and errors.
+        const TRACKER = 1 << 4;
 
-        // Enables alerts when peers send invalid requests, get banned or
-        // snubbed.
-        const PEER = 1;
+        /// Low level alerts for when peers are connected and disconnected.
+        const CONNECT = 1 << 5;
 
-        // Enables alerts for port mapping events. For NAT-PMP and UPnP.
-        const PORT_MAPPING = 2;
+        /// Enables alerts for when a torrent or the session changes state.
+        const STATUS = 1 << 6;
 
-        // Enables alerts for events related to the storage. File errors and
-        // synchronization events for moving the storage, renaming files etc.
-        const STORAGE = 3;
+        /// Alerts when a peer is blocked by the ip blocker or port blocker.
+        const IP_BLOCK = 1 << 8;
 
-        // Enables all tracker events. Includes announcing to trackers,
-        // receiving responses, warnings and errors.
-        const TRACKER = 4;
+        /// Alerts when some limit is reached that might limit the download
+        /// or upload rate.
+        const PERFORMANCE_WARNING = 1 << 9;
 
-        // Low level alerts for when peers are connected and disconnected.
-        const CONNECT = 5;
+        /// Alerts on events in the DHT node. For incoming searches or
+        /// bootstrapping being done etc.
+        const DHT = 1 << 10;
 
-        // Enables alerts for when a torrent or the session changes state.
-        const STATUS = 6;
+        // bit 11 was the stats category, removed from the 2.0 ABI
 
-        // Alerts when a peer is blocked by the ip blocker or port blocker.
-        const IP_BLOCK = 8;
+        /// Enables debug logging alerts. These are available unless libtorrent
+        /// was built with logging disabled (``TORRENT_DISABLE_LOGGING``). The
+        /// alerts being posted are log_alert and are session wide.
+        const SESSION_LOG = 1 << 13;
 
-        // Alerts when some limit is reached that might limit the download
-        // or upload rate.
-        const PERFORMANCE_WARNING = 9;
+        /// Enables debug logging alerts for torrents. These are available
+        /// unless libtorrent was built with logging disabled
+        /// (``TORRENT_DISABLE_LOGGING``). The alerts being posted are
+        /// torrent_log_alert and are torrent wide debug events.
+        const TORRENT_LOG = 1 << 14;
 
-        // Alerts on events in the DHT node. For incoming searches or
-        // bootstrapping being done etc.
-        const DHT = 10;
+        /// Enables debug logging alerts for peers. These are available unless
+        /// libtorrent was built with logging disabled
+        /// (``TORRENT_DISABLE_LOGGING``). The alerts being posted are
+        /// peer_log_alert and low-level peer events and messages.
+        const PEER_LOG = 1 << 15;
 
-        // If you enable these alerts, you will receive a stats_alert
-        // approximately once every second, for every active torrent.
-        // These alerts contain all statistics counters for the interval since
-        // the lasts stats alert.
-        const STATS = 11;
+        /// enables the incoming_request_alert.
+        const INCOMING_REQUEST = 1 << 16;
 
-        // Enables debug logging alerts. These are available unless libtorrent
-        // was built with logging disabled (``TORRENT_DISABLE_LOGGING``). The
-        // alerts being posted are log_alert and are session wide.
-        const SESSION_LOG = 13;
+        /// enables dht_log_alert, debug logging for the DHT
+        const DHT_LOG = 1 << 17;
 
-        // Enables debug logging alerts for torrents. These are available
-        // unless libtorrent was built with logging disabled
-        // (``TORRENT_DISABLE_LOGGING``). The alerts being posted are
-        // torrent_log_alert and are torrent wide debug events.
-        const TORRENT_LOG = 14;
+        /// enable events from pure dht operations not related to torrents
+        const DHT_OPERATION = 1 << 18;
 
-        // Enables debug logging alerts for peers. These are available unless
-        // libtorrent was built with logging disabled
-        // (``TORRENT_DISABLE_LOGGING``). The alerts being posted are
-        // peer_log_alert and low-level peer events and messages.
-        const PEER_LOG = 15;
+        /// enables port mapping log events. This log is useful
+        /// for debugging the UPnP or NAT-PMP implementation
+        const PORT_MAPPING_LOG = 1 << 19;
 
-        // enables the incoming_request_alert.
-        const INCOMING_REQUEST = 16;
+        /// enables verbose logging from the piece picker.
+        const PICKER_LOG = 1 << 20;
 
-        // enables dht_log_alert, debug logging for the DHT
-        const DHT_LOG = 17;
+        /// alerts when files complete downloading
+        const FILE_PROGRESS = 1 << 21;
 
-        // enable events from pure dht operations not related to torrents
-        const DHT_OPERATION = 18;
+        /// alerts when pieces complete downloading or fail hash check
+        const PIECE_PROGRESS = 1 << 22;
 
-        // enables port mapping log events. This log is useful
-        // for debugging the UPnP or NAT-PMP implementation
-        const PORT_MAPPING_LOG = 19;
+        /// alerts when we upload blocks to other peers
+        const UPLOAD = 1 << 23;
 
-        // enables verbose logging from the piece picker.
-        const PICKER_LOG = 20;
+        /// alerts on individual blocks being requested, downloading, finished,
+        /// rejected, time-out and cancelled. This is likely to post alerts at a
+        /// high rate.
+        const BLOCK_PROGRESS = 1 << 24;
+    }
+}
 
-        // alerts when files complete downloading
-        const FILE_PROGRESS = 21;
+bitflags! {
+    /// Mirror of `lt::torrent_flags_t`. Each constant has the same bit
+    /// position as its libtorrent counterpart.
+    pub struct TorrentFlags: u64 {
+        /// If ``seed_mode`` is set, libtorrent will assume that all files
+        /// are present for this torrent and that they all match the hashes in
+        /// the torrent file. Each time a peer requests to download a block,
+        /// the piece is verified against the hash, unless it has been verified
+        /// already. If a hash fails, the torrent will automatically leave the
+        /// seed mode and recheck all the files. The use case for this mode is
+        /// if a torrent is created and seeded, or if the user already know
+        /// that the files are complete, this is a way to avoid the initial
+        /// file checks, and significantly reduce the startup time.
+        ///
+        /// Setting ``seed_mode`` on a torrent without metadata (a
+        /// .torrent file) is a no-op and will be ignored.
+        ///
+        /// It is not possible to *set* the ``seed_mode`` flag on a torrent after it has
+        /// been added to a session. It is possible to *clear* it though.
+        const SEED_MODE = 1 << 0;
+
+        /// If ``upload_mode`` is set, the torrent will be initialized in
+        /// upload-mode, which means it will not make any piece requests. This
+        /// state is typically entered on disk I/O errors, and if the torrent
+        /// is also auto managed, it will be taken out of this state
+        /// periodically (see ``settings_pack::optimistic_disk_retry``).
+        ///
+        /// This mode can be used to avoid race conditions when
+        /// adjusting priorities of pieces before allowing the torrent to start
+        /// downloading.
+        ///
+        /// If the torrent is auto-managed (``auto_managed``), the torrent
+        /// will eventually be taken out of upload-mode, regardless of how it
+        /// got there. If it's important to manually control when the torrent
+        /// leaves upload mode, don't make it auto managed.
+        const UPLOAD_MODE = 1 << 1;
+
+        /// determines if the torrent should be added in *share mode* or not.
+        /// Share mode indicates that we are not interested in downloading the
+        /// torrent, but merely want to improve our share ratio (i.e. increase
+        /// it). A torrent started in share mode will do its best to never
+        /// download more than it uploads to the swarm. If the swarm does not
+        /// have enough demand for upload capacity, the torrent will not
+        /// download anything. This mode is intended to be safe to add any
+        /// number of torrents to, without manual screening, without the risk
+        /// of downloading more than is uploaded.
+        ///
+        /// A torrent in share mode sets the priority to all pieces to 0,
+        /// except for the pieces that are downloaded, when pieces are decided
+        /// to be downloaded. This affects the progress bar, which might be set
+        /// to "100% finished" most of the time. Do not change file or piece
+        /// priorities for torrents in share mode, it will make it not work.
+        ///
+        /// The share mode has one setting, the share ratio target, see
+        /// ``settings_pack::share_mode_target`` for more info.
+        const SHARE_MODE = 1 << 2;
+
+        /// determines if the IP filter should apply to this torrent or not. By
+        /// default all torrents are subject to filtering by the IP filter
+        /// (i.e. this flag is set by default). This is useful if certain
+        /// torrents needs to be exempt for some reason, being an auto-update
+        /// torrent for instance.
+        const APPLY_IP_FILTER = 1 << 3;
+
+        /// specifies whether or not the torrent is paused. i.e. it won't connect to the tracker or any of the peers
+        /// until it's resumed. Note that a paused torrent that also has the
+        /// auto_managed flag set can be started at any time by libtorrent's queuing
+        /// logic. See queuing_.
+        const PAUSED = 1 << 4;
+
+        /// If the torrent is auto-managed (``auto_managed``), the torrent
+        /// may be resumed at any point, regardless of how it paused. If it's
+        /// important to manually control when the torrent is paused and
+        /// resumed, don't make it auto managed.
+        ///
+        /// If ``auto_managed`` is set, the torrent will be queued,
+        /// started and seeded automatically by libtorrent. When this is set,
+        /// the torrent should also be started as paused. The default queue
+        /// order is the order the torrents were added. They are all downloaded
+        /// in that order. For more details, see queuing_.
+        const AUTO_MANAGED = 1 << 5;
+
+        /// used in add_torrent_params to indicate that it's an error to attempt
+        /// to add a torrent that's already in the session. If it's not considered an
+        /// error, a handle to the existing torrent is returned.
+        /// This flag is not saved by write_resume_data(), since it is only meant for
+        /// adding torrents.
+        const DUPLICATE_IS_ERROR = 1 << 6;
+
+        /// on by default and means that this torrent will be part of state
+        /// updates when calling post_torrent_updates().
+        /// This flag is not saved by write_resume_data().
+        const UPDATE_SUBSCRIBE = 1 << 7;
+
+        /// sets the torrent into super seeding/initial seeding mode. If the torrent
+        /// is not a seed, this flag has no effect.
+        const SUPER_SEEDING = 1 << 8;
+
+        /// sets the sequential download state for the torrent. In this mode the
+        /// piece picker will pick pieces with low index numbers before pieces with
+        /// high indices. The actual pieces that are picked depend on other factors
+        /// still, such as which pieces a peer has and whether it is in parole mode
+        /// or "prefer whole pieces"-mode. Sequential mode is not ideal for streaming
+        /// media. For that, see set_piece_deadline() instead.
+        const SEQUENTIAL_DOWNLOAD = 1 << 9;
+
+        /// When this flag is set, the torrent will *force stop* whenever it
+        /// transitions from a non-data-transferring state into a data-transferring
+        /// state (referred to as being ready to download or seed). This is useful
+        /// for torrents that should not start downloading or seeding yet, but want
+        /// to be made ready to do so. A torrent may need to have its files checked
+        /// for instance, so it needs to be started and possibly queued for checking
+        /// (auto-managed and started) but as soon as it's done, it should be
+        /// stopped.
+        ///
+        /// *Force stopped* means auto-managed is set to false and it's paused. As
+        /// if the auto_manages flag is cleared and the paused flag is set on the torrent.
+        ///
+        /// Note that the torrent may transition into a downloading state while
+        /// setting this flag, and since the logic is edge triggered you may
+        /// miss the edge. To avoid this race, if the torrent already is in a
+        /// downloading state when this call is made, it will trigger the
+        /// stop-when-ready immediately.
+        ///
+        /// When the stop-when-ready logic fires, the flag is cleared. Any
+        /// subsequent transitions between downloading and non-downloading states
+        /// will not be affected, until this flag is set again.
+        ///
+        /// The behavior is more robust when setting this flag as part of adding
+        /// the torrent. See add_torrent_params.
+        ///
+        /// The stop-when-ready flag fixes the inherent race condition of waiting
+        /// for the state_changed_alert and then call pause(). The download/seeding
+        /// will most likely start in between posting the alert and receiving the
+        /// call to pause.
+        ///
+        /// A downloading state is one where peers are being connected. Which means
+        /// just downloading the metadata via the ``ut_metadata`` extension counts
+        /// as a downloading state. In order to stop a torrent once the metadata
+        /// has been downloaded, instead set all file priorities to dont_download
+        const STOP_WHEN_READY = 1 << 10;
+
+        /// when this flag is set, the tracker list in the add_torrent_params
+        /// object override any trackers from the torrent file. If the flag is
+        /// not set, the trackers from the add_torrent_params object will be
+        /// added to the list of trackers used by the torrent.
+        /// This flag is set by read_resume_data() if there are trackers present in
+        /// the resume data file. This effectively makes the trackers saved in the
+        /// resume data take precedence over the original trackers. This includes if
+        /// there's an empty list of trackers, to support the case where they were
+        /// explicitly removed in the previous session.
+        /// This flag is not saved by write_resume_data()
+        const OVERRIDE_TRACKERS = 1 << 11;
+
+        /// If this flag is set, the web seeds from the add_torrent_params
+        /// object will override any web seeds in the torrent file. If it's not
+        /// set, web seeds in the add_torrent_params object will be added to the
+        /// list of web seeds used by the torrent.
+        /// This flag is set by read_resume_data() if there are web seeds present in
+        /// the resume data file. This effectively makes the web seeds saved in the
+        /// resume data take precedence over the original ones. This includes if
+        /// there's an empty list of web seeds, to support the case where they were
+        /// explicitly removed in the previous session.
+        /// This flag is not saved by write_resume_data()
+        const OVERRIDE_WEB_SEEDS = 1 << 12;
+
+        /// if this flag is set (which it is by default) the torrent will be
+        /// considered needing to save its resume data immediately as it's
+        /// added. New torrents that don't have any resume data should do that.
+        /// This flag is cleared by a successful call to save_resume_data()
+        /// This flag is not saved by write_resume_data(), since it represents an
+        /// ephemeral state of a running torrent.
+        const NEED_SAVE_RESUME = 1 << 13;
+
+        /// set this flag to disable DHT for this torrent. This lets you have the DHT
+        /// enabled for the whole client, and still have specific torrents not
+        /// participating in it. i.e. not announcing to the DHT nor picking up peers
+        /// from it.
+        const DISABLE_DHT = 1 << 19;
+
+        /// set this flag to disable local service discovery for this torrent.
+        const DISABLE_LSD = 1 << 20;
+
+        /// set this flag to disable peer exchange for this torrent.
+        const DISABLE_PEX = 1 << 21;
+
+        /// if this flag is set, the resume data will be assumed to be correct
+        /// without validating it against any files on disk. This may be used when
+        /// restoring a session by loading resume data from disk. It will save time
+        /// and also delay any hard disk errors until files are actually needed. If
+        /// the resume data cannot be trusted, or if a torrent is added for the first
+        /// time to some save path that may already have some of the files, this flag
+        /// should not be set.
+        const NO_VERIFY_FILES = 1 << 22;
+
+        /// default all file priorities to dont_download. This is useful for adding
+        /// magnet links where the number of files is unknown, but the
+        /// file_priorities is still set for some files. Any file not covered by
+        /// the file_priorities list will be set to normal download priority,
+        /// unless this flag is set, in which case they will be set to 0
+        /// (dont_download).
+        const DEFAULT_DONT_DOWNLOAD = 1 << 23;
+
+        /// The flags libtorrent sets on a default constructed
+        /// add_torrent_params.
+        const DEFAULT_FLAGS = Self::UPDATE_SUBSCRIBE.bits
+            | Self::AUTO_MANAGED.bits
+            | Self::PAUSED.bits
+            | Self::APPLY_IP_FILTER.bits
+            | Self::NEED_SAVE_RESUME.bits;
+    }
+}
 
-        // alerts when pieces complete downloading or fail hash check
-        const PIECE_PROGRESS = 22;
+bitflags! {
+    /// Mirror of `lt::torrent_handle::status_flags_t`, selects the fields of
+    /// [`TorrentStatus`] that are expensive to query. Each constant has the
+    /// same bit position as its libtorrent counterpart.
+    pub struct StatusFlags: u32 {
+        /// Calculate ``distributed_copies``, ``distributed_full_copies``
+        /// and ``distributed_fraction``.
+        const DISTRIBUTED_COPIES = 1 << 0;
+        /// Include partial pieces in the download counters.
+        const ACCURATE_DOWNLOAD_COUNTERS = 1 << 1;
+        /// Include ``last_seen_complete``.
+        const LAST_SEEN_COMPLETE = 1 << 2;
+        /// Include the ``pieces`` bitfield.
+        const PIECES = 1 << 3;
+        /// Include the ``verified_pieces`` bitfield, only relevant in seed
+        /// mode.
+        const VERIFIED_PIECES = 1 << 4;
+        /// Include the torrent file, currently not exposed.
+        const TORRENT_FILE = 1 << 5;
+        /// Include the ``name`` of the torrent.
+        const NAME = 1 << 6;
+        /// Include the ``save_path`` of the torrent.
+        const SAVE_PATH = 1 << 7;
+    }
+}
 
-        // alerts when we upload blocks to other peers
-        const UPLOAD = 23;
+impl From<u32> for AlertCategory {
+    /// Converts a raw libtorrent alert mask, dropping unknown bits
+    fn from(raw: u32) -> Self {
+        Self::from_bits_truncate(raw)
+    }
+}
 
-        // alerts on individual blocks being requested, downloading, finished,
-        // rejected, time-out and cancelled. This is likely to post alerts at a
-        // high rate.
-        const BLOCK_PROGRESS = 24;
+impl From<AlertCategory> for u32 {
+    fn from(v: AlertCategory) -> Self {
+        v.bits()
+    }
+}
+
+impl From<u64> for TorrentFlags {
+    /// Converts raw libtorrent torrent flags, dropping unknown bits
+    fn from(raw: u64) -> Self {
+        Self::from_bits_truncate(raw)
+    }
+}
+
+impl From<TorrentFlags> for u64 {
+    fn from(v: TorrentFlags) -> Self {
+        v.bits()
+    }
+}
+
+bitflags! {
+    /// Mirror of `lt::file_storage::file_flags_t`. Each constant has the same
+    /// bit position as its libtorrent counterpart.
+    pub struct FileFlags: u8 {
+        /// Padding inserted to align the next file to a piece boundary, it
+        /// is not written to disk
+        const PAD_FILE = 1 << 0;
+        const HIDDEN = 1 << 1;
+        const EXECUTABLE = 1 << 2;
+        const SYMLINK = 1 << 3;
+    }
+}
+
+bitflags! {
+    /// Mirror of `lt::write_torrent_flags_t`, see
+    /// [`TorrentInfo::to_bencoded_torrent`]. Each constant has the same bit
+    /// position as its libtorrent counterpart.
+    pub struct WriteTorrentFlags: u32 {
+        /// Write v2 torrents even if the piece layers are missing, producing
+        /// a torrent that can't be used to seed without the content.
+        const ALLOW_MISSING_PIECE_LAYER = 1 << 0;
+        /// Don't include the http seeds.
+        const NO_HTTP_SEEDS = 1 << 1;
+        /// Include the DHT nodes.
+        const INCLUDE_DHT_NODES = 1 << 2;
     }
 }
 
 bitflags! {
-    pub struct TorrentFlags : u64 {
-        // If ``seed_mode`` is set, libtorrent will assume that all files
-        // are present for this torrent and that they all match the hashes in
-        // the torrent file. Each time a peer requests to download a block,
-        // the piece is verified against the hash, unless it has been verified
-        // already. If a hash fails, the torrent will automatically leave the
-        // seed mode and recheck all the files. The use case for this mode is
-        // if a torrent is created and seeded, or if the user already know
-        // that the files are complete, this is a way to avoid the initial
-        // file checks, and significantly reduce the startup time.
-        //
-        // Setting ``seed_mode`` on a torrent without metadata (a
-        // .torrent file) is a no-op and will be ignored.
-        //
-        // It is not possible to *set* the ``seed_mode`` flag on a torrent after it has
-        // been added to a session. It is possible to *clear* it though.
-        const SEED_MODE = 0;
-
-        // If ``upload_mode`` is set, the torrent will be initialized in
-        // upload-mode, which means it will not make any piece requests. This
-        // state is typically entered on disk I/O errors, and if the torrent
-        // is also auto managed, it will be taken out of this state
-        // periodically (see ``settings_pack::optimistic_disk_retry``).
-        //
-        // This mode can be used to avoid race conditions when
-        // adjusting priorities of pieces before allowing the torrent to start
-        // downloading.
-        //
-        // If the torrent is auto-managed (``auto_managed``), the torrent
-        // will eventually be taken out of upload-mode, regardless of how it
-        // got there. If it's important to manually control when the torrent
-        // leaves upload mode, don't make it auto managed.
-        const UPLOAD_MODE = 1;
-
-        // determines if the torrent should be added in *share mode* or not.
-        // Share mode indicates that we are not interested in downloading the
-        // torrent, but merely want to improve our share ratio (i.e. increase
-        // it). A torrent started in share mode will do its best to never
-        // download more than it uploads to the swarm. If the swarm does not
-        // have enough demand for upload capacity, the torrent will not
-        // download anything. This mode is intended to be safe to add any
-        // number of torrents to, without manual screening, without the risk
-        // of downloading more than is uploaded.
-        //
-        // A torrent in share mode sets the priority to all pieces to 0,
-        // except for the pieces that are downloaded, when pieces are decided
-        // to be downloaded. This affects the progress bar, which might be set
-        // to "100% finished" most of the time. Do not change file or piece
-        // priorities for torrents in share mode, it will make it not work.
-        //
-        // The share mode has one setting, the share ratio target, see
-        // ``settings_pack::share_mode_target`` for more info.
-        const SHARE_MODE = 2;
-
-        // determines if the IP filter should apply to this torrent or not. By
-        // default all torrents are subject to filtering by the IP filter
-        // (i.e. this flag is set by default). This is useful if certain
-        // torrents needs to be exempt for some reason, being an auto-update
-        // torrent for instance.
-        const APPLY_IP_FILTER = 3;
-
-        // specifies whether or not the torrent is paused. i.e. it won't connect to the tracker or any of the peers
-        // until it's resumed. Note that a paused torrent that also has the
-        // auto_managed flag set can be started at any time by libtorrent's queuing
-        // logic. See queuing_.
-        const PAUSED = 4;
-
-        // If the torrent is auto-managed (``auto_managed``), the torrent
-        // may be resumed at any point, regardless of how it paused. If it's
-        // important to manually control when the torrent is paused and
-        // resumed, don't make it auto managed.
-        //
-        // If ``auto_managed`` is set, the torrent will be queued,
-        // started and seeded automatically by libtorrent. When this is set,
-        // the torrent should also be started as paused. The default queue
-        // order is the order the torrents were added. They are all downloaded
-        // in that order. For more details, see queuing_.
-        const AUTO_MANAGED = 5;
-
-        // used in add_torrent_params to indicate that it's an error to attempt
-        // to add a torrent that's already in the session. If it's not considered an
-        // error, a handle to the existing torrent is returned.
-        // This flag is not saved by write_resume_data(), since it is only meant for
-        // adding torrents.
-        const DUPLICATE_IS_ERROR = 6;
-
-        // on by default and means that this torrent will be part of state
-        // updates when calling post_torrent_updates().
-        // This flag is not saved by write_resume_data().
-        const UPDATE_SUBSCRIBE = 7;
-
-        // sets the torrent into super seeding/initial seeding mode. If the torrent
-        // is not a seed, this flag has no effect.
-        const SUPER_SEEDING = 8;
-
-        // sets the sequential download state for the torrent. In this mode the
-        // piece picker will pick pieces with low index numbers before pieces with
-        // high indices. The actual pieces that are picked depend on other factors
-        // still, such as which pieces a peer has and whether it is in parole mode
-        // or "prefer whole pieces"-mode. Sequential mode is not ideal for streaming
-        // media. For that, see set_piece_deadline() instead.
-        const SEQUENTIAL_DOWNLOAD = 9;
-
-        // When this flag is set, the torrent will *force stop* whenever it
-        // transitions from a non-data-transferring state into a data-transferring
-        // state (referred to as being ready to download or seed). This is useful
-        // for torrents that should not start downloading or seeding yet, but want
-        // to be made ready to do so. A torrent may need to have its files checked
-        // for instance, so it needs to be started and possibly queued for checking
-        // (auto-managed and started) but as soon as it's done, it should be
-        // stopped.
-        //
-        // *Force stopped* means auto-managed is set to false and it's paused. As
-        // if the auto_manages flag is cleared and the paused flag is set on the torrent.
-        //
-        // Note that the torrent may transition into a downloading state while
-        // setting this flag, and since the logic is edge triggered you may
-        // miss the edge. To avoid this race, if the torrent already is in a
-        // downloading state when this call is made, it will trigger the
-        // stop-when-ready immediately.
-        //
-        // When the stop-when-ready logic fires, the flag is cleared. Any
-        // subsequent transitions between downloading and non-downloading states
-        // will not be affected, until this flag is set again.
-        //
-        // The behavior is more robust when setting this flag as part of adding
-        // the torrent. See add_torrent_params.
-        //
-        // The stop-when-ready flag fixes the inherent race condition of waiting
-        // for the state_changed_alert and then call pause(). The download/seeding
-        // will most likely start in between posting the alert and receiving the
-        // call to pause.
-        //
-        // A downloading state is one where peers are being connected. Which means
-        // just downloading the metadata via the ``ut_metadata`` extension counts
-        // as a downloading state. In order to stop a torrent once the metadata
-        // has been downloaded, instead set all file priorities to dont_download
-        const STOP_WHEN_READY = 10;
-
-        // when this flag is set, the tracker list in the add_torrent_params
-        // object override any trackers from the torrent file. If the flag is
-        // not set, the trackers from the add_torrent_params object will be
-        // added to the list of trackers used by the torrent.
-        // This flag is set by read_resume_data() if there are trackers present in
-        // the resume data file. This effectively makes the trackers saved in the
-        // resume data take precedence over the original trackers. This includes if
-        // there's an empty list of trackers, to support the case where they were
-        // explicitly removed in the previous session.
-        // This flag is not saved by write_resume_data()
-        const OVERRIDE_TRACKERS = 11;
-
-        // If this flag is set, the web seeds from the add_torrent_params
-        // object will override any web seeds in the torrent file. If it's not
-        // set, web seeds in the add_torrent_params object will be added to the
-        // list of web seeds used by the torrent.
-        // This flag is set by read_resume_data() if there are web seeds present in
-        // the resume data file. This effectively makes the web seeds saved in the
-        // resume data take precedence over the original ones. This includes if
-        // there's an empty list of web seeds, to support the case where they were
-        // explicitly removed in the previous session.
-        // This flag is not saved by write_resume_data()
-        const OVERRIDE_WEB_SEEDS = 12;
-
-        // if this flag is set (which it is by default) the torrent will be
-        // considered needing to save its resume data immediately as it's
-        // added. New torrents that don't have any resume data should do that.
-        // This flag is cleared by a successful call to save_resume_data()
-        // This flag is not saved by write_resume_data(), since it represents an
-        // ephemeral state of a running torrent.
-        const NEED_SAVE_RESUME = 13;
-
-        // set this flag to disable DHT for this torrent. This lets you have the DHT
-        // enabled for the whole client, and still have specific torrents not
-        // participating in it. i.e. not announcing to the DHT nor picking up peers
-        // from it.
-        const DISABLE_DHT = 19;
-
-        // set this flag to disable local service discovery for this torrent.
-        const DISABLE_LSD = 20;
-
-        // set this flag to disable peer exchange for this torrent.
-        const DISABLE_PEX = 21;
-
-        // if this flag is set, the resume data will be assumed to be correct
-        // without validating it against any files on disk. This may be used when
-        // restoring a session by loading resume data from disk. It will save time
-        // and also delay any hard disk errors until files are actually needed. If
-        // the resume data cannot be trusted, or if a torrent is added for the first
-        // time to some save path that may already have some of the files, this flag
-        // should not be set.
-        const NO_VERIFY_FILES = 22;
-
-        // default all file priorities to dont_download. This is useful for adding
-        // magnet links where the number of files is unknown, but the
-        // file_priorities is still set for some files. Any file not covered by
-        // the file_priorities list will be set to normal download priority,
-        // unless this flag is set, in which case they will be set to 0
-        // (dont_download).
-        const DEFAULT_DONT_DOWNLOAD = 23;
+    /// Mirror of `lt::create_flags_t`, see [`CreateTorrent`]. Each constant
+    /// has the same bit position as its libtorrent counterpart.
+    pub struct CreateFlags: u32 {
+        /// Record the modification time of the files.
+        const MODIFICATION_TIME = 1 << 2;
+        /// Add symlinks as symlinks instead of the files they point to.
+        const SYMLINKS = 1 << 3;
+        /// Create a v2 torrent, without the v1 info section.
+        const V2_ONLY = 1 << 5;
+        /// Create a v1 torrent, without the v2 file tree and piece layers.
+        const V1_ONLY = 1 << 6;
+        /// Sort the files and align them to pieces with pad files, as v2
+        /// torrents always do. Only meaningful for v1 torrents.
+        const CANONICAL_FILES = 1 << 7;
+        /// Don't record the executable and hidden attributes of the files.
+        const NO_ATTRIBUTES = 1 << 8;
+        /// Like `CANONICAL_FILES`, without padding the last file.
+        const CANONICAL_FILES_NO_TAIL_PADDING = 1 << 9;
+    }
+}
+
+bitflags! {
+    /// Mirror of `lt::resume_data_flags_t`, see
+    /// [`TorrentHandle::save_resume_data`]. Each constant has the same bit
+    /// position as its libtorrent counterpart.
+    pub struct ResumeDataFlags: u8 {
+        /// Flush the disk cache of the torrent before saving, making sure
+        /// the resume data reflects what is actually on disk.
+        const FLUSH_DISK_CACHE = 1 << 0;
+        /// Include the info dictionary of the torrent, so it can be restored
+        /// without the .torrent file.
+        const SAVE_INFO_DICT = 1 << 1;
+        /// Only save resume data if the torrent has changed since it was
+        /// last saved.
+        const ONLY_IF_MODIFIED = 1 << 2;
+    }
+}
+
+bitflags! {
+    /// Mirror of `lt::save_state_flags_t`, selects the parts of the session
+    /// state saved by [`Session::save_state`]. Each constant has the same bit
+    /// position as its libtorrent counterpart.
+    pub struct SaveStateFlags: u32 {
+        /// Save the settings of the session.
+        const SETTINGS = 1 << 0;
+        /// Save the DHT node ids and routing table.
+        const DHT_STATE = 1 << 2;
+        /// Save the state of plugins.
+        const EXTENSION_STATE = 1 << 11;
+        /// Save the IP filter.
+        const IP_FILTER = 1 << 12;
+    }
+}
+
+impl From<u32> for StatusFlags {
+    /// Converts raw libtorrent status flags, dropping unknown bits
+    fn from(raw: u32) -> Self {
+        Self::from_bits_truncate(raw)
+    }
+}
+
+impl From<StatusFlags> for u32 {
+    fn from(v: StatusFlags) -> Self {
+        v.bits()
+    }
+}
+
+impl From<u8> for FileFlags {
+    /// Converts raw libtorrent file flags, dropping unknown bits
+    fn from(raw: u8) -> Self {
+        Self::from_bits_truncate(raw)
+    }
+}
+
+impl From<FileFlags> for u8 {
+    fn from(v: FileFlags) -> Self {
+        v.bits()
+    }
+}
+
+impl From<u32> for WriteTorrentFlags {
+    /// Converts raw libtorrent write torrent flags, dropping unknown bits
+    fn from(raw: u32) -> Self {
+        Self::from_bits_truncate(raw)
+    }
+}
+
+impl From<WriteTorrentFlags> for u32 {
+    fn from(v: WriteTorrentFlags) -> Self {
+        v.bits()
+    }
+}
+
+impl From<u32> for CreateFlags {
+    /// Converts raw libtorrent create flags, dropping unknown bits
+    fn from(raw: u32) -> Self {
+        Self::from_bits_truncate(raw)
+    }
+}
+
+impl From<CreateFlags> for u32 {
+    fn from(v: CreateFlags) -> Self {
+        v.bits()
+    }
+}
+
+impl From<u8> for ResumeDataFlags {
+    /// Converts raw libtorrent resume data flags, dropping unknown bits
+    fn from(raw: u8) -> Self {
+        Self::from_bits_truncate(raw)
+    }
+}
+
+impl From<ResumeDataFlags> for u8 {
+    fn from(v: ResumeDataFlags) -> Self {
+        v.bits()
+    }
+}
+
+impl From<u32> for SaveStateFlags {
+    /// Converts raw libtorrent save state flags, dropping unknown bits
+    fn from(raw: u32) -> Self {
+        Self::from_bits_truncate(raw)
+    }
+}
+
+impl From<SaveStateFlags> for u32 {
+    fn from(v: SaveStateFlags) -> Self {
+        v.bits()
     }
 }
 
@@ -476,6 +1180,8 @@ pub struct SessionBuilder {
     listen_interfaces: Option<Vec<String>>,
     alert_mask: Option<AlertCategory>,
     disable_disk: bool,
+    settings: Option<SettingsPack>,
+    saved_state: Option<Vec<u8>>,
 }
 
 impl SessionBuilder {
@@ -483,6 +1189,15 @@ impl SessionBuilder {
         Self::default()
     }
 
+    /// Restores a session from a state saved by [`Session::save_state`]. The
+    /// other options of the builder take precedence over the saved settings.
+    pub fn from_saved_state(state: &[u8]) -> Self {
+        Self {
+            saved_state: Some(state.to_vec()),
+            ..Self::default()
+        }
+    }
+
     pub fn with_user_agent(mut self, user_agent: String) -> Self {
         self.user_agent = Some(user_agent);
         self
@@ -508,15 +1223,43 @@ impl SessionBuilder {
         self
     }
 
-    pub fn build(self) -> Session {
+    /// Settings the session is created with. The other options of the
+    /// builder take precedence over the same settings in the pack.
+    pub fn with_settings(mut self, settings: SettingsPack) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+
+    pub fn build(self) -> Result<Session, Error> {
         let (cmd_tx, cmd_rx) = channel();
+        let cmd_tx = Arc::new(cmd_tx);
 
         let (started_tx, started_rx) = channel();
 
-        std::thread::spawn({
-            let cmd_tx = cmd_tx.clone();
+        let (done_tx, done_rx) = channel();
+
+        let handle = std::thread::spawn({
+            let notify_tx = Arc::downgrade(&cmd_tx);
+            let handle_tx = Arc::downgrade(&cmd_tx);
             move || {
-                let mut params = ffi::new_session_params();
+                let mut params = match self.saved_state {
+                    Some(state) => match ffi::new_session_params_from_state(&state) {
+                        Ok(params) => params,
+                        Err(e) => {
+                            let _ = started_tx.send(Err(Error::from(e)));
+                            return;
+                        }
+                    },
+                    None => ffi::new_session_params(),
+                };
+
+                if let Some(v) = self.settings {
+                    let settings = ffi::session_params_get_settings(params.pin_mut());
+                    if let Err(e) = v.apply_to(settings) {
+                        let _ = started_tx.send(Err(e));
+                        return;
+                    }
+                }
 
                 if let Some(v) = self.user_agent {
                     ffi::session_params_set_user_agent(params.pin_mut(), &v);
@@ -540,180 +1283,474 @@ impl SessionBuilder {
                     ffi::session_params_disable_disk(params.pin_mut());
                 }
 
-                let mut session = ffi::new_session(params);
-
-                let _ = started_tx.send(());
+                let mut session = match ffi::new_session(params) {
+                    Ok(session) => {
+                        let _ = started_tx.send(Ok(()));
+                        session
+                    }
+                    Err(e) => {
+                        let _ = started_tx.send(Err(Error::from(e)));
+                        return;
+                    }
+                };
 
-                let mut add_torrent_counter = 0_usize;
+                ffi::session_set_alert_notify(
+                    session.pin_mut(),
+                    Box::new(AlertNotify::new(move || {
+                        if let Some(cmd_tx) = notify_tx.upgrade() {
+                            let _ = cmd_tx.send(SessionCommand::AlertsAvailable);
+                        }
+                    })),
+                );
 
-                let mut added_torrents = HashMap::<usize, UniquePtr<ffi::torrent_handle>>::new();
+                let alive = Arc::new(());
+                let token = SessionToken {
+                    alive: Arc::downgrade(&alive),
+                    cmd_tx: handle_tx,
+                };
+                let mut alert_subscribers = Vec::<UnboundedSender<OwnedAlert>>::new();
 
                 while let Ok(cmd) = cmd_rx.recv() {
                     match cmd {
                         SessionCommand::AddTorrent { params, cb } => {
-                            let mut p = match params.source {
-                                AddTorrentParamsSource::Magnet(magnet) => {
-                                    ffi::new_add_torrent_params_from_magnet_uri(&magnet)
-                                }
-                                AddTorrentParamsSource::Torrent(path) => {
-                                    let path = path.to_string_lossy();
-                                    ffi::new_add_torrent_params_from_torrent_file(&path)
-                                }
-                            };
-
-                            if let Some(v) = params.save_path {
-                                let v = v.to_string_lossy();
-                                ffi::add_torrent_params_set_save_path(p.pin_mut(), &v);
-                            }
+                            let res = (|| -> Result<TorrentHandle, Error> {
+                                let mut p = params.into_ffi()?;
 
-                            if let Some(v) = params.trackers {
-                                ffi::add_torrent_params_set_trackers(
-                                    p.pin_mut(),
-                                    &v.iter().map(|v| v.as_str()).collect::<Vec<_>>(),
-                                );
-                            }
-
-                            let handle = ffi::session_add_torrent(session.pin_mut(), p.pin_mut());
-
-                            added_torrents.insert(add_torrent_counter, handle);
-
-                            let torrent_handle = TorrentHandle {
-                                cmd_tx: cmd_tx.clone(),
-                                i: add_torrent_counter,
-                            };
+                                let handle =
+                                    ffi::session_add_torrent(session.pin_mut(), p.pin_mut())?;
 
-                            add_torrent_counter += 1;
+                                Ok(TorrentHandle::new(handle, token.clone()))
+                            })();
 
-                            let _ = cb.send(torrent_handle);
+                            let _ = cb.send(res);
                         }
                         SessionCommand::RemoveTorrent { handle, cb } => {
-                            let hdl = added_torrents.remove(&handle.i).unwrap();
-                            ffi::session_remove_torrent(session.pin_mut(), &hdl);
+                            let res = ffi::session_remove_torrent(session.pin_mut(), &handle.inner)
+                                .map_err(Error::from);
 
-                            let _ = cb.send(());
+                            let _ = cb.send(res);
                         }
                         SessionCommand::HandleAlerts { f, cb } => {
                             let alerts = ffi::session_get_alerts(session.pin_mut())
                                 .into_iter()
-                                .map(Alert)
+                                .map(|alert| Alert(alert, token.clone()))
                                 .collect();
                             (f)(alerts);
 
                             let _ = cb.send(());
                         }
+                        SessionCommand::AlertsAvailable => {
+                            if !alert_subscribers.is_empty() {
+                                dispatch_alerts(session.pin_mut(), &token, &mut alert_subscribers);
+                            }
+                        }
+                        SessionCommand::SubscribeAlerts { tx, cb } => {
+                            alert_subscribers.push(tx);
+                            // alerts queued before subscribing won't trigger another notification
+                            dispatch_alerts(session.pin_mut(), &token, &mut alert_subscribers);
+
+                            let _ = cb.send(());
+                        }
+                        SessionCommand::PopAlerts { cb } => {
+                            let alerts = ffi::session_get_alerts(session.pin_mut())
+                                .into_iter()
+                                .map(|alert| Alert(alert, token.clone()).to_owned())
+                                .collect();
+
+                            let _ = cb.send(alerts);
+                        }
                         SessionCommand::WaitForAlert { max_duration, cb } => {
                             ffi::wait_for_alert(session.pin_mut(), max_duration.as_millis() as u64);
 
                             let _ = cb.send(());
                         }
-                        SessionCommand::GetTorrentName { i, cb } => {
+                        SessionCommand::ApplySettings { settings, cb } => {
+                            let res = (|| -> Result<(), Error> {
+                                let mut pack = ffi::new_settings_pack();
+                                settings.apply_to(pack.pin_mut())?;
+                                ffi::session_apply_settings(session.pin_mut(), &pack)?;
+                                Ok(())
+                            })();
+
+                            let _ = cb.send(res);
+                        }
+                        SessionCommand::GetSettings { cb } => {
+                            let _ = cb.send(ffi::session_get_settings(&session).into());
+                        }
+                        SessionCommand::SaveState { flags, cb } => {
                             let _ = cb.send(
-                                ffi::torrent_get_name(added_torrents.get(&i).unwrap()).to_string(),
+                                ffi::session_save_state(&session, flags.bits())
+                                    .map_err(Error::from),
                             );
                         }
-                        SessionCommand::GetTorrentStatus { i, cb } => {
-                            let _ =
-                                cb.send(ffi::torrent_get_status(added_torrents.get(&i).unwrap()));
+                        SessionCommand::GetTorrents { cb } => {
+                            let res = ffi::session_get_torrents(&session)
+                                .map(|handles| {
+                                    handles
+                                        .iter()
+                                        .map(|hdl| {
+                                            TorrentHandle::new(
+                                                ffi::torrent_handle_clone(hdl),
+                                                token.clone(),
+                                            )
+                                        })
+                                        .collect()
+                                })
+                                .map_err(Error::from);
+
+                            let _ = cb.send(res);
+                        }
+                        SessionCommand::QueryTorrent { f } => f(),
+                        SessionCommand::Pause { cb } => {
+                            ffi::session_pause(session.pin_mut());
+
+                            let _ = cb.send(());
                         }
+                        SessionCommand::Abort => break,
                     }
                 }
+
+                // commands still queued are dropped along with their callbacks,
+                // their callers get Error::SessionClosed
+                let proxy = ffi::session_abort(session.pin_mut());
+                drop(session);
+                drop(alive);
+                drop(alert_subscribers);
+                // blocks until trackers have been notified
+                drop(proxy);
+
+                let _ = done_tx.send(());
             }
         });
 
-        started_rx.recv().unwrap();
+        started_rx.recv().map_err(|_| Error::SessionClosed)??;
 
-        Session { cmd_tx }
+        Ok(Session {
+            cmd_tx,
+            thread: Some(SessionThread { handle, done_rx }),
+        })
     }
 }
 
+fn dispatch_alerts(
+    session: Pin<&mut ffi::session>,
+    token: &SessionToken,
+    subscribers: &mut Vec<UnboundedSender<OwnedAlert>>,
+) {
+    let alerts = ffi::session_get_alerts(session)
+        .into_iter()
+        .map(|alert| Alert(alert, token.clone()).to_owned())
+        .collect::<Vec<_>>();
+
+    subscribers.retain(|tx| !tx.is_closed());
+
+    for alert in alerts {
+        for tx in subscribers.iter() {
+            let _ = tx.unbounded_send(alert.clone());
+        }
+    }
+}
+
+/// Sends a command to the session thread and returns a future resolving to its reply
+fn request<T>(
+    cmd_tx: &Sender<SessionCommand>,
+    cmd: impl FnOnce(oneshot::Sender<T>) -> SessionCommand,
+) -> impl Future<Output = Result<T, Error>> {
+    let (cb_tx, cb_rx) = oneshot::channel();
+    let _ = cmd_tx.send(cmd(cb_tx));
+    async move { cb_rx.await.map_err(|_| Error::SessionClosed) }
+}
+
 impl Session {
-    pub fn add_torrent(&self, params: AddTorrentParams) -> TorrentHandle {
-        let (cb_tx, cb_rx) = channel();
-        let _ = self
-            .cmd_tx
-            .send(SessionCommand::AddTorrent { params, cb: cb_tx });
-        cb_rx.recv().unwrap()
+    /// Starts shutting the session down and returns a guard that can be used
+    /// to wait for the shutdown to complete.
+    ///
+    /// Once the session has shut down, queries on its torrent handles fail
+    /// with an error and alert streams end.
+    pub fn abort(mut self) -> SessionProxy {
+        let _ = self.cmd_tx.send(SessionCommand::Abort);
+
+        SessionProxy {
+            thread: self.thread.take(),
+        }
+    }
+
+    /// Pauses the session and its torrents, then shuts it down, waiting up
+    /// to `timeout` for it to complete. Returns `false` if the timeout
+    /// elapsed, in which case the shutdown carries on in the background.
+    ///
+    /// The session is shut down even if it can't be paused, the error is
+    /// returned afterwards.
+    ///
+    /// Use [`Session::shutdown_with_saver`] to save the resume data of the
+    /// paused torrents first.
+    pub fn shutdown(self, timeout: Duration) -> Result<bool, Error> {
+        let paused = self.pause();
+        let done = self.abort().wait_timeout(timeout);
+
+        paused.map(|()| done)
+    }
+
+    /// Pauses every torrent of the session, mirroring `lt::session::pause`
+    pub fn pause(&self) -> Result<(), Error> {
+        block_on(request(&self.cmd_tx, move |cb| SessionCommand::Pause {
+            cb,
+        }))
+    }
+
+    pub fn add_torrent(&self, params: AddTorrentParams) -> Result<TorrentHandle, Error> {
+        block_on(request(&self.cmd_tx, move |cb| {
+            SessionCommand::AddTorrent {
+                params: Box::new(params),
+                cb,
+            }
+        }))?
+    }
+
+    pub fn remove_torrent(&self, handle: TorrentHandle) -> Result<(), Error> {
+        block_on(request(&self.cmd_tx, move |cb| {
+            SessionCommand::RemoveTorrent { handle, cb }
+        }))?
+    }
+
+    /// Returns the handles of every torrent in the session
+    pub fn torrents(&self) -> Result<Vec<TorrentHandle>, Error> {
+        block_on(request(&self.cmd_tx, move |cb| {
+            SessionCommand::GetTorrents { cb }
+        }))?
+    }
+
+    pub fn handle_alerts(
+        &self,
+        f: impl FnOnce(Vec<Alert>) + Send + Sync + 'static,
+    ) -> Result<(), Error> {
+        block_on(request(&self.cmd_tx, move |cb| {
+            SessionCommand::HandleAlerts { f: Box::new(f), cb }
+        }))
+    }
+
+    /// Subscribes to alerts as they are posted, without polling. Every
+    /// stream receives its own copy of each alert.
+    ///
+    /// While at least one stream is alive, the session pops alerts as soon as
+    /// libtorrent signals them, so they will no longer be returned by
+    /// [`Session::pop_alerts`] or [`Session::handle_alerts`]. This includes
+    /// the stream used by [`Session::start_resume_data_saver`].
+    pub fn alerts(&self) -> Result<AlertStream, Error> {
+        let (tx, rx) = unbounded();
+        block_on(request(&self.cmd_tx, move |cb| {
+            SessionCommand::SubscribeAlerts { tx, cb }
+        }))?;
+
+        Ok(AlertStream::new(rx))
+    }
+
+    /// Pops all pending alerts and returns owned copies of them, which may be
+    /// processed on any thread.
+    ///
+    /// Returns nothing while an [`AlertStream`] is alive, see
+    /// [`Session::alerts`].
+    pub fn pop_alerts(&self) -> Result<Vec<OwnedAlert>, Error> {
+        block_on(request(&self.cmd_tx, move |cb| SessionCommand::PopAlerts {
+            cb,
+        }))
     }
 
-    pub fn remove_torrent(&self, handle: TorrentHandle) {
-        let (cb_tx, cb_rx) = channel();
-        let _ = self
-            .cmd_tx
-            .send(SessionCommand::RemoveTorrent { handle, cb: cb_tx });
-        cb_rx.recv().unwrap()
+    pub fn wait_for_alert(&self, max_duration: Duration) -> Result<(), Error> {
+        block_on(request(&self.cmd_tx, move |cb| {
+            SessionCommand::WaitForAlert { max_duration, cb }
+        }))
     }
 
-    pub fn handle_alerts(&self, f: impl FnOnce(Vec<Alert>) + Send + Sync + 'static) {
-        let (cb_tx, cb_rx) = channel();
-        let _ = self.cmd_tx.send(SessionCommand::HandleAlerts {
+    /// Applies the settings set in the pack, other settings are left unchanged
+    pub fn apply_settings(&self, settings: SettingsPack) -> Result<(), Error> {
+        block_on(request(&self.cmd_tx, move |cb| {
+            SessionCommand::ApplySettings { settings, cb }
+        }))?
+    }
+
+    /// Returns every setting of the session
+    pub fn get_settings(&self) -> Result<SettingsPack, Error> {
+        block_on(request(&self.cmd_tx, move |cb| {
+            SessionCommand::GetSettings { cb }
+        }))
+    }
+
+    /// Saves the parts of the session state selected by `flags`, to be
+    /// restored with [`SessionBuilder::from_saved_state`]
+    pub fn save_state(&self, flags: SaveStateFlags) -> Result<Vec<u8>, Error> {
+        block_on(request(&self.cmd_tx, move |cb| SessionCommand::SaveState {
+            flags,
+            cb,
+        }))?
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        // already sent if the session was aborted explicitly
+        if self.thread.is_some() {
+            let _ = self.cmd_tx.send(SessionCommand::Abort);
+        }
+    }
+}
+
+/// Non-blocking versions of the session commands. The returned futures are
+/// runtime agnostic and resolve once the session thread has replied.
+#[cfg(feature = "async")]
+impl Session {
+    pub fn add_torrent_async(
+        &self,
+        params: AddTorrentParams,
+    ) -> impl Future<Output = Result<TorrentHandle, Error>> {
+        let reply = request(&self.cmd_tx, move |cb| SessionCommand::AddTorrent {
+            params: Box::new(params),
+            cb,
+        });
+        async move { reply.await? }
+    }
+
+    pub fn remove_torrent_async(
+        &self,
+        handle: TorrentHandle,
+    ) -> impl Future<Output = Result<(), Error>> {
+        let reply = request(&self.cmd_tx, move |cb| SessionCommand::RemoveTorrent {
+            handle,
+            cb,
+        });
+        async move { reply.await? }
+    }
+
+    pub fn torrents_async(&self) -> impl Future<Output = Result<Vec<TorrentHandle>, Error>> {
+        let reply = request(&self.cmd_tx, move |cb| SessionCommand::GetTorrents { cb });
+        async move { reply.await? }
+    }
+
+    pub fn handle_alerts_async(
+        &self,
+        f: impl FnOnce(Vec<Alert>) + Send + Sync + 'static,
+    ) -> impl Future<Output = Result<(), Error>> {
+        request(&self.cmd_tx, move |cb| SessionCommand::HandleAlerts {
             f: Box::new(f),
-            cb: cb_tx,
+            cb,
+        })
+    }
+
+    pub fn alerts_async(&self) -> impl Future<Output = Result<AlertStream, Error>> {
+        let (tx, rx) = unbounded();
+        let reply = request(&self.cmd_tx, move |cb| SessionCommand::SubscribeAlerts {
+            tx,
+            cb,
         });
-        cb_rx.recv().unwrap()
+        async move {
+            reply.await?;
+            Ok(AlertStream::new(rx))
+        }
+    }
+
+    pub fn pop_alerts_async(&self) -> impl Future<Output = Result<Vec<OwnedAlert>, Error>> {
+        request(&self.cmd_tx, move |cb| SessionCommand::PopAlerts { cb })
     }
 
-    pub fn wait_for_alert(&self, max_duration: Duration) {
-        let (cb_tx, cb_rx) = channel();
-        let _ = self.cmd_tx.send(SessionCommand::WaitForAlert {
+    /// Note that the session thread is blocked for up to `max_duration`,
+    /// delaying other commands.
+    pub fn wait_for_alert_async(
+        &self,
+        max_duration: Duration,
+    ) -> impl Future<Output = Result<(), Error>> {
+        request(&self.cmd_tx, move |cb| SessionCommand::WaitForAlert {
             max_duration,
-            cb: cb_tx,
+            cb,
+        })
+    }
+
+    pub fn apply_settings_async(
+        &self,
+        settings: SettingsPack,
+    ) -> impl Future<Output = Result<(), Error>> {
+        let reply = request(&self.cmd_tx, move |cb| SessionCommand::ApplySettings {
+            settings,
+            cb,
+        });
+        async move { reply.await? }
+    }
+
+    pub fn pause_async(&self) -> impl Future<Output = Result<(), Error>> {
+        request(&self.cmd_tx, move |cb| SessionCommand::Pause { cb })
+    }
+
+    pub fn get_settings_async(&self) -> impl Future<Output = Result<SettingsPack, Error>> {
+        request(&self.cmd_tx, move |cb| SessionCommand::GetSettings { cb })
+    }
+
+    pub fn save_state_async(
+        &self,
+        flags: SaveStateFlags,
+    ) -> impl Future<Output = Result<Vec<u8>, Error>> {
+        let reply = request(&self.cmd_tx, move |cb| SessionCommand::SaveState {
+            flags,
+            cb,
         });
-        cb_rx.recv().unwrap()
+        async move { reply.await? }
     }
 }
 
-impl TorrentHandleTrait for TorrentHandle {
-    fn get_name(&self) -> Option<String> {
-        let (cb_tx, cb_rx) = channel();
-        if self
-            .cmd_tx
-            .send(SessionCommand::GetTorrentName {
-                i: self.i,
-                cb: cb_tx,
+/// Non-blocking versions of the torrent handle queries, run on the session
+/// thread like the session commands
+#[cfg(feature = "async")]
+impl TorrentHandle {
+    /// Runs `f` with a clone of the handle on the session thread
+    fn query<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&TorrentHandle) -> Result<T, Error> + Send + 'static,
+    ) -> impl Future<Output = Result<T, Error>> {
+        let handle = self.clone();
+        let reply = self.session.cmd_tx.upgrade().map(|cmd_tx| {
+            request(&cmd_tx, move |cb| SessionCommand::QueryTorrent {
+                f: Box::new(move || {
+                    let _ = cb.send(f(&handle));
+                }),
             })
-            .is_ok()
-        {
-            Some(cb_rx.recv().unwrap())
-        } else {
-            None
+        });
+        async move {
+            match reply {
+                Some(reply) => reply.await?,
+                None => Err(Error::SessionClosed),
+            }
         }
     }
 
-    fn get_status(&self) -> Option<TorrentStatus> {
-        let (cb_tx, cb_rx) = channel();
-        if self
-            .cmd_tx
-            .send(SessionCommand::GetTorrentStatus {
-                i: self.i,
-                cb: cb_tx,
-            })
-            .is_ok()
-        {
-            Some(cb_rx.recv().unwrap())
-        } else {
-            None
-        }
+    pub fn get_name_async(&self) -> impl Future<Output = Result<String, Error>> {
+        self.query(|handle| handle.get_name())
     }
-}
 
-pub struct TorrentInfo {
-    inner: UniquePtr<torrent_info>,
-}
+    pub fn get_status_async(&self) -> impl Future<Output = Result<TorrentStatus, Error>> {
+        self.query(|handle| handle.get_status())
+    }
 
-impl TorrentInfo {
-    pub fn open(path: &str) -> Self {
-        Self {
-            inner: ffi::open_torrent_info(path),
-        }
+    pub fn get_status_with_async(
+        &self,
+        flags: StatusFlags,
+    ) -> impl Future<Output = Result<TorrentStatus, Error>> {
+        self.query(move |handle| handle.get_status_with(flags))
+    }
+
+    pub fn has_metadata_async(&self) -> impl Future<Output = Result<bool, Error>> {
+        self.query(|handle| handle.has_metadata())
+    }
+
+    pub fn make_magnet_uri_async(&self) -> impl Future<Output = Result<MagnetUri, Error>> {
+        self.query(|handle| handle.make_magnet_uri())
     }
 
-    pub fn nodes(&self) -> impl Iterator<Item = (String, u16)> {
-        ffi::torrent_info_nodes(&self.inner)
-            .into_iter()
-            .map(|inner| (inner.hostname, inner.port))
+    pub fn torrent_file_async(&self) -> impl Future<Output = Result<Option<TorrentInfo>, Error>> {
+        self.query(|handle| handle.torrent_file())
     }
 
-    pub fn trackers(&self) -> Vec<AnnounceEntry> {
-        ffi::torrent_info_trackers(&self.inner)
+    pub fn save_resume_data_async(
+        &self,
+        flags: ResumeDataFlags,
+    ) -> impl Future<Output = Result<(), Error>> {
+        self.query(move |handle| handle.save_resume_data(flags))
     }
 }
@@ -0,0 +1,266 @@
+use crate::{InfoHash, ParseHashError, Sha256Hash};
+use std::{fmt, net::SocketAddr, ops::RangeInclusive, str::FromStr};
+
+/// Magnet link of a torrent, as described in BEP 9 and BEP 53.
+///
+/// Parsed and rendered in Rust, following what `lt::parse_magnet_uri` and
+/// `lt::make_magnet_uri` accept and produce. Invalid peers and file ranges,
+/// and values with malformed percent-escapes, are ignored like libtorrent
+/// does, unknown parameters are dropped. Parameter names are
+/// case-insensitive.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MagnetUri {
+    /// `xt`, `urn:btih:` for the v1 hash and `urn:btmh:` for the v2 hash
+    pub info_hash: InfoHash,
+    /// `dn`, shown until the metadata has been received
+    pub display_name: Option<String>,
+    /// `tr`
+    pub trackers: Vec<String>,
+    /// `ws`
+    pub web_seeds: Vec<String>,
+    /// `x.pe`, peers to connect to
+    pub peers: Vec<SocketAddr>,
+    /// `so`, indices of the files to download, all files if empty
+    pub select_only: Vec<RangeInclusive<u32>>,
+    /// `xs`, urls of the .torrent file
+    pub exact_sources: Vec<String>,
+}
+
+/// Error parsing a [`MagnetUri`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseMagnetError {
+    /// The uri does not start with `magnet:?`
+    NotMagnet,
+    /// Neither a `btih` nor a `btmh` exact topic
+    MissingInfoHash,
+    InvalidInfoHash(ParseHashError),
+    /// The exact topic has a malformed percent-escape
+    InvalidEncoding,
+}
+
+impl fmt::Display for ParseMagnetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotMagnet => write!(f, "not a magnet link"),
+            Self::MissingInfoHash => write!(f, "missing info-hash in magnet link"),
+            Self::InvalidInfoHash(e) => write!(f, "invalid info-hash in magnet link: {e}"),
+            Self::InvalidEncoding => write!(f, "invalid percent-encoding in magnet link"),
+        }
+    }
+}
+
+impl std::error::Error for ParseMagnetError {}
+
+impl From<ParseHashError> for ParseMagnetError {
+    fn from(e: ParseHashError) -> Self {
+        Self::InvalidInfoHash(e)
+    }
+}
+
+const MAGNET_PREFIX: &str = "magnet:?";
+
+impl MagnetUri {
+    pub fn new(info_hash: InfoHash) -> Self {
+        Self {
+            info_hash,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the `so` parameter selects the file at `index`
+    pub fn is_selected(&self, index: u32) -> bool {
+        self.select_only.is_empty() || self.select_only.iter().any(|r| r.contains(&index))
+    }
+}
+
+impl FromStr for MagnetUri {
+    type Err = ParseMagnetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let query = s
+            .strip_prefix(MAGNET_PREFIX)
+            .ok_or(ParseMagnetError::NotMagnet)?;
+
+        let mut magnet = Self::default();
+
+        for param in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+
+            // x.pe and so are not unescaped by libtorrent
+            match key.to_ascii_lowercase().as_str() {
+                "xt" => {
+                    let value = percent_decode(value).ok_or(ParseMagnetError::InvalidEncoding)?;
+                    if let Some(hash) = strip_prefix_no_case(&value, "urn:btih:") {
+                        magnet.info_hash.v1 = Some(hash.parse()?);
+                    } else if let Some(hash) = strip_prefix_no_case(&value, "urn:btmh:") {
+                        magnet.info_hash.v2 = Some(parse_sha256_multihash(hash)?);
+                    }
+                }
+                "dn" => {
+                    if let Some(value) = percent_decode(value) {
+                        magnet.display_name = Some(value).filter(|v| !v.is_empty());
+                    }
+                }
+                "tr" => magnet
+                    .trackers
+                    .extend(percent_decode(value).filter(|v| !v.is_empty())),
+                "ws" => magnet.web_seeds.extend(percent_decode(value)),
+                "x.pe" => magnet.peers.extend(value.parse::<SocketAddr>()),
+                // libtorrent ignores the whole list if it has other characters
+                "so" if value
+                    .bytes()
+                    .all(|c| c.is_ascii_digit() || c == b'-' || c == b',') =>
+                {
+                    magnet
+                        .select_only
+                        .extend(value.split(',').filter_map(parse_file_range))
+                }
+                "xs" => magnet.exact_sources.extend(percent_decode(value)),
+                _ => {}
+            }
+        }
+
+        if magnet.info_hash.is_empty() {
+            return Err(ParseMagnetError::MissingInfoHash);
+        }
+
+        Ok(magnet)
+    }
+}
+
+impl fmt::Display for MagnetUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{MAGNET_PREFIX}")?;
+
+        let mut sep = "";
+        let mut param = |f: &mut fmt::Formatter<'_>, key: &str, value: &dyn fmt::Display| {
+            let res = write!(f, "{sep}{key}={value}");
+            sep = "&";
+            res
+        };
+
+        if let Some(v1) = &self.info_hash.v1 {
+            param(f, "xt", &format_args!("urn:btih:{v1}"))?;
+        }
+        if let Some(v2) = &self.info_hash.v2 {
+            param(f, "xt", &format_args!("urn:btmh:{}", InfoHash::v2(*v2)))?;
+        }
+        if let Some(name) = &self.display_name {
+            param(f, "dn", &PercentEncode(name))?;
+        }
+        for tracker in &self.trackers {
+            param(f, "tr", &PercentEncode(tracker))?;
+        }
+        for web_seed in &self.web_seeds {
+            param(f, "ws", &PercentEncode(web_seed))?;
+        }
+        for peer in &self.peers {
+            param(f, "x.pe", peer)?;
+        }
+        if !self.select_only.is_empty() {
+            param(f, "so", &FileRanges(&self.select_only))?;
+        }
+        for source in &self.exact_sources {
+            param(f, "xs", &PercentEncode(source))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn strip_prefix_no_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    match s.get(..prefix.len()) {
+        Some(p) if p.eq_ignore_ascii_case(prefix) => Some(&s[prefix.len()..]),
+        _ => None,
+    }
+}
+
+/// Parses the hex SHA-256 multihash of a `urn:btmh:` exact topic, the only
+/// form libtorrent accepts there
+fn parse_sha256_multihash(s: &str) -> Result<Sha256Hash, ParseHashError> {
+    if s.len() != 68 {
+        return Err(ParseHashError::InvalidLength(s.len()));
+    }
+    match s.split_at(4) {
+        ("1220", digest) => digest.parse(),
+        _ => Err(ParseHashError::UnsupportedMultihash),
+    }
+}
+
+/// Largest file index libtorrent accepts in `so`, to bound the size of the
+/// file priorities it allocates
+const MAX_SELECT_ONLY_INDEX: u32 = 10000;
+
+/// Parses `index` or `first-last`
+fn parse_file_range(s: &str) -> Option<RangeInclusive<u32>> {
+    let (first, last) = match s.split_once('-') {
+        Some((first, last)) => (first.parse().ok()?, last.parse().ok()?),
+        None => {
+            let index = s.parse().ok()?;
+            (index, index)
+        }
+    };
+
+    (first <= last && last <= MAX_SELECT_ONLY_INDEX).then_some(first..=last)
+}
+
+struct FileRanges<'a>(&'a [RangeInclusive<u32>]);
+
+impl fmt::Display for FileRanges<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, range) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            if range.start() == range.end() {
+                write!(f, "{}", range.start())?;
+            } else {
+                write!(f, "{}-{}", range.start(), range.end())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decodes like `lt::unescape_string`, `+` is a space. `None` if an escape is
+/// malformed. Invalid UTF-8 is replaced, libtorrent keeps the raw bytes.
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = s.bytes();
+    let mut out = Vec::with_capacity(s.len());
+
+    while let Some(b) = bytes.next() {
+        match b {
+            b'+' => out.push(b' '),
+            b'%' => match (
+                bytes.next().and_then(hex_value),
+                bytes.next().and_then(hex_value),
+            ) {
+                (Some(hi), Some(lo)) => out.push(hi << 4 | lo),
+                _ => return None,
+            },
+            _ => out.push(b),
+        }
+    }
+
+    Some(String::from_utf8_lossy(&out).into_owned())
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|v| v as u8)
+}
+
+/// Escapes everything but the unreserved characters of RFC 3986
+struct PercentEncode<'a>(&'a str);
+
+impl fmt::Display for PercentEncode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for &b in self.0.as_bytes() {
+            if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+                write!(f, "{}", b as char)?;
+            } else {
+                write!(f, "%{b:02X}")?;
+            }
+        }
+        Ok(())
+    }
+}
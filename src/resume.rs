@@ -0,0 +1,380 @@
+use crate::{
+    request, AddTorrentParams, AlertKind, Error, InfoHash, OwnedAlert, ResumeDataFlags, Session,
+    SessionCommand, TorrentHandle,
+};
+use futures::{
+    channel::oneshot,
+    executor::block_on,
+    future::{self, Either},
+    StreamExt,
+};
+use std::{
+    collections::HashSet,
+    fs, io,
+    io::Write as _,
+    panic,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+/// Storage for the resume data of the torrents of a session, keyed by
+/// info-hash
+pub trait ResumeDataStore: Send {
+    /// Returns the resume data of every stored torrent. Fails if the store
+    /// can't be listed, entries that can't be read are reported individually.
+    fn load_all(&self) -> io::Result<Vec<io::Result<Vec<u8>>>>;
+
+    /// Stores the resume data of a torrent, replacing what was stored before
+    fn save(&self, info_hash: &InfoHash, resume_data: &[u8]) -> io::Result<()>;
+
+    /// Removes the resume data of a torrent, succeeds if none was stored
+    fn remove(&self, info_hash: &InfoHash) -> io::Result<()>;
+}
+
+/// Stores resume data in a directory, one `<infohash>.fastresume` file per
+/// torrent. Torrents with a v1 info-hash are named after it, others after
+/// their v2 info-hash.
+#[derive(Clone, Debug)]
+pub struct FsResumeStore {
+    dir: PathBuf,
+}
+
+const FASTRESUME_EXTENSION: &str = "fastresume";
+
+impl FsResumeStore {
+    /// Opens the store, creating the directory if it doesn't exist
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        Ok(Self { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the file the resume data of a torrent is stored in
+    pub fn path(&self, info_hash: &InfoHash) -> PathBuf {
+        let name = match info_hash.v1 {
+            Some(v1) => v1.to_string(),
+            None => info_hash.v2.unwrap_or_default().to_string(),
+        };
+
+        self.dir.join(name).with_extension(FASTRESUME_EXTENSION)
+    }
+}
+
+impl ResumeDataStore for FsResumeStore {
+    fn load_all(&self) -> io::Result<Vec<io::Result<Vec<u8>>>> {
+        let mut out = Vec::new();
+
+        for entry in fs::read_dir(&self.dir)? {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    out.push(Err(e));
+                    continue;
+                }
+            };
+            if path.extension() == Some(FASTRESUME_EXTENSION.as_ref()) {
+                out.push(fs::read(path));
+            }
+        }
+
+        Ok(out)
+    }
+
+    fn save(&self, info_hash: &InfoHash, resume_data: &[u8]) -> io::Result<()> {
+        let path = self.path(info_hash);
+        let tmp = path.with_extension("fastresume.tmp");
+
+        // write to a temporary file first, so a crash never leaves a
+        // truncated file behind
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(resume_data)?;
+        file.sync_all()?;
+        drop(file);
+
+        fs::rename(tmp, path)
+    }
+
+    fn remove(&self, info_hash: &InfoHash) -> io::Result<()> {
+        match fs::remove_file(self.path(info_hash)) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            res => res,
+        }
+    }
+}
+
+impl Session {
+    /// Adds every torrent of the store to the session.
+    ///
+    /// Fails if the store can't be listed, torrents that can't be read or
+    /// added are reported individually.
+    pub fn restore_torrents(
+        &self,
+        store: &dyn ResumeDataStore,
+    ) -> Result<Vec<Result<TorrentHandle, Error>>, Error> {
+        Ok(store
+            .load_all()?
+            .into_iter()
+            .map(|resume_data| self.add_torrent(AddTorrentParams::from_resume_data(resume_data?)))
+            .collect())
+    }
+
+    /// Pauses the session, saves the resume data of every torrent that
+    /// changed through `saver` and shuts the session down, see
+    /// [`Session::shutdown`]. Returns the first error of pausing the session
+    /// or of the saver, if any, and whether the session shut down within
+    /// `timeout`.
+    pub fn shutdown_with_saver(
+        self,
+        saver: ResumeDataSaver,
+        timeout: Duration,
+    ) -> (Result<(), Error>, bool) {
+        let paused = self.pause();
+        let saved = saver.stop();
+
+        (paused.and(saved), self.abort().wait_timeout(timeout))
+    }
+
+    /// Starts saving the resume data of the torrents of the session to
+    /// `store`, see [`ResumeDataSaver`].
+    ///
+    /// The saver subscribes to the alerts of the session, so while it runs
+    /// [`Session::pop_alerts`] and [`Session::handle_alerts`] return nothing.
+    /// Use [`Session::alerts`] to receive alerts alongside the saver.
+    pub fn start_resume_data_saver(
+        &self,
+        store: impl ResumeDataStore + 'static,
+        interval: Duration,
+    ) -> Result<ResumeDataSaver, Error> {
+        let mut alerts = self.alerts()?;
+        let (tx, rx) = channel();
+        let (stop_forwarding, mut stopped) = oneshot::channel::<()>();
+
+        // forwards alerts until the session shuts down or the saver stops,
+        // dropping the stream closes the subscription
+        let forwarder = std::thread::spawn({
+            let tx = tx.clone();
+            move || {
+                while let Either::Left((Some(alert), _)) =
+                    block_on(future::select(alerts.next(), &mut stopped))
+                {
+                    if tx.send(SaverMsg::Alert(Box::new(alert))).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        let thread = std::thread::spawn({
+            let cmd_tx = Sender::clone(&self.cmd_tx);
+            move || {
+                let mut saver = Saver {
+                    cmd_tx,
+                    store,
+                    first_error: None,
+                };
+                let mut next_save = Instant::now() + interval;
+
+                loop {
+                    let timeout = next_save.saturating_duration_since(Instant::now());
+
+                    match rx.recv_timeout(timeout) {
+                        Ok(SaverMsg::Alert(alert)) => {
+                            saver.handle_alert(&alert);
+                        }
+                        Ok(SaverMsg::Stop { flush: true }) => {
+                            match saver.request_saves() {
+                                Ok(pending) => saver.wait_for_saves(&rx, pending),
+                                Err(e) => {
+                                    saver.first_error.get_or_insert(e);
+                                }
+                            }
+                            break;
+                        }
+                        Ok(SaverMsg::Stop { flush: false }) => break,
+                        Err(RecvTimeoutError::Timeout) => {
+                            match saver.request_saves() {
+                                Ok(_) => {}
+                                Err(Error::SessionClosed) => break,
+                                Err(e) => {
+                                    saver.first_error.get_or_insert(e);
+                                }
+                            }
+                            next_save = Instant::now() + interval;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+
+                match saver.first_error {
+                    Some(e) => Err(e),
+                    None => Ok(()),
+                }
+            }
+        });
+
+        Ok(ResumeDataSaver {
+            tx,
+            thread: Some(thread),
+            stop_forwarding: Some(stop_forwarding),
+            forwarder: Some(forwarder),
+        })
+    }
+}
+
+enum SaverMsg {
+    Alert(Box<OwnedAlert>),
+    Stop { flush: bool },
+}
+
+/// How long [`ResumeDataSaver::stop`] waits for libtorrent to answer the last
+/// save requests
+pub const RESUME_DATA_FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Saves the resume data of the torrents of a session in the background.
+///
+/// Every `interval`, resume data is requested for the torrents that changed
+/// since it was last saved. Resume data posted by libtorrent is written to the
+/// store, and removed from it when its torrent is removed from the session.
+///
+/// Dropping the saver stops it without saving pending changes, use
+/// [`ResumeDataSaver::stop`] before shutting the session down.
+pub struct ResumeDataSaver {
+    tx: Sender<SaverMsg>,
+    thread: Option<JoinHandle<Result<(), Error>>>,
+    stop_forwarding: Option<oneshot::Sender<()>>,
+    forwarder: Option<JoinHandle<()>>,
+}
+
+impl ResumeDataSaver {
+    /// Saves the resume data of every torrent that changed and stops, once
+    /// it has been written to the store or [`RESUME_DATA_FLUSH_TIMEOUT`] has
+    /// elapsed. Returns the first error the store reported since the saver
+    /// was started, or an error naming the torrents whose resume data did not
+    /// arrive in time.
+    ///
+    /// Panics if the saver thread panicked, with the same payload.
+    pub fn stop(mut self) -> Result<(), Error> {
+        let _ = self.tx.send(SaverMsg::Stop { flush: true });
+        let res = self.thread.take().map(JoinHandle::join);
+        // the saver needs the alerts until it has flushed
+        self.stop_forwarding();
+
+        match res {
+            Some(Ok(res)) => res,
+            Some(Err(payload)) => panic::resume_unwind(payload),
+            None => Ok(()),
+        }
+    }
+
+    /// Closes the alert subscription and waits for the thread forwarding
+    /// alerts to exit
+    fn stop_forwarding(&mut self) {
+        drop(self.stop_forwarding.take());
+        if let Some(forwarder) = self.forwarder.take() {
+            let _ = forwarder.join();
+        }
+    }
+}
+
+impl Drop for ResumeDataSaver {
+    fn drop(&mut self) {
+        if self.thread.is_some() {
+            let _ = self.tx.send(SaverMsg::Stop { flush: false });
+        }
+        self.stop_forwarding();
+    }
+}
+
+struct Saver<S> {
+    cmd_tx: Sender<SessionCommand>,
+    store: S,
+    first_error: Option<Error>,
+}
+
+impl<S: ResumeDataStore> Saver<S> {
+    /// Asks for the resume data of every torrent that changed, returns the
+    /// torrents an alert is expected for
+    fn request_saves(&mut self) -> Result<HashSet<TorrentHandle>, Error> {
+        let torrents = block_on(request(&self.cmd_tx, |cb| SessionCommand::GetTorrents {
+            cb,
+        }))??;
+
+        let flags = ResumeDataFlags::SAVE_INFO_DICT | ResumeDataFlags::ONLY_IF_MODIFIED;
+
+        // handles of torrents removed in the meantime fail, no alert is
+        // posted for them
+        Ok(torrents
+            .into_iter()
+            .filter(|handle| handle.save_resume_data(flags).is_ok())
+            .collect())
+    }
+
+    /// Handles alerts until the save resume data requests of the `pending`
+    /// torrents have been answered, the session has shut down or
+    /// [`RESUME_DATA_FLUSH_TIMEOUT`] has elapsed
+    fn wait_for_saves(&mut self, rx: &Receiver<SaverMsg>, mut pending: HashSet<TorrentHandle>) {
+        let deadline = Instant::now() + RESUME_DATA_FLUSH_TIMEOUT;
+
+        while !pending.is_empty() {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+
+            match rx.recv_timeout(timeout) {
+                Ok(SaverMsg::Alert(alert)) => {
+                    if self.handle_alert(&alert) {
+                        if let Some(handle) = &alert.handle {
+                            pending.remove(handle);
+                        }
+                    }
+                }
+                Ok(SaverMsg::Stop { .. }) => {}
+                Err(RecvTimeoutError::Timeout) => {
+                    let missing = pending
+                        .iter()
+                        .map(|handle| handle.info_hashes().to_string())
+                        .collect::<Vec<_>>();
+                    self.first_error.get_or_insert(Error::Io {
+                        kind: io::ErrorKind::TimedOut,
+                        message: format!("no resume data received for {}", missing.join(" ")),
+                    });
+                    break;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    }
+
+    /// Returns `true` if the alert answers a save resume data request
+    fn handle_alert(&mut self, alert: &OwnedAlert) -> bool {
+        match &alert.kind {
+            AlertKind::SaveResumeData {
+                info_hash,
+                resume_data,
+            } => {
+                let res = self.store.save(info_hash, resume_data);
+                self.record(res);
+                true
+            }
+            // includes torrents that did not change since they were last saved
+            AlertKind::SaveResumeDataFailed { .. } => true,
+            AlertKind::TorrentRemoved { info_hash } => {
+                let res = self.store.remove(info_hash);
+                self.record(res);
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn record(&mut self, res: io::Result<()>) {
+        if let Err(e) = res {
+            self.first_error.get_or_insert(e.into());
+        }
+    }
+}
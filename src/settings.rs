@@ -0,0 +1,485 @@
+use crate::Error;
+use rbtorrent_sys::ffi::{self, SettingKind};
+use std::{collections::BTreeMap, pin::Pin};
+
+/// Value of a single setting
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SettingValue {
+    Int(i32),
+    Bool(bool),
+    Str(String),
+}
+
+/// Algorithm used to decide which peers to unchoke, mirroring
+/// `lt::settings_pack::choking_algorithm_t`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChokingAlgorithm {
+    /// Unchoke a fixed number of peers, set by ``unchoke_slots_limit``
+    FixedSlots,
+    /// Open up unchoke slots based on the upload rate achieved to peers
+    RateBased,
+}
+
+impl From<ChokingAlgorithm> for i32 {
+    fn from(v: ChokingAlgorithm) -> Self {
+        match v {
+            ChokingAlgorithm::FixedSlots => 0,
+            ChokingAlgorithm::RateBased => 2,
+        }
+    }
+}
+
+impl TryFrom<i32> for ChokingAlgorithm {
+    type Error = i32;
+
+    fn try_from(v: i32) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(Self::FixedSlots),
+            2 => Ok(Self::RateBased),
+            other => Err(other),
+        }
+    }
+}
+
+/// Algorithm used to decide which peers to unchoke while seeding, mirroring
+/// `lt::settings_pack::seed_choking_algorithm_t`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SeedChokingAlgorithm {
+    RoundRobin,
+    FastestUpload,
+    AntiLeech,
+}
+
+impl From<SeedChokingAlgorithm> for i32 {
+    fn from(v: SeedChokingAlgorithm) -> Self {
+        match v {
+            SeedChokingAlgorithm::RoundRobin => 0,
+            SeedChokingAlgorithm::FastestUpload => 1,
+            SeedChokingAlgorithm::AntiLeech => 2,
+        }
+    }
+}
+
+impl TryFrom<i32> for SeedChokingAlgorithm {
+    type Error = i32;
+
+    fn try_from(v: i32) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(Self::RoundRobin),
+            1 => Ok(Self::FastestUpload),
+            2 => Ok(Self::AntiLeech),
+            other => Err(other),
+        }
+    }
+}
+
+/// Whether to use protocol encryption, mirroring `lt::settings_pack::enc_policy`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EncPolicy {
+    /// Only accept encrypted connections
+    Forced,
+    /// Accept both encrypted and plaintext connections
+    Enabled,
+    /// Only accept plaintext connections
+    Disabled,
+}
+
+impl From<EncPolicy> for i32 {
+    fn from(v: EncPolicy) -> Self {
+        match v {
+            EncPolicy::Forced => 0,
+            EncPolicy::Enabled => 1,
+            EncPolicy::Disabled => 2,
+        }
+    }
+}
+
+impl TryFrom<i32> for EncPolicy {
+    type Error = i32;
+
+    fn try_from(v: i32) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(Self::Forced),
+            1 => Ok(Self::Enabled),
+            2 => Ok(Self::Disabled),
+            other => Err(other),
+        }
+    }
+}
+
+/// Encryption levels allowed, mirroring `lt::settings_pack::enc_level`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EncLevel {
+    Plaintext,
+    Rc4,
+    Both,
+}
+
+impl From<EncLevel> for i32 {
+    fn from(v: EncLevel) -> Self {
+        match v {
+            EncLevel::Plaintext => 1,
+            EncLevel::Rc4 => 2,
+            EncLevel::Both => 3,
+        }
+    }
+}
+
+impl TryFrom<i32> for EncLevel {
+    type Error = i32;
+
+    fn try_from(v: i32) -> Result<Self, Self::Error> {
+        match v {
+            1 => Ok(Self::Plaintext),
+            2 => Ok(Self::Rc4),
+            3 => Ok(Self::Both),
+            other => Err(other),
+        }
+    }
+}
+
+/// Type of proxy to connect through, mirroring `lt::settings_pack::proxy_type_t`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProxyType {
+    None,
+    Socks4,
+    Socks5,
+    /// SOCKS5 with username and password authentication
+    Socks5Pw,
+    Http,
+    /// HTTP with basic authentication
+    HttpPw,
+}
+
+impl From<ProxyType> for i32 {
+    fn from(v: ProxyType) -> Self {
+        match v {
+            ProxyType::None => 0,
+            ProxyType::Socks4 => 1,
+            ProxyType::Socks5 => 2,
+            ProxyType::Socks5Pw => 3,
+            ProxyType::Http => 4,
+            ProxyType::HttpPw => 5,
+        }
+    }
+}
+
+impl TryFrom<i32> for ProxyType {
+    type Error = i32;
+
+    fn try_from(v: i32) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Socks4),
+            2 => Ok(Self::Socks5),
+            3 => Ok(Self::Socks5Pw),
+            4 => Ok(Self::Http),
+            5 => Ok(Self::HttpPw),
+            other => Err(other),
+        }
+    }
+}
+
+/// Proxy configuration, see [`SettingsPack::with_proxy`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ProxySettings {
+    pub proxy_type: ProxyType,
+    pub hostname: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    /// Resolve hostnames through the proxy
+    pub proxy_hostnames: bool,
+    pub proxy_peer_connections: bool,
+    pub proxy_tracker_connections: bool,
+}
+
+/// Set of session settings, mirroring `lt::settings_pack`.
+///
+/// Settings are identified by their libtorrent name. Only the settings that
+/// were set are applied. Unknown names and values of the wrong type are
+/// rejected when they are set.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SettingsPack {
+    values: BTreeMap<String, SettingValue>,
+}
+
+impl SettingsPack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_int(self, name: &str, value: i32) -> Result<Self, Error> {
+        self.with_checked(name, SettingKind::Int, SettingValue::Int(value))
+    }
+
+    pub fn with_bool(self, name: &str, value: bool) -> Result<Self, Error> {
+        self.with_checked(name, SettingKind::Bool, SettingValue::Bool(value))
+    }
+
+    pub fn with_str(self, name: &str, value: String) -> Result<Self, Error> {
+        self.with_checked(name, SettingKind::Str, SettingValue::Str(value))
+    }
+
+    fn with_checked(
+        self,
+        name: &str,
+        kind: SettingKind,
+        value: SettingValue,
+    ) -> Result<Self, Error> {
+        let actual = ffi::setting_kind(name)
+            .map_err(|_| Error::InvalidArgument(format!("unknown setting: {name}")))?;
+        if actual != kind {
+            return Err(Error::InvalidArgument(format!(
+                "setting {name} is not of type {kind:?}"
+            )));
+        }
+
+        Ok(self.with(name, value))
+    }
+
+    /// Sets a setting whose name and type are known to be valid
+    fn with(mut self, name: &str, value: SettingValue) -> Self {
+        self.values.insert(name.to_string(), value);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SettingValue> {
+        self.values.get(name)
+    }
+
+    pub fn get_int(&self, name: &str) -> Option<i32> {
+        match self.values.get(name)? {
+            SettingValue::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        match self.values.get(name)? {
+            SettingValue::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        match self.values.get(name)? {
+            SettingValue::Str(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &SettingValue)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Upload rate limit of the session in bytes per second, 0 is unlimited
+    pub fn with_upload_rate_limit(self, limit: i32) -> Self {
+        self.with("upload_rate_limit", SettingValue::Int(limit))
+    }
+
+    pub fn upload_rate_limit(&self) -> Option<i32> {
+        self.get_int("upload_rate_limit")
+    }
+
+    /// Download rate limit of the session in bytes per second, 0 is unlimited
+    pub fn with_download_rate_limit(self, limit: i32) -> Self {
+        self.with("download_rate_limit", SettingValue::Int(limit))
+    }
+
+    pub fn download_rate_limit(&self) -> Option<i32> {
+        self.get_int("download_rate_limit")
+    }
+
+    /// Maximum number of connections of the session
+    pub fn with_connections_limit(self, limit: i32) -> Self {
+        self.with("connections_limit", SettingValue::Int(limit))
+    }
+
+    pub fn connections_limit(&self) -> Option<i32> {
+        self.get_int("connections_limit")
+    }
+
+    /// Maximum number of unchoked peers, -1 is unlimited
+    pub fn with_unchoke_slots_limit(self, limit: i32) -> Self {
+        self.with("unchoke_slots_limit", SettingValue::Int(limit))
+    }
+
+    pub fn unchoke_slots_limit(&self) -> Option<i32> {
+        self.get_int("unchoke_slots_limit")
+    }
+
+    pub fn with_choking_algorithm(self, algorithm: ChokingAlgorithm) -> Self {
+        self.with("choking_algorithm", SettingValue::Int(algorithm.into()))
+    }
+
+    pub fn choking_algorithm(&self) -> Option<ChokingAlgorithm> {
+        self.get_int("choking_algorithm")?.try_into().ok()
+    }
+
+    pub fn with_seed_choking_algorithm(self, algorithm: SeedChokingAlgorithm) -> Self {
+        self.with(
+            "seed_choking_algorithm",
+            SettingValue::Int(algorithm.into()),
+        )
+    }
+
+    pub fn seed_choking_algorithm(&self) -> Option<SeedChokingAlgorithm> {
+        self.get_int("seed_choking_algorithm")?.try_into().ok()
+    }
+
+    /// Maximum number of auto managed torrents downloading, -1 is unlimited
+    pub fn with_active_downloads(self, limit: i32) -> Self {
+        self.with("active_downloads", SettingValue::Int(limit))
+    }
+
+    pub fn active_downloads(&self) -> Option<i32> {
+        self.get_int("active_downloads")
+    }
+
+    /// Maximum number of auto managed torrents seeding, -1 is unlimited
+    pub fn with_active_seeds(self, limit: i32) -> Self {
+        self.with("active_seeds", SettingValue::Int(limit))
+    }
+
+    pub fn active_seeds(&self) -> Option<i32> {
+        self.get_int("active_seeds")
+    }
+
+    /// Maximum number of auto managed torrents checking files
+    pub fn with_active_checking(self, limit: i32) -> Self {
+        self.with("active_checking", SettingValue::Int(limit))
+    }
+
+    pub fn active_checking(&self) -> Option<i32> {
+        self.get_int("active_checking")
+    }
+
+    /// Maximum number of active auto managed torrents, downloading and
+    /// seeding, -1 is unlimited
+    pub fn with_active_limit(self, limit: i32) -> Self {
+        self.with("active_limit", SettingValue::Int(limit))
+    }
+
+    pub fn active_limit(&self) -> Option<i32> {
+        self.get_int("active_limit")
+    }
+
+    pub fn with_enable_dht(self, enable: bool) -> Self {
+        self.with("enable_dht", SettingValue::Bool(enable))
+    }
+
+    pub fn enable_dht(&self) -> Option<bool> {
+        self.get_bool("enable_dht")
+    }
+
+    pub fn with_enable_lsd(self, enable: bool) -> Self {
+        self.with("enable_lsd", SettingValue::Bool(enable))
+    }
+
+    pub fn enable_lsd(&self) -> Option<bool> {
+        self.get_bool("enable_lsd")
+    }
+
+    pub fn with_enable_upnp(self, enable: bool) -> Self {
+        self.with("enable_upnp", SettingValue::Bool(enable))
+    }
+
+    pub fn enable_upnp(&self) -> Option<bool> {
+        self.get_bool("enable_upnp")
+    }
+
+    pub fn with_enable_natpmp(self, enable: bool) -> Self {
+        self.with("enable_natpmp", SettingValue::Bool(enable))
+    }
+
+    pub fn enable_natpmp(&self) -> Option<bool> {
+        self.get_bool("enable_natpmp")
+    }
+
+    /// Encryption policy of outgoing and incoming connections
+    pub fn with_enc_policy(self, outgoing: EncPolicy, incoming: EncPolicy) -> Self {
+        self.with("out_enc_policy", SettingValue::Int(outgoing.into()))
+            .with("in_enc_policy", SettingValue::Int(incoming.into()))
+    }
+
+    pub fn out_enc_policy(&self) -> Option<EncPolicy> {
+        self.get_int("out_enc_policy")?.try_into().ok()
+    }
+
+    pub fn in_enc_policy(&self) -> Option<EncPolicy> {
+        self.get_int("in_enc_policy")?.try_into().ok()
+    }
+
+    pub fn with_allowed_enc_level(self, level: EncLevel) -> Self {
+        self.with("allowed_enc_level", SettingValue::Int(level.into()))
+    }
+
+    pub fn allowed_enc_level(&self) -> Option<EncLevel> {
+        self.get_int("allowed_enc_level")?.try_into().ok()
+    }
+
+    pub fn with_proxy(self, proxy: ProxySettings) -> Self {
+        self.with("proxy_type", SettingValue::Int(proxy.proxy_type.into()))
+            .with("proxy_hostname", SettingValue::Str(proxy.hostname))
+            .with("proxy_port", SettingValue::Int(proxy.port.into()))
+            .with("proxy_username", SettingValue::Str(proxy.username))
+            .with("proxy_password", SettingValue::Str(proxy.password))
+            .with("proxy_hostnames", SettingValue::Bool(proxy.proxy_hostnames))
+            .with(
+                "proxy_peer_connections",
+                SettingValue::Bool(proxy.proxy_peer_connections),
+            )
+            .with(
+                "proxy_tracker_connections",
+                SettingValue::Bool(proxy.proxy_tracker_connections),
+            )
+    }
+
+    /// The proxy configuration, if every proxy setting is in the pack
+    pub fn proxy(&self) -> Option<ProxySettings> {
+        Some(ProxySettings {
+            proxy_type: self.get_int("proxy_type")?.try_into().ok()?,
+            hostname: self.get_str("proxy_hostname")?.to_string(),
+            port: self.get_int("proxy_port")?.try_into().ok()?,
+            username: self.get_str("proxy_username")?.to_string(),
+            password: self.get_str("proxy_password")?.to_string(),
+            proxy_hostnames: self.get_bool("proxy_hostnames")?,
+            proxy_peer_connections: self.get_bool("proxy_peer_connections")?,
+            proxy_tracker_connections: self.get_bool("proxy_tracker_connections")?,
+        })
+    }
+
+    pub(crate) fn apply_to(&self, mut pack: Pin<&mut ffi::settings_pack>) -> Result<(), Error> {
+        for (name, value) in &self.values {
+            match value {
+                SettingValue::Int(v) => ffi::settings_pack_set_int(pack.as_mut(), name, *v)?,
+                SettingValue::Bool(v) => ffi::settings_pack_set_bool(pack.as_mut(), name, *v)?,
+                SettingValue::Str(v) => ffi::settings_pack_set_str(pack.as_mut(), name, v)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<Vec<ffi::SettingEntry>> for SettingsPack {
+    fn from(entries: Vec<ffi::SettingEntry>) -> Self {
+        let values = entries
+            .into_iter()
+            .filter_map(|e| {
+                let value = match e.kind {
+                    SettingKind::Int => SettingValue::Int(e.int_value),
+                    SettingKind::Bool => SettingValue::Bool(e.bool_value),
+                    SettingKind::Str => SettingValue::Str(e.str_value),
+                    _ => return None,
+                };
+                Some((e.name, value))
+            })
+            .collect();
+
+        Self { values }
+    }
+}
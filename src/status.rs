@@ -0,0 +1,262 @@
+use crate::{ErrorCode, InfoHash, TorrentFlags};
+use rbtorrent_sys::ffi::{self, storage_mode_t, torrent_state};
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+/// File an error of a torrent refers to, mirroring `lt::torrent_status::error_file`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorFile {
+    /// The error does not refer to a file
+    None,
+    /// The error occurred on the url of a torrent added by url
+    Url,
+    /// The error occurred setting up the SSL context
+    SslCtx,
+    /// The error occurred while loading the metadata
+    Metadata,
+    /// An exception was thrown while handling the torrent
+    Exception,
+    /// The error occurred accessing the part file
+    Partfile,
+    /// Index of the file the error occurred on
+    File(u32),
+    Other(i32),
+}
+
+impl From<i32> for ErrorFile {
+    fn from(v: i32) -> Self {
+        match v {
+            -1 => Self::None,
+            -2 => Self::Url,
+            -3 => Self::SslCtx,
+            -4 => Self::Metadata,
+            -5 => Self::Exception,
+            -6 => Self::Partfile,
+            index if index >= 0 => Self::File(index as u32),
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// Snapshot of the state of a torrent, mirroring `lt::torrent_status`.
+///
+/// Rates are in bytes per second, totals in bytes. Totals not prefixed with
+/// `all_time` only cover the current session.
+#[derive(Clone, Debug)]
+pub struct TorrentStatus {
+    pub info_hashes: InfoHash,
+    pub state: torrent_state,
+    /// Progress of the current task, in parts per million
+    pub progress_ppm: u32,
+    /// The error that caused the torrent to be paused, if any
+    pub error: Option<ErrorCode>,
+    pub error_file: ErrorFile,
+    /// Empty unless queried with [`StatusFlags::SAVE_PATH`](crate::StatusFlags::SAVE_PATH)
+    pub save_path: PathBuf,
+    /// Empty unless queried with [`StatusFlags::NAME`](crate::StatusFlags::NAME)
+    pub name: String,
+    /// Url of the tracker last announced to successfully
+    pub current_tracker: Option<String>,
+    /// Time until the next tracker announce
+    pub next_announce: Duration,
+    pub total_download: i64,
+    pub total_upload: i64,
+    pub total_payload_download: i64,
+    pub total_payload_upload: i64,
+    /// Bytes of pieces that failed the hash check
+    pub total_failed_bytes: i64,
+    /// Bytes downloaded more than once
+    pub total_redundant_bytes: i64,
+    /// Bytes of verified pieces, including pieces not wanted
+    pub total_done: i64,
+    /// Size of the torrent, excluding pad files
+    pub total: i64,
+    /// Bytes of verified pieces of the wanted files
+    pub total_wanted_done: i64,
+    /// Bytes of the wanted files
+    pub total_wanted: i64,
+    pub all_time_upload: i64,
+    pub all_time_download: i64,
+    pub added_time: Option<SystemTime>,
+    pub completed_time: Option<SystemTime>,
+    /// Last time a seed or the distributed copies covered every piece
+    pub last_seen_complete: Option<SystemTime>,
+    /// Last time payload was uploaded to a peer
+    pub last_upload: Option<SystemTime>,
+    /// Last time payload was downloaded from a peer
+    pub last_download: Option<SystemTime>,
+    /// Time the torrent has been started, not counting time paused
+    pub active_duration: Duration,
+    pub finished_duration: Duration,
+    pub seeding_duration: Duration,
+    pub storage_mode: storage_mode_t,
+    /// Position in the download queue, `-1` if the torrent is not queued
+    pub queue_position: i32,
+    pub download_rate: i32,
+    pub upload_rate: i32,
+    pub download_payload_rate: i32,
+    pub upload_payload_rate: i32,
+    /// Connected peers that are seeds
+    pub num_seeds: i32,
+    /// Connected peers, including seeds
+    pub num_peers: i32,
+    /// Seeds in the swarm according to the tracker, `-1` if unknown
+    pub num_complete: i32,
+    /// Downloaders in the swarm according to the tracker, `-1` if unknown
+    pub num_incomplete: i32,
+    /// Seeds in the peer list, connected or not
+    pub list_seeds: i32,
+    /// Peers in the peer list, connected or not
+    pub list_peers: i32,
+    /// Peers in the peer list that can be connected to
+    pub connect_candidates: i32,
+    /// Pieces we have
+    pub num_pieces: i32,
+    /// Number of full copies of the torrent among connected peers
+    pub distributed_full_copies: i32,
+    /// Fraction of a copy beyond the full copies, in thousandths
+    pub distributed_fraction: i32,
+    /// Availability of the torrent among connected peers, `-1` if not
+    /// queried with [`StatusFlags::DISTRIBUTED_COPIES`](crate::StatusFlags::DISTRIBUTED_COPIES)
+    pub distributed_copies: f32,
+    pub block_size: i32,
+    pub num_uploads: i32,
+    pub num_connections: i32,
+    pub uploads_limit: i32,
+    pub connections_limit: i32,
+    pub up_bandwidth_queue: i32,
+    pub down_bandwidth_queue: i32,
+    pub seed_rank: i32,
+    pub need_save_resume: bool,
+    pub is_seeding: bool,
+    pub is_finished: bool,
+    pub has_metadata: bool,
+    pub has_incoming: bool,
+    pub moving_storage: bool,
+    pub announcing_to_trackers: bool,
+    pub announcing_to_lsd: bool,
+    pub announcing_to_dht: bool,
+    pub flags: TorrentFlags,
+    /// Pieces we have, if queried with [`StatusFlags::PIECES`](crate::StatusFlags::PIECES).
+    /// Empty until the metadata of the torrent is known.
+    pub pieces: Option<Vec<bool>>,
+    /// Pieces verified in seed mode, if queried with
+    /// [`StatusFlags::VERIFIED_PIECES`](crate::StatusFlags::VERIFIED_PIECES)
+    pub verified_pieces: Option<Vec<bool>>,
+}
+
+impl TorrentStatus {
+    /// Progress of the current task, between 0 and 1
+    pub fn progress(&self) -> f32 {
+        self.progress_ppm as f32 / 1_000_000.
+    }
+
+    /// Estimated time until the wanted files are downloaded at the current
+    /// payload rate, `None` while nothing is being downloaded
+    pub fn eta(&self) -> Option<Duration> {
+        let remaining = self.total_wanted - self.total_wanted_done;
+        if remaining <= 0 {
+            return Some(Duration::ZERO);
+        }
+
+        if self.download_payload_rate <= 0 {
+            return None;
+        }
+
+        Some(Duration::from_secs(
+            remaining as u64 / self.download_payload_rate as u64,
+        ))
+    }
+}
+
+impl From<ffi::TorrentStatus> for TorrentStatus {
+    fn from(s: ffi::TorrentStatus) -> Self {
+        Self {
+            info_hashes: s.info_hashes.into(),
+            state: s.state,
+            progress_ppm: s.progress_ppm as u32,
+            error: Some(s.errc).filter(ErrorCode::is_error),
+            error_file: ErrorFile::from(s.error_file),
+            save_path: PathBuf::from(s.save_path),
+            name: s.name,
+            current_tracker: Some(s.current_tracker).filter(|url| !url.is_empty()),
+            next_announce: Duration::from_secs(s.next_announce_s.max(0) as u64),
+            total_download: s.total_download,
+            total_upload: s.total_upload,
+            total_payload_download: s.total_payload_download,
+            total_payload_upload: s.total_payload_upload,
+            total_failed_bytes: s.total_failed_bytes,
+            total_redundant_bytes: s.total_redundant_bytes,
+            total_done: s.total_done,
+            total: s.total,
+            total_wanted_done: s.total_wanted_done,
+            total_wanted: s.total_wanted,
+            all_time_upload: s.all_time_upload,
+            all_time_download: s.all_time_download,
+            added_time: unix_time(Duration::from_secs, s.added_time),
+            completed_time: unix_time(Duration::from_secs, s.completed_time),
+            last_seen_complete: unix_time(Duration::from_secs, s.last_seen_complete),
+            last_upload: unix_time(Duration::from_micros, s.last_upload_us),
+            last_download: unix_time(Duration::from_micros, s.last_download_us),
+            active_duration: Duration::from_secs(s.active_duration_s.max(0) as u64),
+            finished_duration: Duration::from_secs(s.finished_duration_s.max(0) as u64),
+            seeding_duration: Duration::from_secs(s.seeding_duration_s.max(0) as u64),
+            storage_mode: s.storage_mode,
+            queue_position: s.queue_position,
+            download_rate: s.download_rate,
+            upload_rate: s.upload_rate,
+            download_payload_rate: s.download_payload_rate,
+            upload_payload_rate: s.upload_payload_rate,
+            num_seeds: s.num_seeds,
+            num_peers: s.num_peers,
+            num_complete: s.num_complete,
+            num_incomplete: s.num_incomplete,
+            list_seeds: s.list_seeds,
+            list_peers: s.list_peers,
+            connect_candidates: s.connect_candidates,
+            num_pieces: s.num_pieces,
+            distributed_full_copies: s.distributed_full_copies,
+            distributed_fraction: s.distributed_fraction,
+            distributed_copies: s.distributed_copies,
+            block_size: s.block_size,
+            num_uploads: s.num_uploads,
+            num_connections: s.num_connections,
+            uploads_limit: s.uploads_limit,
+            connections_limit: s.connections_limit,
+            up_bandwidth_queue: s.up_bandwidth_queue,
+            down_bandwidth_queue: s.down_bandwidth_queue,
+            seed_rank: s.seed_rank,
+            need_save_resume: s.need_save_resume,
+            is_seeding: s.is_seeding,
+            is_finished: s.is_finished,
+            has_metadata: s.has_metadata,
+            has_incoming: s.has_incoming,
+            moving_storage: s.moving_storage,
+            announcing_to_trackers: s.announcing_to_trackers,
+            announcing_to_lsd: s.announcing_to_lsd,
+            announcing_to_dht: s.announcing_to_dht,
+            flags: TorrentFlags::from(s.flags),
+            pieces: bitfield(s.pieces),
+            verified_pieces: bitfield(s.verified_pieces),
+        }
+    }
+}
+
+/// Times that never happened are zero on the C++ side
+pub(crate) fn unix_time(unit: fn(u64) -> Duration, t: i64) -> Option<SystemTime> {
+    if t > 0 {
+        Some(SystemTime::UNIX_EPOCH + unit(t as u64))
+    } else {
+        None
+    }
+}
+
+fn bitfield(pieces: Vec<u8>) -> Option<Vec<bool>> {
+    if pieces.is_empty() {
+        None
+    } else {
+        Some(pieces.into_iter().map(|have| have != 0).collect())
+    }
+}
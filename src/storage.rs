@@ -0,0 +1,257 @@
+use crate::{hash::non_zero, status::unix_time, FileFlags, Sha256Hash};
+use rbtorrent_sys::ffi;
+use std::{
+    ffi::OsString,
+    fmt,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+/// Files of a torrent, mirroring `lt::file_storage`. Borrowed from the
+/// [`TorrentInfo`](crate::TorrentInfo) it belongs to.
+#[derive(Clone, Copy)]
+pub struct FileStorage<'a> {
+    inner: &'a ffi::file_storage,
+}
+
+impl<'a> FileStorage<'a> {
+    pub(crate) fn new(inner: &'a ffi::file_storage) -> Self {
+        Self { inner }
+    }
+
+    /// Number of files, including pad files
+    pub fn len(&self) -> u32 {
+        ffi::file_storage_num_files(self.inner) as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the file at `index`, `None` if out of range
+    pub fn get(&self, index: u32) -> Option<FileEntry> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let e = ffi::file_storage_file_entry(self.inner, index as i32);
+        let flags = FileFlags::from(e.flags);
+
+        Some(FileEntry {
+            index,
+            path: PathBuf::from(e.path),
+            size: e.size as u64,
+            offset: e.offset as u64,
+            flags,
+            symlink: flags
+                .contains(FileFlags::SYMLINK)
+                .then(|| PathBuf::from(e.symlink)),
+            mtime: unix_time(Duration::from_secs, e.mtime),
+            root: non_zero(e.root).map(Sha256Hash),
+        })
+    }
+
+    /// Iterates over the files in torrent order, including pad files
+    pub fn iter(&self) -> impl Iterator<Item = FileEntry> + 'a {
+        let files = *self;
+        (0..self.len()).filter_map(move |index| files.get(index))
+    }
+
+    /// Size of the torrent in bytes, including pad files
+    pub fn total_size(&self) -> u64 {
+        ffi::file_storage_total_size(self.inner) as u64
+    }
+
+    pub fn num_pieces(&self) -> u32 {
+        ffi::file_storage_num_pieces(self.inner) as u32
+    }
+
+    /// Size of `piece` in bytes, `None` if out of range
+    pub fn piece_size(&self, piece: u32) -> Option<u32> {
+        (piece < self.num_pieces())
+            .then(|| ffi::file_storage_piece_size(self.inner, piece as i32) as u32)
+    }
+
+    /// Size of the file at `index` in bytes, `None` if out of range
+    pub fn file_size(&self, index: u32) -> Option<u64> {
+        (index < self.len()).then(|| ffi::file_storage_file_size(self.inner, index as i32) as u64)
+    }
+
+    /// Maps a byte range of a file to the piece it starts in, `None` if the
+    /// file is out of range or `offset` is past its end. Ranges reaching past
+    /// the end of the file are truncated.
+    pub fn map_file(&self, file_index: u32, offset: u64, size: u32) -> Option<PeerRequest> {
+        if offset >= self.file_size(file_index)? {
+            return None;
+        }
+
+        let size = i32::try_from(size).unwrap_or(i32::MAX);
+        let r = ffi::file_storage_map_file(self.inner, file_index as i32, offset as i64, size);
+
+        Some(PeerRequest {
+            piece: r.piece as u32,
+            start: r.start as u32,
+            length: r.length as u32,
+        })
+    }
+
+    /// Maps a byte range of a piece to the file ranges it covers, `None` if
+    /// the piece is out of range or the range reaches past the end of the
+    /// piece. Pad files are included.
+    pub fn map_block(&self, piece: u32, offset: u64, size: u64) -> Option<Vec<FileSlice>> {
+        let end = offset.checked_add(size)?;
+        if end > u64::from(self.piece_size(piece)?) {
+            return None;
+        }
+
+        // both fit in an i32 since the range lies within the piece
+        let slices =
+            ffi::file_storage_map_block(self.inner, piece as i32, offset as i64, size as i64);
+
+        Some(
+            slices
+                .into_iter()
+                .map(|s| FileSlice {
+                    file_index: s.file_index as u32,
+                    offset: s.offset as u64,
+                    size: s.size as u64,
+                })
+                .collect(),
+        )
+    }
+
+    /// Iterates over the directory tree of the torrent, depth first. Every
+    /// directory comes before its content, siblings are in the order they
+    /// first appear in the torrent. Pad files are skipped.
+    pub fn tree(&self) -> impl Iterator<Item = FileTreeEntry> {
+        let mut root = TreeNode::default();
+        for file in self.iter() {
+            if !file.is_pad_file() {
+                root.insert(&file);
+            }
+        }
+
+        let mut entries = Vec::new();
+        root.walk(&mut PathBuf::new(), 0, &mut entries);
+        entries.into_iter()
+    }
+}
+
+impl fmt::Debug for FileStorage<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// A file of a torrent
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FileEntry {
+    pub index: u32,
+    /// Path relative to the save path, starting with the name of the
+    /// torrent for multi-file torrents
+    pub path: PathBuf,
+    pub size: u64,
+    /// Offset of the file in the torrent, as if all files were concatenated
+    pub offset: u64,
+    pub flags: FileFlags,
+    /// Target of the symlink, relative to the save path
+    pub symlink: Option<PathBuf>,
+    pub mtime: Option<SystemTime>,
+    /// Merkle root of the file, `None` for v1-only torrents and pad files
+    pub root: Option<Sha256Hash>,
+}
+
+impl FileEntry {
+    pub fn is_pad_file(&self) -> bool {
+        self.flags.contains(FileFlags::PAD_FILE)
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.flags.contains(FileFlags::HIDDEN)
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.flags.contains(FileFlags::EXECUTABLE)
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.flags.contains(FileFlags::SYMLINK)
+    }
+}
+
+/// Byte range of a piece, mirroring `lt::peer_request`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PeerRequest {
+    pub piece: u32,
+    /// Offset in the piece
+    pub start: u32,
+    pub length: u32,
+}
+
+/// Byte range of a file, mirroring `lt::file_slice`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FileSlice {
+    pub file_index: u32,
+    /// Offset in the file
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// Directory or file of the tree returned by [`FileStorage::tree`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FileTreeEntry {
+    /// Path relative to the save path
+    pub path: PathBuf,
+    /// Number of directories above the entry, 0 at the top level
+    pub depth: usize,
+    pub kind: FileTreeKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FileTreeKind {
+    Directory,
+    /// Index of the file in the [`FileStorage`]
+    File(u32),
+}
+
+#[derive(Default)]
+struct TreeNode {
+    file: Option<u32>,
+    children: Vec<(OsString, TreeNode)>,
+}
+
+impl TreeNode {
+    fn insert(&mut self, file: &FileEntry) {
+        let mut node = self;
+        for name in file.path.iter() {
+            let pos = match node.children.iter().position(|(n, _)| n == name) {
+                Some(pos) => pos,
+                None => {
+                    node.children.push((name.to_owned(), TreeNode::default()));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[pos].1;
+        }
+
+        node.file = Some(file.index);
+    }
+
+    fn walk(&self, path: &mut PathBuf, depth: usize, out: &mut Vec<FileTreeEntry>) {
+        for (name, child) in &self.children {
+            path.push(name);
+
+            out.push(FileTreeEntry {
+                path: path.clone(),
+                depth,
+                kind: match child.file {
+                    Some(index) => FileTreeKind::File(index),
+                    None => FileTreeKind::Directory,
+                },
+            });
+            child.walk(path, depth + 1, out);
+
+            path.pop();
+        }
+    }
+}
@@ -4,6 +4,8 @@ fn main() {
     let manifest_dir = PathBuf::from(&env::var("CARGO_MANIFEST_DIR").unwrap());
     let libtorrent_path = manifest_dir.join("libtorrent");
 
+    // the aux:: merkle tree helpers are only exported with
+    // TORRENT_EXPORT_EXTRA
     let dst = cmake::Config::new("libtorrent")
         .define("NDEBUG", "1")
         .define("CMAKE_CXX_STANDARD", "17")
@@ -11,6 +13,7 @@ fn main() {
         .define("TORRENT_USE_LIBCRYPTO", "1")
         .define("TORRENT_LINKING_SHARED", "1")
         .define("TORRENT_BUILDING_SHARED", "1")
+        .cxxflag("-DTORRENT_EXPORT_EXTRA")
         .build();
 
     println!("cargo:rustc-link-lib=torrent-rasterbar");
@@ -26,6 +29,7 @@ fn main() {
         .include(libtorrent_path.join("include"))
         .include(manifest_dir)
         .include(out_dir)
+        .define("TORRENT_EXPORT_EXTRA", None)
         .flag_if_supported("-std=c++17")
         .flag("-Wno-deprecated-declarations")
         .compile("rbtorrent-sys");
@@ -4,9 +4,17 @@
 #![allow(non_snake_case)]
 #![allow(clippy::all)]
 
+use std::{
+    any::Any,
+    fmt, io,
+    panic::{self, AssertUnwindSafe},
+    sync::Mutex,
+};
+
 #[cxx::bridge(namespace = "libtorrent")]
 pub mod ffi {
     #[repr(i32)]
+    #[derive(Debug)]
     enum torrent_state {
         queued_for_checking,
         checking_files,
@@ -18,9 +26,325 @@ pub mod ffi {
         checking_resume_data,
     }
 
+    #[repr(i32)]
+    #[derive(Debug)]
+    enum storage_mode_t {
+        /// All pieces will be written to their final position, all files will
+        /// be allocated in full when the torrent is first started.
+        storage_mode_allocate,
+        /// All pieces will be written to the place where they belong and
+        /// sparse files will be used.
+        storage_mode_sparse,
+    }
+
+    /// Flattened copy of an `lt::torrent_status`
+    ///
+    /// Times are in seconds or microseconds since the Unix epoch and are zero
+    /// when they never happened. Pieces are one byte per piece and are empty
+    /// unless requested through the status flags.
+    #[derive(Clone, Debug)]
     struct TorrentStatus {
+        info_hashes: InfoHashes,
         state: torrent_state,
         progress_ppm: u64,
+        errc: ErrorCode,
+        error_file: i32,
+        save_path: String,
+        name: String,
+        current_tracker: String,
+        next_announce_s: i64,
+        total_download: i64,
+        total_upload: i64,
+        total_payload_download: i64,
+        total_payload_upload: i64,
+        total_failed_bytes: i64,
+        total_redundant_bytes: i64,
+        total_done: i64,
+        total: i64,
+        total_wanted_done: i64,
+        total_wanted: i64,
+        all_time_upload: i64,
+        all_time_download: i64,
+        added_time: i64,
+        completed_time: i64,
+        last_seen_complete: i64,
+        last_upload_us: i64,
+        last_download_us: i64,
+        active_duration_s: i64,
+        finished_duration_s: i64,
+        seeding_duration_s: i64,
+        storage_mode: storage_mode_t,
+        queue_position: i32,
+        download_rate: i32,
+        upload_rate: i32,
+        download_payload_rate: i32,
+        upload_payload_rate: i32,
+        num_seeds: i32,
+        num_peers: i32,
+        num_complete: i32,
+        num_incomplete: i32,
+        list_seeds: i32,
+        list_peers: i32,
+        connect_candidates: i32,
+        num_pieces: i32,
+        distributed_full_copies: i32,
+        distributed_fraction: i32,
+        distributed_copies: f32,
+        block_size: i32,
+        num_uploads: i32,
+        num_connections: i32,
+        uploads_limit: i32,
+        connections_limit: i32,
+        up_bandwidth_queue: i32,
+        down_bandwidth_queue: i32,
+        seed_rank: i32,
+        need_save_resume: bool,
+        is_seeding: bool,
+        is_finished: bool,
+        has_metadata: bool,
+        has_incoming: bool,
+        moving_storage: bool,
+        announcing_to_trackers: bool,
+        announcing_to_lsd: bool,
+        announcing_to_dht: bool,
+        flags: u64,
+        pieces: Vec<u8>,
+        verified_pieces: Vec<u8>,
+    }
+
+    /// Mirror of `lt::error_code`
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+    struct ErrorCode {
+        /// name of the error category, e.g. ``libtorrent`` or ``system``
+        category: String,
+        /// numeric value of the error within its category
+        value: i32,
+        /// human readable description of the error
+        message: String,
+    }
+
+    /// Flattened copy of an `lt::alert` and the fields of its concrete type.
+    ///
+    /// Only the fields relevant to the alert type identified by `alert_type`
+    /// are set, the rest are left at their defaults.
+    #[derive(Default)]
+    struct AlertData {
+        /// value of alert::type()
+        alert_type: i32,
+        /// value of alert::what(), identifies the concrete alert type
+        what: String,
+        category: u32,
+        /// alert::timestamp() translated to microseconds since the Unix epoch
+        timestamp_us: i64,
+        message: String,
+        /// name of the torrent, for alerts deriving from torrent_alert
+        torrent_name: String,
+        /// remote endpoint of peer, DHT node, listen socket or external address
+        endpoint: Endpoint,
+        /// local endpoint of tracker alerts
+        local_endpoint: Endpoint,
+        peer_id: [u8; 20],
+        /// v1 info hash, DHT target or node id
+        hash_v1: [u8; 20],
+        /// v2 info hash or DHT public key
+        hash_v2: [u8; 32],
+        error: ErrorCode,
+        /// name of the operation that failed
+        operation: String,
+        /// tracker, web seed or server URL
+        url: String,
+        /// file name, storage path or log event type
+        path: String,
+        old_path: String,
+        /// secondary message: warning, failure reason, tracker id or log line
+        text: String,
+        piece: i32,
+        block: i32,
+        file: i32,
+        start: i32,
+        length: i32,
+        state: torrent_state,
+        prev_state: torrent_state,
+        num_peers: i32,
+        complete: i32,
+        incomplete: i32,
+        times_in_row: i32,
+        /// close reason, block reason, performance warning or picker flags
+        reason: i32,
+        socket_type: i32,
+        mapping: i32,
+        transport: i32,
+        protocol: i32,
+        event: i32,
+        direction: i32,
+        module: i32,
+        interval_s: i32,
+        seq: i64,
+        we_have: bool,
+        peer_interested: bool,
+        withheld: bool,
+        authoritative: bool,
+        /// piece data, packet, bencoded DHT item or concatenated sample hashes
+        buffer: Vec<u8>,
+        /// salt of DHT mutable items, arbitrary bytes
+        salt: Vec<u8>,
+        endpoints: Vec<Endpoint>,
+        counters: Vec<i64>,
+        statuses: Vec<TorrentStatus>,
+    }
+
+    /// Raw values of the constants in `lt::alert_category`
+    #[derive(Debug)]
+    struct AlertCategoryBits {
+        error: u32,
+        peer: u32,
+        port_mapping: u32,
+        storage: u32,
+        tracker: u32,
+        connect: u32,
+        status: u32,
+        ip_block: u32,
+        performance_warning: u32,
+        dht: u32,
+        session_log: u32,
+        torrent_log: u32,
+        peer_log: u32,
+        incoming_request: u32,
+        dht_log: u32,
+        dht_operation: u32,
+        port_mapping_log: u32,
+        picker_log: u32,
+        file_progress: u32,
+        piece_progress: u32,
+        upload: u32,
+        block_progress: u32,
+    }
+
+    /// Raw values of the constants in `lt::torrent_flags`
+    #[derive(Debug)]
+    struct TorrentFlagsBits {
+        seed_mode: u64,
+        upload_mode: u64,
+        share_mode: u64,
+        apply_ip_filter: u64,
+        paused: u64,
+        auto_managed: u64,
+        duplicate_is_error: u64,
+        update_subscribe: u64,
+        super_seeding: u64,
+        sequential_download: u64,
+        stop_when_ready: u64,
+        override_trackers: u64,
+        override_web_seeds: u64,
+        need_save_resume: u64,
+        disable_dht: u64,
+        disable_lsd: u64,
+        disable_pex: u64,
+        no_verify_files: u64,
+        default_dont_download: u64,
+        default_flags: u64,
+    }
+
+    /// Mirror of `lt::info_hash_t`, a hash that is not present is all zeros
+    #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+    struct InfoHashes {
+        /// SHA-1 info-hash of the v1 info section
+        v1: [u8; 20],
+        /// SHA-256 info-hash of the v2 info section
+        v2: [u8; 32],
+    }
+
+    /// Type of a setting in an `lt::settings_pack`
+    #[derive(Debug)]
+    enum SettingKind {
+        Str,
+        Int,
+        Bool,
+    }
+
+    /// A single setting of an `lt::settings_pack`, only the value matching
+    /// `kind` is set
+    struct SettingEntry {
+        name: String,
+        kind: SettingKind,
+        int_value: i32,
+        bool_value: bool,
+        str_value: String,
+    }
+
+    /// Values of the `lt::file_storage::file_flags_t` constants
+    struct FileFlagsBits {
+        pad_file: u8,
+        hidden: u8,
+        executable: u8,
+        symlink: u8,
+    }
+
+    /// Values of the `lt::create_flags_t` constants
+    struct CreateFlagsBits {
+        modification_time: u32,
+        symlinks: u32,
+        v2_only: u32,
+        v1_only: u32,
+        canonical_files: u32,
+        no_attributes: u32,
+        canonical_files_no_tail_padding: u32,
+    }
+
+    /// Values of the `lt::write_torrent_flags_t` constants
+    struct WriteTorrentFlagsBits {
+        allow_missing_piece_layer: u32,
+        no_http_seeds: u32,
+        include_dht_nodes: u32,
+    }
+
+    /// Values of the `lt::resume_data_flags_t` constants
+    struct ResumeDataFlagsBits {
+        flush_disk_cache: u8,
+        save_info_dict: u8,
+        only_if_modified: u8,
+    }
+
+    /// Values of the `lt::save_state_flags_t` constants
+    struct SaveStateFlagsBits {
+        save_settings: u32,
+        save_dht_state: u32,
+        save_extension_state: u32,
+        save_ip_filter: u32,
+    }
+
+    /// Values of the `lt::torrent_handle::status_flags_t` constants
+    struct StatusFlagsBits {
+        query_distributed_copies: u32,
+        query_accurate_download_counters: u32,
+        query_last_seen_complete: u32,
+        query_pieces: u32,
+        query_verified_pieces: u32,
+        query_torrent_file: u32,
+        query_name: u32,
+        query_save_path: u32,
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+    struct Endpoint {
+        address: String,
+        port: u16,
+    }
+
+    struct RenamedFile {
+        index: i32,
+        path: String,
+    }
+
+    /// Fields of the lt::add_torrent_params returned by lt::parse_magnet_uri
+    struct MagnetUriParams {
+        info_hashes: InfoHashes,
+        name: String,
+        trackers: Vec<String>,
+        url_seeds: Vec<String>,
+        peers: Vec<Endpoint>,
+        /// Priorities set from the `so` parameter, empty if none
+        file_priorities: Vec<u8>,
     }
 
     struct TorrentInfoNode {
@@ -28,6 +352,109 @@ pub mod ffi {
         port: u16,
     }
 
+    /// Content and metadata of a torrent to create with `lt::create_torrent`
+    struct CreateTorrentParams {
+        /// file or directory to add, the files are read relative to its
+        /// parent directory
+        path: String,
+        /// 0 lets libtorrent pick a piece size from the total size
+        piece_size: i32,
+        /// `lt::create_flags_t`
+        flags: u32,
+        /// url and tier, the tracker id is ignored
+        trackers: Vec<AnnounceEntry>,
+        web_seeds: Vec<String>,
+        nodes: Vec<TorrentInfoNode>,
+        comment: String,
+        creator: String,
+        /// seconds since the epoch, 0 leaves the field out
+        creation_date: i64,
+        is_private: bool,
+        /// `source` key of the info dictionary, left out if empty
+        source: String,
+        collections: Vec<String>,
+        /// concatenated SHA-1 info-hashes
+        similar_torrents: Vec<u8>,
+        /// Number of threads hashing the pieces, one when 0 or less
+        hashing_threads: i32,
+    }
+
+    /// A file of a torrent created from a file list rather than from disk
+    struct VirtualFileEntry {
+        /// path starting with the name of the torrent, `/` separated
+        path: String,
+        size: i64,
+        /// `lt::file_storage::file_flags_t`
+        flags: u8,
+        /// seconds since the epoch, 0 if unknown
+        mtime: i64,
+    }
+
+    /// Kind of web seed, mirroring `lt::web_seed_entry::type_t`
+    #[derive(Debug, Hash)]
+    #[repr(u8)]
+    enum WebSeedKind {
+        /// BEP 19 url seed
+        UrlSeed,
+        /// BEP 17 http seed
+        HttpSeed,
+    }
+
+    struct HttpHeader {
+        name: String,
+        value: String,
+    }
+
+    struct WebSeedEntry {
+        url: String,
+        kind: WebSeedKind,
+        /// Credentials of the web seed, empty if none
+        auth: String,
+        extra_headers: Vec<HttpHeader>,
+    }
+
+    /// A file of a lt::file_storage
+    struct FileStorageEntry {
+        path: String,
+        size: i64,
+        offset: i64,
+        flags: u8,
+        /// Target of the symlink, empty unless the file is a symlink
+        symlink: String,
+        /// Modification time in unix seconds, 0 if unknown
+        mtime: i64,
+        /// Merkle root of the file, zeros for v1-only torrents and pad files
+        root: [u8; 32],
+    }
+
+    /// Mirror of `lt::peer_request`
+    struct PeerRequest {
+        piece: i32,
+        start: i32,
+        length: i32,
+    }
+
+    /// Mirror of `lt::file_slice`
+    struct FileSlice {
+        file_index: i32,
+        offset: i64,
+        size: i64,
+    }
+
+    /// Limits applied when loading a .torrent file, mirroring
+    /// `lt::load_torrent_limits`
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+    struct LoadTorrentLimits {
+        /// Largest .torrent file accepted, in bytes
+        max_buffer_size: i32,
+        /// Largest number of pieces accepted
+        max_pieces: i32,
+        /// Deepest nesting of bencoded lists and dictionaries accepted
+        max_decode_depth: i32,
+        /// Largest number of bencoded tokens accepted
+        max_decode_tokens: i32,
+    }
+
     #[derive(Debug)]
     struct AnnounceEntry {
         /// tracker URL as it appeared in the torrent file
@@ -47,22 +474,43 @@ pub mod ffi {
         inner: *mut alert,
     }
 
+    extern "Rust" {
+        type AlertNotify;
+        type HashProgress<'a>;
+        type FileFilter<'a>;
+        type FileReader<'a>;
+
+        fn notify(self: &AlertNotify);
+        fn progress(self: &mut HashProgress<'_>, done: i32, total: i32) -> bool;
+        fn include(self: &mut FileFilter<'_>, path: String) -> bool;
+        fn panicked(self: &FileFilter<'_>) -> bool;
+        fn read(self: &FileReader<'_>, file: u32, offset: u64, buf: &mut [u8]) -> Result<()>;
+    }
+
     unsafe extern "C++" {
         include!("src/rb.hpp");
 
         type alert;
         type session;
+        type session_proxy;
         type session_params;
+        type settings_pack;
         type add_torrent_params;
         type metadata_received_alert;
         type save_resume_data_alert;
         type save_resume_data_failed_alert;
         type torrent_handle;
         type torrent_info;
+        type file_storage;
         type torrent_state;
+        type storage_mode_t;
 
         pub fn new_session_params() -> UniquePtr<session_params>;
 
+        /// This function return the session params stored in a buffer written
+        /// by session_save_state(), by calling lt::read_session_params()
+        pub fn new_session_params_from_state(buf: &[u8]) -> Result<UniquePtr<session_params>>;
+
         pub fn session_params_set_user_agent(
             session_params: Pin<&mut session_params>,
             user_agent: &str,
@@ -85,17 +533,65 @@ pub mod ffi {
 
         pub fn session_params_disable_disk(session_params: Pin<&mut session_params>);
 
+        /// This function return the settings the session will be created with
+        pub fn session_params_get_settings(
+            session_params: Pin<&mut session_params>,
+        ) -> Pin<&mut settings_pack>;
+
+        pub fn new_settings_pack() -> UniquePtr<settings_pack>;
+
+        /// These functions set a setting by name, they fail if no setting of
+        /// that type has the given name
+        pub fn settings_pack_set_int(
+            pack: Pin<&mut settings_pack>,
+            name: &str,
+            value: i32,
+        ) -> Result<()>;
+
+        pub fn settings_pack_set_bool(
+            pack: Pin<&mut settings_pack>,
+            name: &str,
+            value: bool,
+        ) -> Result<()>;
+
+        pub fn settings_pack_set_str(
+            pack: Pin<&mut settings_pack>,
+            name: &str,
+            value: &str,
+        ) -> Result<()>;
+
+        /// This function return the type of the setting with the given name,
+        /// it fails if there is none
+        pub fn setting_kind(name: &str) -> Result<SettingKind>;
+
         /// This function return a struct of type lt::session
-        pub fn new_session(settings: UniquePtr<session_params>) -> UniquePtr<session>;
+        pub fn new_session(settings: UniquePtr<session_params>) -> Result<UniquePtr<session>>;
 
         /// This function return a struct of type lt::add_torrent_params
         ///
         /// lt::add_torrent_params is returned by lt::parse_magnet_uri,
-        pub fn new_add_torrent_params_from_magnet_uri(uri: &str) -> UniquePtr<add_torrent_params>;
+        pub fn new_add_torrent_params_from_magnet_uri(
+            uri: &str,
+        ) -> Result<UniquePtr<add_torrent_params>>;
 
         pub fn new_add_torrent_params_from_torrent_file(
             path: &str,
-        ) -> UniquePtr<add_torrent_params>;
+            limits: &LoadTorrentLimits,
+        ) -> Result<UniquePtr<add_torrent_params>>;
+
+        /// This function return a struct of type lt::add_torrent_params
+        ///
+        /// lt::add_torrent_params is returned by lt::load_torrent_buffer,
+        pub fn new_add_torrent_params_from_torrent_buffer(
+            buf: &[u8],
+            limits: &LoadTorrentLimits,
+        ) -> Result<UniquePtr<add_torrent_params>>;
+
+        /// This function return the add_torrent_params stored in a resume
+        /// data buffer, by calling lt::read_resume_data()
+        pub fn new_add_torrent_params_from_resume_data(
+            buf: &[u8],
+        ) -> Result<UniquePtr<add_torrent_params>>;
 
         pub fn add_torrent_params_set_save_path(params: Pin<&mut add_torrent_params>, path: &str);
 
@@ -109,6 +605,72 @@ pub mod ffi {
             torrent_flags: u64,
         );
 
+        pub fn add_torrent_params_set_name(params: Pin<&mut add_torrent_params>, name: &str);
+
+        pub fn add_torrent_params_set_file_priorities(
+            params: Pin<&mut add_torrent_params>,
+            priorities: &[u8],
+        );
+
+        pub fn add_torrent_params_set_piece_priorities(
+            params: Pin<&mut add_torrent_params>,
+            priorities: &[u8],
+        );
+
+        pub fn add_torrent_params_set_url_seeds(
+            params: Pin<&mut add_torrent_params>,
+            url_seeds: &[&str],
+        );
+
+        pub fn add_torrent_params_set_dht_nodes(
+            params: Pin<&mut add_torrent_params>,
+            nodes: &[TorrentInfoNode],
+        );
+
+        pub fn add_torrent_params_set_peers(
+            params: Pin<&mut add_torrent_params>,
+            peers: &[Endpoint],
+        ) -> Result<()>;
+
+        pub fn add_torrent_params_set_banned_peers(
+            params: Pin<&mut add_torrent_params>,
+            peers: &[Endpoint],
+        ) -> Result<()>;
+
+        pub fn add_torrent_params_set_max_connections(
+            params: Pin<&mut add_torrent_params>,
+            max_connections: i32,
+        );
+
+        pub fn add_torrent_params_set_max_uploads(
+            params: Pin<&mut add_torrent_params>,
+            max_uploads: i32,
+        );
+
+        pub fn add_torrent_params_set_upload_limit(
+            params: Pin<&mut add_torrent_params>,
+            upload_limit: i32,
+        );
+
+        pub fn add_torrent_params_set_download_limit(
+            params: Pin<&mut add_torrent_params>,
+            download_limit: i32,
+        );
+
+        pub fn add_torrent_params_set_storage_mode(
+            params: Pin<&mut add_torrent_params>,
+            storage_mode: storage_mode_t,
+        );
+
+        pub fn add_torrent_params_set_renamed_files(
+            params: Pin<&mut add_torrent_params>,
+            renamed_files: &[RenamedFile],
+        );
+
+        /// This function return the params encoded by lt::write_resume_data_buf
+        pub fn add_torrent_params_write_resume_data(params: &add_torrent_params)
+            -> Result<Vec<u8>>;
+
         /// This function return a struct of type lt::torrent_handle
         ///
         /// Call the function add_torrent() using the current session and add_torrent_params
@@ -116,19 +678,56 @@ pub mod ffi {
         pub fn session_add_torrent(
             ses: Pin<&mut session>,
             params: Pin<&mut add_torrent_params>,
-        ) -> UniquePtr<torrent_handle>;
+        ) -> Result<UniquePtr<torrent_handle>>;
 
         /// This function remove the given torrent from session
-        pub fn session_remove_torrent(ses: Pin<&mut session>, hdl: &torrent_handle);
+        pub fn session_remove_torrent(ses: Pin<&mut session>, hdl: &torrent_handle) -> Result<()>;
 
         /// This function call pause() for the given session
         pub fn session_pause(ses: Pin<&mut session>);
 
+        /// This function call abort() for the given session
+        ///
+        /// Destroying the session afterwards no longer blocks, the returned
+        /// proxy blocks in its destructor until the shutdown has completed.
+        pub fn session_abort(ses: Pin<&mut session>) -> UniquePtr<session_proxy>;
+
+        /// This function call apply_settings() for the given session
+        pub fn session_apply_settings(ses: Pin<&mut session>, pack: &settings_pack) -> Result<()>;
+
+        /// This function return the handles of every torrent in the session
+        pub fn session_get_torrents(ses: &session) -> Result<UniquePtr<CxxVector<torrent_handle>>>;
+
+        /// This function return the parts of the session state selected by
+        /// `flags`, bencoded by lt::write_session_params_buf()
+        pub fn session_save_state(ses: &session, flags: u32) -> Result<Vec<u8>>;
+
+        /// This function return every setting of the given session
+        pub fn session_get_settings(ses: &session) -> Vec<SettingEntry>;
+
         pub fn session_get_alerts(ses: Pin<&mut session>) -> Vec<Alert>;
 
+        /// This function register a callback invoked by libtorrent whenever
+        /// the alert queue goes from empty to non-empty
+        ///
+        /// The callback runs on a libtorrent internal thread and must not
+        /// call back into the session.
+        pub fn session_set_alert_notify(ses: Pin<&mut session>, notify: Box<AlertNotify>);
+
         // Alert
         pub fn alert_message(alert: &Alert) -> String;
 
+        /// This function copy the alert and the fields of its concrete type
+        pub fn alert_data(alert: &Alert) -> AlertData;
+
+        /// This function return a copy of the handle of a torrent_alert, or
+        /// null for other alerts
+        pub fn alert_get_torrent_handle(alert: &Alert) -> UniquePtr<torrent_handle>;
+
+        /// This function return lt::alert_name() of an alert type, empty if
+        /// the type is unknown
+        pub fn alert_type_name(alert_type: i32) -> String;
+
         pub fn alert_cast_metadata_received(alert: Alert) -> *mut metadata_received_alert;
 
         pub unsafe fn metadata_received_alert_get_torrent_handle(
@@ -137,34 +736,431 @@ pub mod ffi {
 
         pub fn alert_cast_save_resume_data(alert: Alert) -> *mut save_resume_data_alert;
 
+        /// This function return the resume data of the alert, bencoded by
+        /// lt::write_resume_data_buf()
+        pub unsafe fn save_resume_data_alert_get_resume_data(
+            alert: *mut save_resume_data_alert,
+        ) -> Vec<u8>;
+
         pub fn alert_cast_save_resume_data_failed(
             alert: Alert,
         ) -> *mut save_resume_data_failed_alert;
 
+        pub unsafe fn save_resume_data_failed_alert_get_error(
+            alert: *mut save_resume_data_failed_alert,
+        ) -> ErrorCode;
+
         pub fn wait_for_alert(ses: Pin<&mut session>, duration_ms: u64);
 
         /// This function return true if torrent has metadata
-        pub fn torrent_has_metadata(hdl: &torrent_handle) -> bool;
+        pub fn torrent_has_metadata(hdl: &torrent_handle) -> Result<bool>;
 
         /// This function return the torrent's name
         ///
-        /// Name is found in torrent_info return by function torrent_file()
-        pub fn torrent_get_name(hdl: &torrent_handle) -> &str;
+        /// The name is queried from the torrent status, so it is available
+        /// even before the metadata has been received.
+        pub fn torrent_get_name(hdl: &torrent_handle) -> Result<String>;
+
+        /// This function return the torrent's status, `flags` select the
+        /// optional fields to query
+        pub fn torrent_get_status(hdl: &torrent_handle, flags: u32) -> Result<TorrentStatus>;
+
+        /// This function ask libtorrent to post a save_resume_data_alert, or
+        /// a save_resume_data_failed_alert
+        pub fn torrent_save_resume_data(hdl: &torrent_handle, flags: u8) -> Result<()>;
+
+        /// This function return a new handle referring to the same torrent
+        pub fn torrent_handle_clone(hdl: &torrent_handle) -> UniquePtr<torrent_handle>;
+
+        /// This function return false once the torrent has been removed
+        /// from the session
+        pub fn torrent_is_valid(hdl: &torrent_handle) -> bool;
+
+        /// This function return the torrent's info-hashes, or zeros if the
+        /// handle is no longer valid
+        pub fn torrent_get_info_hashes(hdl: &torrent_handle) -> InfoHashes;
+
+        /// This function return a copy of the torrent's metadata, including
+        /// the piece layers of v2 torrents, or null if it has not been
+        /// received yet
+        pub fn torrent_get_torrent_file(hdl: &torrent_handle) -> Result<UniquePtr<torrent_info>>;
+
+        /// This function hash the files of `params.path` on
+        /// `params.hashing_threads` threads and return the bencoded .torrent
+        /// file
+        ///
+        /// `filter` is called by lt::add_files() with the path of each file
+        /// and directory, those it returns false for are left out. The
+        /// generation fails with `operation_aborted` if it panicked.
+        /// `progress` is called on the calling thread after each piece, the
+        /// hashing stops and fails with `operation_aborted` once it returns
+        /// false.
+        pub fn create_torrent_generate(
+            params: &CreateTorrentParams,
+            filter: &mut FileFilter<'_>,
+            progress: &mut HashProgress<'_>,
+        ) -> Result<Vec<u8>>;
 
-        pub fn torrent_get_status(hdl: &torrent_handle) -> TorrentStatus;
+        /// This function return the .torrent file of every file of
+        /// `params.path` hashed by lt::set_piece_hashes()
+        pub fn create_torrent_generate_with_libtorrent(
+            params: &CreateTorrentParams,
+        ) -> Result<Vec<u8>>;
 
-        /// This function return bencoded data by lt::bencode()
-        pub fn torrent_bencode(hdl: &torrent_handle) -> &[u8];
+        /// This function hash the content of `files` read through `reader`
+        /// on `params.hashing_threads` threads and return the bencoded
+        /// .torrent file, `params.path` is ignored
+        ///
+        /// `reader` is called from the hashing threads with the index of the
+        /// file in `files` and an offset in it. Fails if a file of the
+        /// torrent doesn't map back to one of `files`.
+        pub fn create_torrent_generate_from_files(
+            params: &CreateTorrentParams,
+            files: &[VirtualFileEntry],
+            reader: &FileReader<'_>,
+            progress: &mut HashProgress<'_>,
+        ) -> Result<Vec<u8>>;
 
-        pub fn create_torrent_for_path(path: &str) -> Vec<u8>;
+        pub fn open_torrent_info(
+            path: &str,
+            limits: &LoadTorrentLimits,
+        ) -> Result<UniquePtr<torrent_info>>;
 
-        pub fn open_torrent_info(path: &str) -> UniquePtr<torrent_info>;
+        /// This function return the torrent info parsed from a bencoded
+        /// .torrent file
+        pub fn new_torrent_info_from_buffer(
+            buf: &[u8],
+            limits: &LoadTorrentLimits,
+        ) -> Result<UniquePtr<torrent_info>>;
 
         pub fn torrent_info_nodes(info: &torrent_info) -> Vec<TorrentInfoNode>;
 
         pub fn torrent_info_trackers(info: &torrent_info) -> Vec<AnnounceEntry>;
 
+        pub fn torrent_info_name(info: &torrent_info) -> String;
+
+        /// This function return the comment of the torrent, empty if none
+        pub fn torrent_info_comment(info: &torrent_info) -> String;
+
+        /// This function return the creator of the torrent, empty if none
+        pub fn torrent_info_creator(info: &torrent_info) -> String;
+
+        /// This function return the creation date in unix seconds, 0 if
+        /// the torrent has none
+        pub fn torrent_info_creation_date(info: &torrent_info) -> i64;
+
+        pub fn torrent_info_total_size(info: &torrent_info) -> i64;
+
+        pub fn torrent_info_piece_length(info: &torrent_info) -> i32;
+
+        pub fn torrent_info_num_pieces(info: &torrent_info) -> i32;
+
+        pub fn torrent_info_priv(info: &torrent_info) -> bool;
+
+        pub fn torrent_info_is_i2p(info: &torrent_info) -> bool;
+
+        pub fn torrent_info_info_hashes(info: &torrent_info) -> InfoHashes;
+
+        pub fn torrent_info_web_seeds(info: &torrent_info) -> Vec<WebSeedEntry>;
+
+        /// This function return the info-hashes of the similar torrents,
+        /// concatenated
+        pub fn torrent_info_similar_torrents(info: &torrent_info) -> Vec<u8>;
+
+        pub fn torrent_info_collections(info: &torrent_info) -> Vec<String>;
+
+        /// This function return the PEM encoded SSL root certificate of the
+        /// torrent, empty if none
+        pub fn torrent_info_ssl_cert(info: &torrent_info) -> String;
+
+        /// This function return the bencoded info dictionary
+        pub fn torrent_info_info_section(info: &torrent_info) -> Vec<u8>;
+
+        pub fn torrent_info_files(info: &torrent_info) -> &file_storage;
+
+        pub fn file_storage_num_files(fs: &file_storage) -> i32;
+
+        pub fn file_storage_num_pieces(fs: &file_storage) -> i32;
+
+        /// This function return the size of the torrent in bytes, including
+        /// pad files
+        pub fn file_storage_total_size(fs: &file_storage) -> i64;
+
+        /// This function return the size of `piece`, which must be in range
+        pub fn file_storage_piece_size(fs: &file_storage, piece: i32) -> i32;
+
+        /// This function return the size of the file at `index`, which must
+        /// be in range
+        pub fn file_storage_file_size(fs: &file_storage, index: i32) -> i64;
+
+        /// This function return the file at `index`, which must be in range
+        pub fn file_storage_file_entry(fs: &file_storage, index: i32) -> FileStorageEntry;
+
+        /// This function return the piece range a byte range of a file maps
+        /// to, `index` must be in range
+        pub fn file_storage_map_file(
+            fs: &file_storage,
+            index: i32,
+            offset: i64,
+            size: i32,
+        ) -> PeerRequest;
+
+        /// This function return the file ranges a byte range of a piece maps
+        /// to, `piece` must be in range
+        pub fn file_storage_map_block(
+            fs: &file_storage,
+            piece: i32,
+            offset: i64,
+            size: i64,
+        ) -> Vec<FileSlice>;
+
+        /// This function return the .torrent file of the given torrent info,
+        /// bencoded by lt::write_torrent_file_buf()
+        pub fn torrent_info_write_torrent_file(info: &torrent_info, flags: u32) -> Result<Vec<u8>>;
+
+        /// This function return the magnet link of the torrent, generated by
+        /// lt::make_magnet_uri()
+        pub fn torrent_info_make_magnet_uri(info: &torrent_info) -> String;
+
+        /// This function return the magnet link of the torrent, generated by
+        /// lt::make_magnet_uri()
+        pub fn torrent_make_magnet_uri(hdl: &torrent_handle) -> Result<String>;
+
+        /// This function return the fields lt::parse_magnet_uri() extracts
+        /// from a magnet link
+        pub fn magnet_uri_parse(uri: &str) -> Result<MagnetUriParams>;
+
+        /// This function return the values of libtorrent's alert category constants
+        pub fn alert_category_bits() -> AlertCategoryBits;
+
+        /// This function return the values of libtorrent's torrent flag constants
+        pub fn torrent_flags_bits() -> TorrentFlagsBits;
+
+        pub fn status_flags_bits() -> StatusFlagsBits;
+
+        pub fn save_state_flags_bits() -> SaveStateFlagsBits;
+
+        pub fn resume_data_flags_bits() -> ResumeDataFlagsBits;
+
+        pub fn write_torrent_flags_bits() -> WriteTorrentFlagsBits;
+
+        pub fn file_flags_bits() -> FileFlagsBits;
+
+        pub fn create_flags_bits() -> CreateFlagsBits;
+
+        /// This function return the default lt::load_torrent_limits
+        pub fn default_load_torrent_limits() -> LoadTorrentLimits;
+
         /// This function call libtorrent::version() and return libtorrent version
         pub fn version() -> *const c_char;
     }
 }
+
+/// Callback passed to `lt::session::set_alert_notify`
+pub struct AlertNotify(Box<dyn Fn() + Send + Sync>);
+
+/// Payload of a panic caught by [`catch_unwind`](std::panic::catch_unwind)
+pub type PanicPayload = Box<dyn Any + Send>;
+
+/// Callback of the torrent creation, with the number of pieces hashed so far
+/// and the total. Returning false cancels the hashing, as does a panic,
+/// which is kept for [`take_panic`](Self::take_panic).
+pub struct HashProgress<'a> {
+    f: Box<dyn FnMut(i32, i32) -> bool + 'a>,
+    panic: Option<PanicPayload>,
+}
+
+/// Predicate passed to `lt::add_files`, with the path of a file or
+/// directory. Returning false leaves it out of the torrent. After a panic,
+/// which is kept for [`take_panic`](Self::take_panic), every path is left
+/// out.
+pub struct FileFilter<'a> {
+    f: Box<dyn FnMut(String) -> bool + 'a>,
+    panic: Option<PanicPayload>,
+}
+
+/// Source of the content of the files passed to
+/// `create_torrent_generate_from_files`, filling the buffer with the data of
+/// a file at an offset. Called from several threads at once. A panic fails
+/// the read, as do later reads, and is kept for
+/// [`take_panic`](Self::take_panic).
+pub struct FileReader<'a> {
+    f: Box<dyn Fn(usize, u64, &mut [u8]) -> io::Result<()> + Sync + 'a>,
+    panic: Mutex<Option<PanicPayload>>,
+}
+
+/// Error returned to C++ by a callback that panicked
+#[derive(Debug)]
+struct Panicked;
+
+impl fmt::Display for Panicked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "callback panicked")
+    }
+}
+
+/// Calls `f`, keeping its panic in `panic` instead of unwinding into C++,
+/// which aborts. `f` isn't called again once it panicked.
+fn catch_panic<T>(panic: &mut Option<PanicPayload>, f: impl FnOnce() -> T) -> Result<T, Panicked> {
+    if panic.is_some() {
+        return Err(Panicked);
+    }
+
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        *panic = Some(payload);
+        Panicked
+    })
+}
+
+// lt::torrent_handle is a weak reference to the torrent and all of its member
+// functions are safe to call from any thread.
+unsafe impl Send for ffi::torrent_handle {}
+unsafe impl Sync for ffi::torrent_handle {}
+
+// lt::torrent_info is not modified through the bridge once created
+unsafe impl Send for ffi::torrent_info {}
+unsafe impl Sync for ffi::torrent_info {}
+
+impl Default for ffi::torrent_state {
+    fn default() -> Self {
+        Self::queued_for_checking
+    }
+}
+
+impl Default for ffi::LoadTorrentLimits {
+    fn default() -> Self {
+        ffi::default_load_torrent_limits()
+    }
+}
+
+impl AlertNotify {
+    pub fn new(f: impl Fn() + Send + Sync + 'static) -> Self {
+        Self(Box::new(f))
+    }
+
+    fn notify(&self) {
+        (self.0)()
+    }
+}
+
+impl<'a> HashProgress<'a> {
+    pub fn new(f: impl FnMut(i32, i32) -> bool + 'a) -> Self {
+        Self {
+            f: Box::new(f),
+            panic: None,
+        }
+    }
+
+    pub fn take_panic(&mut self) -> Option<PanicPayload> {
+        self.panic.take()
+    }
+
+    fn progress(&mut self, done: i32, total: i32) -> bool {
+        let f = &mut self.f;
+        catch_panic(&mut self.panic, || f(done, total)).unwrap_or(false)
+    }
+}
+
+impl<'a> FileFilter<'a> {
+    pub fn new(f: impl FnMut(String) -> bool + 'a) -> Self {
+        Self {
+            f: Box::new(f),
+            panic: None,
+        }
+    }
+
+    pub fn take_panic(&mut self) -> Option<PanicPayload> {
+        self.panic.take()
+    }
+
+    fn include(&mut self, path: String) -> bool {
+        let f = &mut self.f;
+        catch_panic(&mut self.panic, || f(path)).unwrap_or(false)
+    }
+
+    fn panicked(&self) -> bool {
+        self.panic.is_some()
+    }
+}
+
+impl<'a> FileReader<'a> {
+    pub fn new(f: impl Fn(usize, u64, &mut [u8]) -> io::Result<()> + Sync + 'a) -> Self {
+        Self {
+            f: Box::new(f),
+            panic: Mutex::new(None),
+        }
+    }
+
+    pub fn take_panic(&mut self) -> Option<PanicPayload> {
+        self.panic.get_mut().unwrap().take()
+    }
+
+    fn read(&self, file: u32, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        if self.panic.lock().unwrap().is_some() {
+            return Err(io::Error::other(Panicked.to_string()));
+        }
+
+        // the lock isn't held while reading, the threads read in parallel
+        panic::catch_unwind(AssertUnwindSafe(|| (self.f)(file as usize, offset, buf)))
+            .unwrap_or_else(|payload| {
+                self.panic.lock().unwrap().get_or_insert(payload);
+                Err(io::Error::other(Panicked.to_string()))
+            })
+    }
+}
+
+/// Prefix the C++ side puts before an `lt::error_code` packed into the
+/// message of the exception that crosses the bridge, so that other
+/// exceptions are never mistaken for one.
+const ERROR_CODE_MARKER: &str = "\u{1f}lt::system_error\u{1f}";
+
+/// Separator of the packed category, value and message
+const ERROR_CODE_SEPARATOR: char = '\u{1f}';
+
+impl ffi::ErrorCode {
+    /// Recovers the `lt::error_code` carried by an exception thrown from the
+    /// bridge. Returns `None` if the exception was not a `system_error`.
+    pub fn from_exception(e: &cxx::Exception) -> Option<Self> {
+        let packed = e.what().strip_prefix(ERROR_CODE_MARKER)?;
+        let mut parts = packed.splitn(3, ERROR_CODE_SEPARATOR);
+
+        let category = parts.next()?;
+        let value = parts.next()?.parse().ok()?;
+        let message = parts.next()?;
+
+        Some(Self {
+            category: category.to_string(),
+            value,
+            message: message.to_string(),
+        })
+    }
+
+    /// Returns `true` if this represents an actual error, i.e. the value is non-zero
+    pub fn is_error(&self) -> bool {
+        self.value != 0
+    }
+}
+
+impl std::fmt::Display for ffi::ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}:{})", self.message, self.category, self.value)
+    }
+}
+
+impl From<&std::net::SocketAddr> for ffi::Endpoint {
+    fn from(addr: &std::net::SocketAddr) -> Self {
+        Self {
+            address: addr.ip().to_string(),
+            port: addr.port(),
+        }
+    }
+}
+
+impl ffi::Endpoint {
+    /// Parses the endpoint into a socket address. Returns `None` for
+    /// endpoints that were not set, such as on alerts without a peer.
+    pub fn to_socket_addr(&self) -> Option<std::net::SocketAddr> {
+        let ip = self.address.parse().ok()?;
+        Some(std::net::SocketAddr::new(ip, self.port))
+    }
+}
@@ -1,13 +1,54 @@
 #include "src/rb.hpp"
 #include "rbtorrent-sys/src/lib.rs.h"
 #include "libtorrent/disabled_disk_io.hpp"
+#include "libtorrent/aux_/merkle.hpp"
+#include <algorithm>
+#include <cerrno>
+#include <chrono>
+#include <condition_variable>
+#include <cstring>
+#include <exception>
+#include <fstream>
 #include <iterator>
+#include <mutex>
+#include <thread>
+#include <unordered_map>
 
 namespace libtorrent {
 
+static rust::Vec<uint8_t> to_byte_vec(const std::vector<char>& buf) {
+	rust::Vec<uint8_t> out;
+	out.reserve(buf.size());
+	std::copy(buf.begin(), buf.end(), std::back_inserter(out));
+	return out;
+}
+
+static load_torrent_limits to_load_torrent_limits(const LoadTorrentLimits& limits) {
+	load_torrent_limits cfg;
+	cfg.max_buffer_size = limits.max_buffer_size;
+	cfg.max_pieces = limits.max_pieces;
+	cfg.max_decode_depth = limits.max_decode_depth;
+	cfg.max_decode_tokens = limits.max_decode_tokens;
+	return cfg;
+}
+
+// libtorrent only enforces max_buffer_size when reading from a file
+static span<char const> to_torrent_span(rust::Slice<const uint8_t> buf, const LoadTorrentLimits& limits) {
+	if (buf.size() > static_cast<std::size_t>(limits.max_buffer_size)) {
+		throw system_error(errors::metadata_too_large);
+	}
+	return span<char const>(reinterpret_cast<char const*>(buf.data()), buf.size());
+}
+
 std::unique_ptr<session_params> new_session_params() {
 	return std::make_unique<session_params>();
 }
+
+std::unique_ptr<session_params> new_session_params_from_state(rust::Slice<const uint8_t> buf) {
+	span<char const> state(reinterpret_cast<char const*>(buf.data()), buf.size());
+	return std::make_unique<session_params>(read_session_params(state));
+}
+
 void session_params_set_user_agent(session_params& params, rust::Str user_agent) {
 	params.settings.set_str(settings_pack::user_agent, std::string(user_agent));
 }
@@ -28,6 +69,50 @@ void session_params_disable_disk(session_params& params) {
 	params.disk_io_constructor = disabled_disk_io_constructor;
 }
 
+settings_pack& session_params_get_settings(session_params& params) {
+	return params.settings;
+}
+
+std::unique_ptr<settings_pack> new_settings_pack() {
+	return std::make_unique<settings_pack>();
+}
+
+static int setting_index(rust::Str name, int type_base) {
+	int const s = lt::setting_by_name(lt::string_view(name.data(), name.size()));
+
+	if (s < 0 || (s & settings_pack::type_mask) != type_base) {
+		throw std::invalid_argument("unknown setting: " + std::string(name));
+	}
+
+	return s;
+}
+
+void settings_pack_set_int(settings_pack& pack, rust::Str name, int value) {
+	pack.set_int(setting_index(name, settings_pack::int_type_base), value);
+}
+
+void settings_pack_set_bool(settings_pack& pack, rust::Str name, bool value) {
+	pack.set_bool(setting_index(name, settings_pack::bool_type_base), value);
+}
+
+void settings_pack_set_str(settings_pack& pack, rust::Str name, rust::Str value) {
+	pack.set_str(setting_index(name, settings_pack::string_type_base), std::string(value));
+}
+
+SettingKind setting_kind(rust::Str name) {
+	int const s = lt::setting_by_name(lt::string_view(name.data(), name.size()));
+
+	if (s < 0) {
+		throw std::invalid_argument("unknown setting: " + std::string(name));
+	}
+
+	switch (s & settings_pack::type_mask) {
+	case settings_pack::int_type_base: return SettingKind::Int;
+	case settings_pack::bool_type_base: return SettingKind::Bool;
+	default: return SettingKind::Str;
+	}
+}
+
 std::unique_ptr<session> new_session(std::unique_ptr<session_params> params) {
 	return std::make_unique<session>(std::move(*params));
 }
@@ -41,8 +126,17 @@ std::unique_ptr<add_torrent_params> new_add_torrent_params_from_magnet_uri(rust:
 	return std::make_unique<add_torrent_params>(std::move(p));
 }
 
-std::unique_ptr<add_torrent_params> new_add_torrent_params_from_torrent_file(rust::Str path) {
-	return std::make_unique<add_torrent_params>(load_torrent_file(std::string(path)));
+std::unique_ptr<add_torrent_params> new_add_torrent_params_from_torrent_file(rust::Str path, const LoadTorrentLimits& limits) {
+	return std::make_unique<add_torrent_params>(load_torrent_file(std::string(path), to_load_torrent_limits(limits)));
+}
+
+std::unique_ptr<add_torrent_params> new_add_torrent_params_from_torrent_buffer(rust::Slice<const uint8_t> buf, const LoadTorrentLimits& limits) {
+	return std::make_unique<add_torrent_params>(load_torrent_buffer(to_torrent_span(buf, limits), to_load_torrent_limits(limits)));
+}
+
+std::unique_ptr<add_torrent_params> new_add_torrent_params_from_resume_data(rust::Slice<const uint8_t> buf) {
+	span<char const> resume_data(reinterpret_cast<char const*>(buf.data()), buf.size());
+	return std::make_unique<add_torrent_params>(read_resume_data(resume_data));
 }
 
 void add_torrent_params_set_save_path(add_torrent_params& params, rust::Str path) {
@@ -61,6 +155,94 @@ void add_torrent_params_set_torrent_flags(add_torrent_params& params, uint64_t t
 	params.flags = torrent_flags_t(torrent_flags);
 }
 
+void add_torrent_params_set_name(add_torrent_params& params, rust::Str name) {
+	params.name = std::string(name);
+}
+
+void add_torrent_params_set_file_priorities(add_torrent_params& params, rust::Slice<const uint8_t> priorities) {
+	params.file_priorities.clear();
+
+	for (auto prio : priorities) {
+		params.file_priorities.push_back(download_priority_t(prio));
+	}
+}
+
+void add_torrent_params_set_piece_priorities(add_torrent_params& params, rust::Slice<const uint8_t> priorities) {
+	params.piece_priorities.clear();
+
+	for (auto prio : priorities) {
+		params.piece_priorities.push_back(download_priority_t(prio));
+	}
+}
+
+void add_torrent_params_set_url_seeds(add_torrent_params& params, rust::Slice<const rust::Str> url_seeds) {
+	params.url_seeds.clear();
+
+	for (auto url : url_seeds) {
+		params.url_seeds.push_back(std::string(url));
+	}
+}
+
+void add_torrent_params_set_dht_nodes(add_torrent_params& params, rust::Slice<const TorrentInfoNode> nodes) {
+	params.dht_nodes.clear();
+
+	for (const auto& node : nodes) {
+		params.dht_nodes.emplace_back(std::string(node.hostname), int(node.port));
+	}
+}
+
+static tcp::endpoint make_endpoint(const Endpoint& ep) {
+	return tcp::endpoint(boost::asio::ip::make_address(std::string(ep.address)), ep.port);
+}
+
+void add_torrent_params_set_peers(add_torrent_params& params, rust::Slice<const Endpoint> peers) {
+	params.peers.clear();
+
+	for (const auto& peer : peers) {
+		params.peers.push_back(make_endpoint(peer));
+	}
+}
+
+void add_torrent_params_set_banned_peers(add_torrent_params& params, rust::Slice<const Endpoint> peers) {
+	params.banned_peers.clear();
+
+	for (const auto& peer : peers) {
+		params.banned_peers.push_back(make_endpoint(peer));
+	}
+}
+
+void add_torrent_params_set_max_connections(add_torrent_params& params, int max_connections) {
+	params.max_connections = max_connections;
+}
+
+void add_torrent_params_set_max_uploads(add_torrent_params& params, int max_uploads) {
+	params.max_uploads = max_uploads;
+}
+
+void add_torrent_params_set_upload_limit(add_torrent_params& params, int upload_limit) {
+	params.upload_limit = upload_limit;
+}
+
+void add_torrent_params_set_download_limit(add_torrent_params& params, int download_limit) {
+	params.download_limit = download_limit;
+}
+
+void add_torrent_params_set_storage_mode(add_torrent_params& params, storage_mode_t storage_mode) {
+	params.storage_mode = storage_mode;
+}
+
+void add_torrent_params_set_renamed_files(add_torrent_params& params, rust::Slice<const RenamedFile> renamed_files) {
+	params.renamed_files.clear();
+
+	for (const auto& f : renamed_files) {
+		params.renamed_files[file_index_t(f.index)] = std::string(f.path);
+	}
+}
+
+rust::Vec<uint8_t> add_torrent_params_write_resume_data(const add_torrent_params& params) {
+	return to_byte_vec(write_resume_data_buf(params));
+}
+
 std::unique_ptr<torrent_handle> session_add_torrent(session& ses, add_torrent_params& params) {
 	torrent_handle hdl;
 
@@ -77,6 +259,70 @@ void session_pause(session& ses) {
 	ses.pause();
 }
 
+std::unique_ptr<session_proxy> session_abort(session& ses) {
+	return std::make_unique<session_proxy>(ses.abort());
+}
+
+void session_apply_settings(session& ses, const settings_pack& pack) {
+	ses.apply_settings(pack);
+}
+
+std::unique_ptr<std::vector<torrent_handle>> session_get_torrents(const session& ses) {
+	return std::make_unique<std::vector<torrent_handle>>(ses.get_torrents());
+}
+
+rust::Vec<uint8_t> session_save_state(const session& ses, uint32_t flags) {
+	auto const state_flags = save_state_flags_t(flags);
+	return to_byte_vec(write_session_params_buf(ses.session_state(state_flags), state_flags));
+}
+
+rust::Vec<SettingEntry> session_get_settings(const session& ses) {
+	auto const pack = ses.get_settings();
+
+	rust::Vec<SettingEntry> out;
+
+	for (int i = 0; i < settings_pack::num_string_settings; ++i) {
+		int const s = settings_pack::string_type_base + i;
+		char const* name = name_for_setting(s);
+		if (*name == '\0') continue;
+		out.push_back(SettingEntry {
+			name: rust::String(name),
+			kind: SettingKind::Str,
+			int_value: 0,
+			bool_value: false,
+			str_value: rust::String(pack.get_str(s)),
+		});
+	}
+
+	for (int i = 0; i < settings_pack::num_int_settings; ++i) {
+		int const s = settings_pack::int_type_base + i;
+		char const* name = name_for_setting(s);
+		if (*name == '\0') continue;
+		out.push_back(SettingEntry {
+			name: rust::String(name),
+			kind: SettingKind::Int,
+			int_value: pack.get_int(s),
+			bool_value: false,
+			str_value: rust::String(),
+		});
+	}
+
+	for (int i = 0; i < settings_pack::num_bool_settings; ++i) {
+		int const s = settings_pack::bool_type_base + i;
+		char const* name = name_for_setting(s);
+		if (*name == '\0') continue;
+		out.push_back(SettingEntry {
+			name: rust::String(name),
+			kind: SettingKind::Bool,
+			int_value: 0,
+			bool_value: pack.get_bool(s),
+			str_value: rust::String(),
+		});
+	}
+
+	return out;
+}
+
 rust::Vec<Alert> session_get_alerts(session& ses) {
 	std::vector<alert*> alerts;
 
@@ -91,8 +337,15 @@ rust::Vec<Alert> session_get_alerts(session& ses) {
 	return out;
 }
 
+void session_set_alert_notify(session& ses, rust::Box<AlertNotify> notify) {
+	// std::function must be copyable, rust::Box is move-only
+	auto n = std::make_shared<rust::Box<AlertNotify>>(std::move(notify));
+
+	ses.set_alert_notify([n] { (*n)->notify(); });
+}
+
 rust::String alert_message(const Alert& alert) {
-	return rust::String(alert.inner->message());
+	return rust::String::lossy(alert.inner->message());
 }
 
 metadata_received_alert* alert_cast_metadata_received(Alert alert) {
@@ -107,10 +360,24 @@ save_resume_data_alert* alert_cast_save_resume_data(Alert alert) {
 	return alert_cast<save_resume_data_alert>(alert.inner);
 }
 
+static rust::Vec<uint8_t> to_resume_data(const add_torrent_params& params) {
+	return to_byte_vec(write_resume_data_buf(params));
+}
+
+rust::Vec<uint8_t> save_resume_data_alert_get_resume_data(save_resume_data_alert* alert) {
+	return to_resume_data(alert->params);
+}
+
 save_resume_data_failed_alert* alert_cast_save_resume_data_failed(Alert alert) {
 	return alert_cast<save_resume_data_failed_alert>(alert.inner);
 }
 
+static ErrorCode to_error_code(const error_code& ec);
+
+ErrorCode save_resume_data_failed_alert_get_error(save_resume_data_failed_alert* alert) {
+	return to_error_code(alert->error);
+}
+
 void wait_for_alert(session& ses, uint64_t max_duration) {
 	ses.wait_for_alert(std::chrono::milliseconds(max_duration));
 }
@@ -119,27 +386,130 @@ bool torrent_has_metadata(const torrent_handle& hdl) {
 	return hdl.status().has_metadata;
 }
 
-rust::Str torrent_get_name(const torrent_handle& hdl) {
-	auto infos = hdl.torrent_file();
-	return infos->name();
+rust::String torrent_get_name(const torrent_handle& hdl) {
+	return rust::String::lossy(hdl.status(torrent_handle::query_name).name);
+}
+
+static InfoHashes to_info_hashes(const info_hash_t& ih) {
+	InfoHashes out{};
+	if (ih.has_v1()) std::memcpy(out.v1.data(), ih.v1.data(), out.v1.size());
+	if (ih.has_v2()) std::memcpy(out.v2.data(), ih.v2.data(), out.v2.size());
+	return out;
+}
+
+static rust::Vec<uint8_t> to_piece_vec(const typed_bitfield<piece_index_t>& pieces) {
+	rust::Vec<uint8_t> out;
+	out.reserve(pieces.size());
+	for (bool have : pieces) out.push_back(have);
+	return out;
 }
 
-TorrentStatus torrent_get_status(const torrent_handle& hdl) {
-	auto s = hdl.status();
+// translates a time point of libtorrent's monotonic clock to microseconds
+// since the Unix epoch, time points that were never set become zero
+static int64_t to_unix_us(time_point t) {
+	if (t.time_since_epoch().count() <= 0) return 0;
 
+	auto const ts = std::chrono::system_clock::now()
+		- std::chrono::duration_cast<std::chrono::system_clock::duration>(clock_type::now() - t);
+	return std::chrono::duration_cast<std::chrono::microseconds>(ts.time_since_epoch()).count();
+}
+
+static TorrentStatus to_torrent_status(const torrent_status& s) {
 	return TorrentStatus {
+		info_hashes: to_info_hashes(s.info_hashes),
 		state: s.state,
 		progress_ppm: uint64_t(s.progress_ppm),
+		errc: to_error_code(s.errc),
+		error_file: static_cast<int>(s.error_file),
+		save_path: rust::String::lossy(s.save_path),
+		name: rust::String::lossy(s.name),
+		current_tracker: rust::String::lossy(s.current_tracker),
+		next_announce_s: s.next_announce.count(),
+		total_download: s.total_download,
+		total_upload: s.total_upload,
+		total_payload_download: s.total_payload_download,
+		total_payload_upload: s.total_payload_upload,
+		total_failed_bytes: s.total_failed_bytes,
+		total_redundant_bytes: s.total_redundant_bytes,
+		total_done: s.total_done,
+		total: s.total,
+		total_wanted_done: s.total_wanted_done,
+		total_wanted: s.total_wanted,
+		all_time_upload: s.all_time_upload,
+		all_time_download: s.all_time_download,
+		added_time: int64_t(s.added_time),
+		completed_time: int64_t(s.completed_time),
+		last_seen_complete: int64_t(s.last_seen_complete),
+		last_upload_us: to_unix_us(s.last_upload),
+		last_download_us: to_unix_us(s.last_download),
+		active_duration_s: s.active_duration.count(),
+		finished_duration_s: s.finished_duration.count(),
+		seeding_duration_s: s.seeding_duration.count(),
+		storage_mode: s.storage_mode,
+		queue_position: static_cast<int>(s.queue_position),
+		download_rate: s.download_rate,
+		upload_rate: s.upload_rate,
+		download_payload_rate: s.download_payload_rate,
+		upload_payload_rate: s.upload_payload_rate,
+		num_seeds: s.num_seeds,
+		num_peers: s.num_peers,
+		num_complete: s.num_complete,
+		num_incomplete: s.num_incomplete,
+		list_seeds: s.list_seeds,
+		list_peers: s.list_peers,
+		connect_candidates: s.connect_candidates,
+		num_pieces: s.num_pieces,
+		distributed_full_copies: s.distributed_full_copies,
+		distributed_fraction: s.distributed_fraction,
+		distributed_copies: s.distributed_copies,
+		block_size: s.block_size,
+		num_uploads: s.num_uploads,
+		num_connections: s.num_connections,
+		uploads_limit: s.uploads_limit,
+		connections_limit: s.connections_limit,
+		up_bandwidth_queue: s.up_bandwidth_queue,
+		down_bandwidth_queue: s.down_bandwidth_queue,
+		seed_rank: s.seed_rank,
+		need_save_resume: s.need_save_resume,
+		is_seeding: s.is_seeding,
+		is_finished: s.is_finished,
+		has_metadata: s.has_metadata,
+		has_incoming: s.has_incoming,
+		moving_storage: s.moving_storage,
+		announcing_to_trackers: s.announcing_to_trackers,
+		announcing_to_lsd: s.announcing_to_lsd,
+		announcing_to_dht: s.announcing_to_dht,
+		flags: static_cast<std::uint64_t>(s.flags),
+		pieces: to_piece_vec(s.pieces),
+		verified_pieces: to_piece_vec(s.verified_pieces),
 	};
 }
 
-rust::Slice<const uint8_t> torrent_bencode(const torrent_handle& hdl) {
-	auto infos = hdl.torrent_file();
-	auto entry = create_torrent(*infos).generate();
-	std::vector<char> vec;
-	bencode(std::back_inserter(vec), entry);
-	rust::Slice<const uint8_t> slice{reinterpret_cast<const unsigned char *>(vec.data()), vec.size()};
-	return slice;
+TorrentStatus torrent_get_status(const torrent_handle& hdl, uint32_t flags) {
+	return to_torrent_status(hdl.status(status_flags_t(flags)));
+}
+
+void torrent_save_resume_data(const torrent_handle& hdl, uint8_t flags) {
+	hdl.save_resume_data(resume_data_flags_t(flags));
+}
+
+std::unique_ptr<torrent_handle> torrent_handle_clone(const torrent_handle& hdl) {
+	return std::make_unique<torrent_handle>(hdl);
+}
+
+bool torrent_is_valid(const torrent_handle& hdl) {
+	return hdl.is_valid();
+}
+
+InfoHashes torrent_get_info_hashes(const torrent_handle& hdl) {
+	return to_info_hashes(hdl.info_hashes());
+}
+
+std::unique_ptr<torrent_info> torrent_get_torrent_file(const torrent_handle& hdl) {
+	auto ti = hdl.torrent_file_with_hashes();
+	if (!ti) return nullptr;
+
+	return std::make_unique<torrent_info>(*ti);
 }
 
 static std::string branch_path(std::string const& f) {
@@ -163,25 +533,327 @@ static std::string branch_path(std::string const& f) {
 	return std::string(f.c_str(), len);
 }
 
-rust::Vec<uint8_t> create_torrent_for_path(rust::Str path) {
+static void set_metadata(create_torrent& t, const CreateTorrentParams& params) {
+	for (const auto& tracker : params.trackers) {
+		t.add_tracker(std::string(tracker.url), tracker.tier);
+	}
+	for (const auto& url : params.web_seeds) {
+		t.add_url_seed(std::string(url));
+	}
+	for (const auto& node : params.nodes) {
+		t.add_node(std::make_pair(std::string(node.hostname), int(node.port)));
+	}
+	for (const auto& collection : params.collections) {
+		t.add_collection(std::string(collection));
+	}
+	for (std::size_t i = 0; i + sha1_hash::size() <= params.similar_torrents.size(); i += sha1_hash::size()) {
+		t.add_similar_torrent(sha1_hash(reinterpret_cast<char const*>(params.similar_torrents.data() + i)));
+	}
+	t.set_comment(std::string(params.comment).c_str());
+	t.set_creator(std::string(params.creator).c_str());
+	t.set_creation_date(std::time_t(params.creation_date));
+	t.set_priv(params.is_private);
+}
+
+static rust::Vec<uint8_t> to_torrent_file(create_torrent& t, const CreateTorrentParams& params) {
+	auto e = t.generate();
+	if (!params.source.empty()) e["info"]["source"] = std::string(params.source);
+
+	rust::Vec<uint8_t> torrent;
+	bencode(std::back_inserter(torrent), e);
+	return torrent;
+}
+
+// hashes of a piece, computed by one of the hashing threads
+struct piece_hashes {
+	sha1_hash v1;
+	sha256_hash v2;
+	bool done = false;
+};
+
+// reads and hashes pieces the way set_piece_hashes() does, each hashing
+// thread has its own
+class piece_hasher {
+public:
+	piece_hasher(create_torrent const& t, create_flags_t flags)
+		: m_fs(t.files())
+		, m_v1(!(flags & create_torrent::v2_only))
+		, m_v2(!(flags & create_torrent::v1_only))
+		, m_piece_length(t.piece_length())
+		, m_buf(std::size_t(m_piece_length), 0) {}
+
+	// `read(file, offset, buf)` fills the buffer with the content of a file
+	template <typename Read>
+	piece_hashes hash(piece_index_t piece, Read& read) {
+		piece_hashes out;
+		int const size = m_fs.piece_size(piece);
+
+		int pos = 0;
+		for (auto const& slice : m_fs.map_block(piece, 0, size)) {
+			auto* const p = m_buf.data() + pos;
+			if (m_fs.pad_file_at(slice.file_index)) {
+				std::memset(p, 0, std::size_t(slice.size));
+			} else if (slice.size > 0) {
+				read(slice.file_index, slice.offset, span<char>(p, slice.size));
+			}
+			pos += int(slice.size);
+		}
+
+		if (m_v1) out.v1 = hasher(m_buf.data(), size).final();
+
+		// with v2, each piece belongs to a single file and its hash is the
+		// root of the subtree of its blocks. For files smaller than a piece,
+		// the tree is only padded up to the next power of two.
+		auto const file = m_fs.file_index_at_piece(piece);
+		if (m_v2 && !m_fs.pad_file_at(file)) {
+			int const size2 = m_fs.piece_size2(piece);
+			m_blocks.clear();
+			for (int offset = 0; offset < size2; offset += default_block_size) {
+				m_blocks.push_back(hasher256(m_buf.data() + offset, std::min(default_block_size, size2 - offset)).final());
+			}
+
+			int const num_leaves = m_fs.file_size(file) <= m_piece_length
+				? aux::merkle_num_leafs(m_fs.file_num_blocks(file))
+				: m_piece_length / default_block_size;
+			out.v2 = aux::merkle_root_scratch(m_blocks, num_leaves, sha256_hash(), m_scratch);
+		}
+
+		out.done = true;
+		return out;
+	}
+
+private:
+	file_storage const& m_fs;
+	bool m_v1;
+	bool m_v2;
+	int m_piece_length;
+	std::vector<char> m_buf;
+	std::vector<sha256_hash> m_blocks;
+	std::vector<sha256_hash> m_scratch;
+};
+
+// hashes the pieces of `t` on `num_threads` threads, each reading the files
+// through its own `make_read()`. The hashes are set and `progress` is called
+// on this thread in piece order, so the torrent doesn't depend on the number
+// of threads. Returns false if `progress` stopped it.
+template <typename MakeRead>
+static bool hash_pieces(create_torrent& t, create_flags_t flags, int num_threads, MakeRead const& make_read, HashProgress& progress) {
+	auto const& fs = t.files();
+	bool const v1 = !(flags & create_torrent::v2_only);
+	bool const v2 = !(flags & create_torrent::v1_only);
+	int const total = t.num_pieces();
+	num_threads = std::max(1, std::min(num_threads, total));
+	// how far the threads may get ahead of this one, pieces hashed past the
+	// point the hashing is stopped at are wasted
+	int const window = num_threads * 4;
+
+	std::mutex mutex;
+	std::condition_variable cond;
+	std::vector<piece_hashes> hashes(std::size_t(total));
+	int next = 0;
+	int set = 0;
+	bool stop = false;
+	std::exception_ptr error;
+
+	std::vector<std::thread> threads;
+	auto join = [&] {
+		{
+			std::lock_guard<std::mutex> l(mutex);
+			stop = true;
+		}
+		cond.notify_all();
+		for (auto& th : threads) th.join();
+		threads.clear();
+	};
+	// the threads are joined however this function is left
+	struct joiner {
+		decltype(join)& f;
+		~joiner() { f(); }
+	} const joined{join};
+
+	for (int i = 0; i < num_threads; ++i) {
+		threads.emplace_back([&] {
+			try {
+				auto read = make_read();
+				piece_hasher h(t, flags);
+				for (;;) {
+					int piece;
+					{
+						std::unique_lock<std::mutex> l(mutex);
+						cond.wait(l, [&] { return stop || error || next == total || next < set + window; });
+						if (stop || error || next == total) return;
+						piece = next++;
+					}
+
+					auto const out = h.hash(piece_index_t(piece), read);
+					{
+						std::lock_guard<std::mutex> l(mutex);
+						hashes[std::size_t(piece)] = out;
+					}
+					cond.notify_all();
+				}
+			} catch (...) {
+				{
+					std::lock_guard<std::mutex> l(mutex);
+					if (!error) error = std::current_exception();
+				}
+				cond.notify_all();
+			}
+		});
+	}
+
+	for (piece_index_t piece(0); piece < fs.end_piece(); ++piece) {
+		piece_hashes h;
+		{
+			std::unique_lock<std::mutex> l(mutex);
+			cond.wait(l, [&] { return error || hashes[std::size_t(static_cast<int>(piece))].done; });
+			if (error) break;
+			h = hashes[std::size_t(static_cast<int>(piece))];
+			set = static_cast<int>(piece) + 1;
+		}
+		cond.notify_all();
+
+		if (v1) t.set_hash(piece, h.v1);
+
+		auto const file = fs.file_index_at_piece(piece);
+		if (v2 && !fs.pad_file_at(file)) {
+			piece_index_t const first_piece(int(fs.file_offset(file) / t.piece_length()));
+			t.set_hash2(file, piece - first_piece, h.v2);
+		}
+
+		if (!progress.progress(static_cast<int>(piece) + 1, total)) return false;
+	}
+
+	// the error of a thread is raised on this one
+	join();
+	if (error) std::rethrow_exception(error);
+	return true;
+}
+
+// reads the files of a torrent from under `base`, keeping the last one open
+class disk_reader {
+public:
+	disk_reader(file_storage const& fs, std::string base) : m_fs(fs), m_base(std::move(base)) {}
+
+	void operator()(file_index_t file, std::int64_t offset, span<char> buf) {
+		if (file != m_file) {
+			m_in = std::ifstream(m_fs.file_path(file, m_base), std::ios::binary);
+			if (!m_in) throw system_error(error_code(errno, generic_category()));
+			m_file = file;
+		}
+
+		m_in.seekg(offset);
+		m_in.read(buf.data(), buf.size());
+		if (m_in.gcount() != buf.size()) {
+			// the file shrank since it was added
+			m_file = file_index_t(-1);
+			throw system_error(errors::file_too_short);
+		}
+	}
+
+private:
+	file_storage const& m_fs;
+	std::string m_base;
+	file_index_t m_file{-1};
+	std::ifstream m_in;
+};
+
+rust::Vec<uint8_t> create_torrent_generate(const CreateTorrentParams& params, FileFilter& filter, HashProgress& progress) {
 	file_storage fs;
 
-	auto p = std::string(path);
+	auto const path = std::string(params.path);
+	auto const flags = create_flags_t(params.flags);
 
-	add_files(fs, p);
+	// the filter doesn't throw, add_files() can't be stopped through it
+	add_files(fs, path, [&](std::string const& p) {
+		return filter.include(rust::String::lossy(p));
+	}, flags);
+	if (filter.panicked()) throw system_error(boost::asio::error::operation_aborted);
+	if (fs.num_files() == 0) throw system_error(errors::no_files_in_torrent);
 
-	create_torrent t(fs);
+	create_torrent t(fs, params.piece_size, flags);
+	set_metadata(t, params);
 
-	set_piece_hashes(t, branch_path(p));
+	auto const base = branch_path(path);
+	auto make_read = [&] { return disk_reader(t.files(), base); };
+	if (!hash_pieces(t, flags, params.hashing_threads, make_read, progress))
+		throw system_error(boost::asio::error::operation_aborted);
 
-	rust::Vec<uint8_t> torrent;
-	bencode(std::back_inserter(torrent), t.generate());
+	return to_torrent_file(t, params);
+}
 
-	return torrent;
+rust::Vec<uint8_t> create_torrent_generate_with_libtorrent(const CreateTorrentParams& params) {
+	file_storage fs;
+
+	auto const path = std::string(params.path);
+	auto const flags = create_flags_t(params.flags);
+
+	add_files(fs, path, flags);
+	if (fs.num_files() == 0) throw system_error(errors::no_files_in_torrent);
+
+	create_torrent t(fs, params.piece_size, flags);
+	set_metadata(t, params);
+
+	settings_pack sett;
+	if (params.hashing_threads > 0) sett.set_int(settings_pack::hashing_threads, params.hashing_threads);
+	set_piece_hashes(t, branch_path(path), sett, [](piece_index_t) {});
+
+	return to_torrent_file(t, params);
 }
 
-std::unique_ptr<torrent_info> open_torrent_info(rust::Str path) {
-	return std::make_unique<torrent_info>(std::string(path));
+rust::Vec<uint8_t> create_torrent_generate_from_files(const CreateTorrentParams& params, rust::Slice<const VirtualFileEntry> files, const FileReader& reader, HashProgress& progress) {
+	file_storage fs;
+
+	auto const flags = create_flags_t(params.flags);
+
+	// v2 and canonical torrents reorder the files, the reader is called with
+	// the index in `files`
+	std::unordered_map<std::string, std::uint32_t> indices;
+	for (std::size_t i = 0; i < files.size(); ++i) {
+		auto const& file = files[i];
+		error_code ec;
+		fs.add_file(ec, std::string(file.path), file.size, file_flags_t(file.flags), std::time_t(file.mtime));
+		if (ec) throw system_error(ec);
+		indices[fs.file_path(fs.last_file())] = std::uint32_t(i);
+	}
+	if (fs.num_files() == 0) throw system_error(errors::no_files_in_torrent);
+
+	create_torrent t(fs, params.piece_size, flags);
+	set_metadata(t, params);
+
+	// pad files are never read
+	std::vector<std::uint32_t> file_indices;
+	for (auto const i : t.files().file_range()) {
+		auto const path = t.files().file_path(i);
+		auto const it = indices.find(path);
+		if (it != indices.end()) {
+			file_indices.push_back(it->second);
+		} else if (t.files().pad_file_at(i)) {
+			file_indices.push_back(0);
+		} else {
+			throw std::invalid_argument("no virtual file for path: " + path);
+		}
+	}
+
+	// the reader is shared by the hashing threads
+	auto make_read = [&] {
+		return [&](file_index_t file, std::int64_t offset, span<char> buf) {
+			reader.read(file_indices[std::size_t(static_cast<int>(file))], std::uint64_t(offset),
+				rust::Slice<uint8_t>(reinterpret_cast<uint8_t*>(buf.data()), std::size_t(buf.size())));
+		};
+	};
+	if (!hash_pieces(t, flags, params.hashing_threads, make_read, progress))
+		throw system_error(boost::asio::error::operation_aborted);
+
+	return to_torrent_file(t, params);
+}
+
+std::unique_ptr<torrent_info> open_torrent_info(rust::Str path, const LoadTorrentLimits& limits) {
+	return std::make_unique<torrent_info>(std::string(path), to_load_torrent_limits(limits));
+}
+
+std::unique_ptr<torrent_info> new_torrent_info_from_buffer(rust::Slice<const uint8_t> buf, const LoadTorrentLimits& limits) {
+	return std::make_unique<torrent_info>(to_torrent_span(buf, limits), to_load_torrent_limits(limits), from_span);
 }
 
 rust::Vec<TorrentInfoNode> torrent_info_nodes(const lt::torrent_info& info) {
@@ -190,7 +862,7 @@ rust::Vec<TorrentInfoNode> torrent_info_nodes(const lt::torrent_info& info) {
 	auto nodes = info.nodes();
 	for (const auto& node : nodes) {
 		v.push_back(TorrentInfoNode {
-			hostname: rust::String(node.first),
+			hostname: rust::String::lossy(node.first),
 			port: uint16_t(node.second),
 		});
 	}
@@ -211,4 +883,896 @@ rust::Vec<AnnounceEntry> torrent_info_trackers(const lt::torrent_info& info) {
 	return v;
 }
 
+rust::String torrent_info_name(const torrent_info& info) {
+	return rust::String::lossy(info.name());
+}
+
+rust::String torrent_info_comment(const torrent_info& info) {
+	return rust::String::lossy(info.comment());
+}
+
+rust::String torrent_info_creator(const torrent_info& info) {
+	return rust::String::lossy(info.creator());
+}
+
+int64_t torrent_info_creation_date(const torrent_info& info) {
+	return static_cast<int64_t>(info.creation_date());
+}
+
+int64_t torrent_info_total_size(const torrent_info& info) {
+	return info.total_size();
+}
+
+int torrent_info_piece_length(const torrent_info& info) {
+	return info.piece_length();
+}
+
+int torrent_info_num_pieces(const torrent_info& info) {
+	return info.num_pieces();
+}
+
+bool torrent_info_priv(const torrent_info& info) {
+	return info.priv();
+}
+
+bool torrent_info_is_i2p(const torrent_info& info) {
+	return info.is_i2p();
+}
+
+InfoHashes torrent_info_info_hashes(const torrent_info& info) {
+	return to_info_hashes(info.info_hashes());
+}
+
+rust::Vec<WebSeedEntry> torrent_info_web_seeds(const torrent_info& info) {
+	rust::Vec<WebSeedEntry> v;
+
+	for (const auto& e : info.web_seeds()) {
+		rust::Vec<HttpHeader> headers;
+		for (const auto& h : e.extra_headers) {
+			headers.push_back(HttpHeader {
+				name: rust::String::lossy(h.first),
+				value: rust::String::lossy(h.second),
+			});
+		}
+
+		v.push_back(WebSeedEntry {
+			url: rust::String::lossy(e.url),
+			kind: e.type == web_seed_entry::url_seed ? WebSeedKind::UrlSeed : WebSeedKind::HttpSeed,
+			auth: rust::String::lossy(e.auth),
+			extra_headers: std::move(headers),
+		});
+	}
+
+	return v;
+}
+
+rust::Vec<uint8_t> torrent_info_similar_torrents(const torrent_info& info) {
+	rust::Vec<uint8_t> v;
+
+	for (const auto& h : info.similar_torrents()) {
+		std::copy(h.begin(), h.end(), std::back_inserter(v));
+	}
+
+	return v;
+}
+
+rust::Vec<rust::String> torrent_info_collections(const torrent_info& info) {
+	rust::Vec<rust::String> v;
+
+	for (const auto& c : info.collections()) {
+		v.push_back(rust::String::lossy(c));
+	}
+
+	return v;
+}
+
+rust::String torrent_info_ssl_cert(const torrent_info& info) {
+	string_view const cert = info.ssl_cert();
+	return rust::String::lossy(cert.data(), cert.size());
+}
+
+rust::Vec<uint8_t> torrent_info_info_section(const torrent_info& info) {
+	span<char const> const section = info.info_section();
+
+	rust::Vec<uint8_t> v;
+	v.reserve(section.size());
+	std::copy(section.begin(), section.end(), std::back_inserter(v));
+	return v;
+}
+
+const file_storage& torrent_info_files(const torrent_info& info) {
+	return info.files();
+}
+
+int file_storage_num_files(const file_storage& fs) {
+	return fs.num_files();
+}
+
+int file_storage_num_pieces(const file_storage& fs) {
+	return fs.num_pieces();
+}
+
+int64_t file_storage_total_size(const file_storage& fs) {
+	return fs.total_size();
+}
+
+int file_storage_piece_size(const file_storage& fs, int piece) {
+	return fs.piece_size(piece_index_t(piece));
+}
+
+int64_t file_storage_file_size(const file_storage& fs, int index) {
+	return fs.file_size(file_index_t(index));
+}
+
+FileStorageEntry file_storage_file_entry(const file_storage& fs, int index) {
+	file_index_t const idx(index);
+	file_flags_t const flags = fs.file_flags(idx);
+
+	FileStorageEntry e {
+		path: rust::String::lossy(fs.file_path(idx)),
+		size: fs.file_size(idx),
+		offset: fs.file_offset(idx),
+		flags: static_cast<std::uint8_t>(flags),
+		symlink: rust::String(),
+		mtime: static_cast<int64_t>(fs.mtime(idx)),
+		root: {},
+	};
+
+	if (flags & file_storage::flag_symlink) {
+		e.symlink = rust::String::lossy(fs.symlink(idx));
+	}
+
+	sha256_hash const root = fs.root(idx);
+	std::memcpy(e.root.data(), root.data(), e.root.size());
+
+	return e;
+}
+
+PeerRequest file_storage_map_file(const file_storage& fs, int index, int64_t offset, int size) {
+	peer_request const r = fs.map_file(file_index_t(index), offset, size);
+
+	return PeerRequest {
+		piece: static_cast<int>(r.piece),
+		start: r.start,
+		length: r.length,
+	};
+}
+
+rust::Vec<FileSlice> file_storage_map_block(const file_storage& fs, int piece, int64_t offset, int64_t size) {
+	rust::Vec<FileSlice> v;
+
+	for (const auto& s : fs.map_block(piece_index_t(piece), offset, size)) {
+		v.push_back(FileSlice {
+			file_index: static_cast<int>(s.file_index),
+			offset: s.offset,
+			size: s.size,
+		});
+	}
+
+	return v;
+}
+
+rust::Vec<uint8_t> torrent_info_write_torrent_file(const torrent_info& info, uint32_t flags) {
+	add_torrent_params atp;
+	atp.ti = std::make_shared<torrent_info>(info);
+
+	// write_torrent_file() takes the trackers and web seeds from the params,
+	// not from the torrent_info
+	for (auto const& ae : info.trackers()) {
+		atp.trackers.push_back(ae.url);
+		atp.tracker_tiers.push_back(ae.tier);
+	}
+	for (auto const& ws : info.web_seeds()) {
+		if (ws.type == web_seed_entry::url_seed) atp.url_seeds.push_back(ws.url);
+		else atp.http_seeds.push_back(ws.url);
+	}
+
+	return to_byte_vec(write_torrent_file_buf(atp, write_torrent_flags_t(flags)));
+}
+
+AlertCategoryBits alert_category_bits() {
+	return AlertCategoryBits {
+		error: static_cast<std::uint32_t>(alert_category::error),
+		peer: static_cast<std::uint32_t>(alert_category::peer),
+		port_mapping: static_cast<std::uint32_t>(alert_category::port_mapping),
+		storage: static_cast<std::uint32_t>(alert_category::storage),
+		tracker: static_cast<std::uint32_t>(alert_category::tracker),
+		connect: static_cast<std::uint32_t>(alert_category::connect),
+		status: static_cast<std::uint32_t>(alert_category::status),
+		ip_block: static_cast<std::uint32_t>(alert_category::ip_block),
+		performance_warning: static_cast<std::uint32_t>(alert_category::performance_warning),
+		dht: static_cast<std::uint32_t>(alert_category::dht),
+		session_log: static_cast<std::uint32_t>(alert_category::session_log),
+		torrent_log: static_cast<std::uint32_t>(alert_category::torrent_log),
+		peer_log: static_cast<std::uint32_t>(alert_category::peer_log),
+		incoming_request: static_cast<std::uint32_t>(alert_category::incoming_request),
+		dht_log: static_cast<std::uint32_t>(alert_category::dht_log),
+		dht_operation: static_cast<std::uint32_t>(alert_category::dht_operation),
+		port_mapping_log: static_cast<std::uint32_t>(alert_category::port_mapping_log),
+		picker_log: static_cast<std::uint32_t>(alert_category::picker_log),
+		file_progress: static_cast<std::uint32_t>(alert_category::file_progress),
+		piece_progress: static_cast<std::uint32_t>(alert_category::piece_progress),
+		upload: static_cast<std::uint32_t>(alert_category::upload),
+		block_progress: static_cast<std::uint32_t>(alert_category::block_progress),
+	};
+}
+
+TorrentFlagsBits torrent_flags_bits() {
+	return TorrentFlagsBits {
+		seed_mode: static_cast<std::uint64_t>(torrent_flags::seed_mode),
+		upload_mode: static_cast<std::uint64_t>(torrent_flags::upload_mode),
+		share_mode: static_cast<std::uint64_t>(torrent_flags::share_mode),
+		apply_ip_filter: static_cast<std::uint64_t>(torrent_flags::apply_ip_filter),
+		paused: static_cast<std::uint64_t>(torrent_flags::paused),
+		auto_managed: static_cast<std::uint64_t>(torrent_flags::auto_managed),
+		duplicate_is_error: static_cast<std::uint64_t>(torrent_flags::duplicate_is_error),
+		update_subscribe: static_cast<std::uint64_t>(torrent_flags::update_subscribe),
+		super_seeding: static_cast<std::uint64_t>(torrent_flags::super_seeding),
+		sequential_download: static_cast<std::uint64_t>(torrent_flags::sequential_download),
+		stop_when_ready: static_cast<std::uint64_t>(torrent_flags::stop_when_ready),
+		override_trackers: static_cast<std::uint64_t>(torrent_flags::override_trackers),
+		override_web_seeds: static_cast<std::uint64_t>(torrent_flags::override_web_seeds),
+		need_save_resume: static_cast<std::uint64_t>(torrent_flags::need_save_resume),
+		disable_dht: static_cast<std::uint64_t>(torrent_flags::disable_dht),
+		disable_lsd: static_cast<std::uint64_t>(torrent_flags::disable_lsd),
+		disable_pex: static_cast<std::uint64_t>(torrent_flags::disable_pex),
+		no_verify_files: static_cast<std::uint64_t>(torrent_flags::no_verify_files),
+		default_dont_download: static_cast<std::uint64_t>(torrent_flags::default_dont_download),
+		default_flags: static_cast<std::uint64_t>(torrent_flags::default_flags),
+	};
+}
+
+StatusFlagsBits status_flags_bits() {
+	return StatusFlagsBits {
+		query_distributed_copies: static_cast<std::uint32_t>(torrent_handle::query_distributed_copies),
+		query_accurate_download_counters: static_cast<std::uint32_t>(torrent_handle::query_accurate_download_counters),
+		query_last_seen_complete: static_cast<std::uint32_t>(torrent_handle::query_last_seen_complete),
+		query_pieces: static_cast<std::uint32_t>(torrent_handle::query_pieces),
+		query_verified_pieces: static_cast<std::uint32_t>(torrent_handle::query_verified_pieces),
+		query_torrent_file: static_cast<std::uint32_t>(torrent_handle::query_torrent_file),
+		query_name: static_cast<std::uint32_t>(torrent_handle::query_name),
+		query_save_path: static_cast<std::uint32_t>(torrent_handle::query_save_path),
+	};
+}
+
+SaveStateFlagsBits save_state_flags_bits() {
+	return SaveStateFlagsBits {
+		save_settings: static_cast<std::uint32_t>(session_handle::save_settings),
+		save_dht_state: static_cast<std::uint32_t>(session_handle::save_dht_state),
+		save_extension_state: static_cast<std::uint32_t>(session_handle::save_extension_state),
+		save_ip_filter: static_cast<std::uint32_t>(session_handle::save_ip_filter),
+	};
+}
+
+ResumeDataFlagsBits resume_data_flags_bits() {
+	return ResumeDataFlagsBits {
+		flush_disk_cache: static_cast<std::uint8_t>(torrent_handle::flush_disk_cache),
+		save_info_dict: static_cast<std::uint8_t>(torrent_handle::save_info_dict),
+		only_if_modified: static_cast<std::uint8_t>(torrent_handle::only_if_modified),
+	};
+}
+
+WriteTorrentFlagsBits write_torrent_flags_bits() {
+	return WriteTorrentFlagsBits {
+		allow_missing_piece_layer: static_cast<std::uint32_t>(write_flags::allow_missing_piece_layer),
+		no_http_seeds: static_cast<std::uint32_t>(write_flags::no_http_seeds),
+		include_dht_nodes: static_cast<std::uint32_t>(write_flags::include_dht_nodes),
+	};
+}
+
+FileFlagsBits file_flags_bits() {
+	return FileFlagsBits {
+		pad_file: static_cast<std::uint8_t>(file_storage::flag_pad_file),
+		hidden: static_cast<std::uint8_t>(file_storage::flag_hidden),
+		executable: static_cast<std::uint8_t>(file_storage::flag_executable),
+		symlink: static_cast<std::uint8_t>(file_storage::flag_symlink),
+	};
+}
+
+CreateFlagsBits create_flags_bits() {
+	return CreateFlagsBits {
+		modification_time: static_cast<std::uint32_t>(create_torrent::modification_time),
+		symlinks: static_cast<std::uint32_t>(create_torrent::symlinks),
+		v2_only: static_cast<std::uint32_t>(create_torrent::v2_only),
+		v1_only: static_cast<std::uint32_t>(create_torrent::v1_only),
+		canonical_files: static_cast<std::uint32_t>(create_torrent::canonical_files),
+		no_attributes: static_cast<std::uint32_t>(create_torrent::no_attributes),
+		canonical_files_no_tail_padding: static_cast<std::uint32_t>(create_torrent::canonical_files_no_tail_padding),
+	};
+}
+
+LoadTorrentLimits default_load_torrent_limits() {
+	load_torrent_limits const cfg;
+	return LoadTorrentLimits {
+		max_buffer_size: cfg.max_buffer_size,
+		max_pieces: cfg.max_pieces,
+		max_decode_depth: cfg.max_decode_depth,
+		max_decode_tokens: cfg.max_decode_tokens,
+	};
+}
+
+static ErrorCode to_error_code(const error_code& ec) {
+	return ErrorCode {
+		category: rust::String(ec.category().name()),
+		value: ec.value(),
+		message: rust::String::lossy(ec.message()),
+	};
+}
+
+static Endpoint to_endpoint(const address& addr, int port) {
+	return Endpoint {
+		address: rust::String(addr.to_string()),
+		port: uint16_t(port),
+	};
+}
+
+template <typename EP>
+static Endpoint to_endpoint(const EP& ep) {
+	return to_endpoint(ep.address(), ep.port());
+}
+
+template <typename Hash>
+static void copy_hash(const Hash& h, uint8_t* out) {
+	std::memcpy(out, h.data(), h.size());
+}
+
+template <typename Array>
+static void copy_bytes(const Array& a, uint8_t* out) {
+	std::memcpy(out, a.data(), a.size());
+}
+
+template <typename Buf>
+static void append_bytes(rust::Vec<uint8_t>& out, const Buf& buf) {
+	for (auto c : buf) {
+		out.push_back(uint8_t(c));
+	}
+}
+
+static void set_info_hashes(AlertData& d, const info_hash_t& ih) {
+	if (ih.has_v1()) copy_hash(ih.v1, d.hash_v1.data());
+	if (ih.has_v2()) copy_hash(ih.v2, d.hash_v2.data());
+}
+
+static void set_peer(AlertData& d, const peer_alert& a) {
+	d.endpoint = to_endpoint(a.endpoint);
+	copy_hash(a.pid, d.peer_id.data());
+}
+
+static void set_request(AlertData& d, const peer_request& r) {
+	d.piece = static_cast<int>(r.piece);
+	d.start = r.start;
+	d.length = r.length;
+}
+
+rust::String torrent_info_make_magnet_uri(const torrent_info& info) {
+	return rust::String::lossy(make_magnet_uri(info));
+}
+
+rust::String torrent_make_magnet_uri(const torrent_handle& hdl) {
+	std::string const uri = make_magnet_uri(hdl);
+	if (uri.empty()) throw system_error(errors::invalid_torrent_handle);
+	return rust::String::lossy(uri);
+}
+
+MagnetUriParams magnet_uri_parse(rust::Str uri) {
+	add_torrent_params const p = parse_magnet_uri(string_view(uri.data(), uri.size()));
+
+	MagnetUriParams out {
+		info_hashes: to_info_hashes(p.info_hashes),
+		name: rust::String::lossy(p.name),
+		trackers: {},
+		url_seeds: {},
+		peers: {},
+		file_priorities: {},
+	};
+
+	for (const auto& t : p.trackers) out.trackers.push_back(rust::String::lossy(t));
+	for (const auto& u : p.url_seeds) out.url_seeds.push_back(rust::String::lossy(u));
+	for (const auto& ep : p.peers) out.peers.push_back(to_endpoint(ep));
+	for (auto prio : p.file_priorities) out.file_priorities.push_back(static_cast<std::uint8_t>(prio));
+
+	return out;
+}
+
+AlertData alert_data(const Alert& alert) {
+	auto const* a = alert.inner;
+
+	AlertData d{};
+	d.alert_type = a->type();
+	d.what = rust::String::lossy(a->what());
+	d.category = static_cast<std::uint32_t>(a->category());
+	d.message = rust::String::lossy(a->message());
+
+	// alert timestamps come from a monotonic clock, translate them to
+	// wall-clock time relative to now
+	auto const age = clock_type::now() - a->timestamp();
+	auto const ts = std::chrono::system_clock::now()
+		- std::chrono::duration_cast<std::chrono::system_clock::duration>(age);
+	d.timestamp_us = std::chrono::duration_cast<std::chrono::microseconds>(ts.time_since_epoch()).count();
+
+	if (auto const* ta = dynamic_cast<torrent_alert const*>(a)) {
+		d.torrent_name = rust::String::lossy(ta->torrent_name());
+	}
+
+	if (auto const* pa = dynamic_cast<peer_alert const*>(a)) {
+		set_peer(d, *pa);
+	}
+
+	if (auto const* ta = dynamic_cast<tracker_alert const*>(a)) {
+		d.url = rust::String::lossy(ta->tracker_url());
+		d.local_endpoint = to_endpoint(ta->local_endpoint);
+	}
+
+	switch (a->type()) {
+	case torrent_removed_alert::alert_type: {
+		auto const* x = static_cast<torrent_removed_alert const*>(a);
+		set_info_hashes(d, x->info_hashes);
+		break;
+	}
+	case read_piece_alert::alert_type: {
+		auto const* x = static_cast<read_piece_alert const*>(a);
+		d.error = to_error_code(x->error);
+		d.piece = static_cast<int>(x->piece);
+		if (x->buffer) {
+			append_bytes(d.buffer, span<char const>(x->buffer.get(), x->size));
+		}
+		break;
+	}
+	case file_completed_alert::alert_type: {
+		auto const* x = static_cast<file_completed_alert const*>(a);
+		d.file = static_cast<int>(x->index);
+		break;
+	}
+	case file_renamed_alert::alert_type: {
+		auto const* x = static_cast<file_renamed_alert const*>(a);
+		d.file = static_cast<int>(x->index);
+		d.path = rust::String::lossy(x->new_name());
+		d.old_path = rust::String::lossy(x->old_name());
+		break;
+	}
+	case file_rename_failed_alert::alert_type: {
+		auto const* x = static_cast<file_rename_failed_alert const*>(a);
+		d.file = static_cast<int>(x->index);
+		d.error = to_error_code(x->error);
+		break;
+	}
+	case performance_alert::alert_type: {
+		auto const* x = static_cast<performance_alert const*>(a);
+		d.reason = static_cast<int>(x->warning_code);
+		break;
+	}
+	case state_changed_alert::alert_type: {
+		auto const* x = static_cast<state_changed_alert const*>(a);
+		d.state = x->state;
+		d.prev_state = x->prev_state;
+		break;
+	}
+	case tracker_error_alert::alert_type: {
+		auto const* x = static_cast<tracker_error_alert const*>(a);
+		d.times_in_row = x->times_in_row;
+		d.error = to_error_code(x->error);
+		d.operation = rust::String::lossy(operation_name(x->op));
+		d.text = rust::String::lossy(x->failure_reason());
+		break;
+	}
+	case tracker_warning_alert::alert_type: {
+		auto const* x = static_cast<tracker_warning_alert const*>(a);
+		d.text = rust::String::lossy(x->warning_message());
+		break;
+	}
+	case scrape_reply_alert::alert_type: {
+		auto const* x = static_cast<scrape_reply_alert const*>(a);
+		d.incomplete = x->incomplete;
+		d.complete = x->complete;
+		break;
+	}
+	case scrape_failed_alert::alert_type: {
+		auto const* x = static_cast<scrape_failed_alert const*>(a);
+		d.error = to_error_code(x->error);
+		d.text = rust::String::lossy(x->error_message());
+		break;
+	}
+	case tracker_reply_alert::alert_type: {
+		auto const* x = static_cast<tracker_reply_alert const*>(a);
+		d.num_peers = x->num_peers;
+		break;
+	}
+	case dht_reply_alert::alert_type: {
+		auto const* x = static_cast<dht_reply_alert const*>(a);
+		d.num_peers = x->num_peers;
+		break;
+	}
+	case tracker_announce_alert::alert_type: {
+		auto const* x = static_cast<tracker_announce_alert const*>(a);
+		d.event = static_cast<int>(x->event);
+		break;
+	}
+	case hash_failed_alert::alert_type: {
+		auto const* x = static_cast<hash_failed_alert const*>(a);
+		d.piece = static_cast<int>(x->piece_index);
+		break;
+	}
+	case peer_error_alert::alert_type: {
+		auto const* x = static_cast<peer_error_alert const*>(a);
+		d.operation = rust::String::lossy(operation_name(x->op));
+		d.error = to_error_code(x->error);
+		break;
+	}
+	case peer_connect_alert::alert_type: {
+		auto const* x = static_cast<peer_connect_alert const*>(a);
+		d.socket_type = static_cast<int>(x->socket_type);
+		break;
+	}
+	case peer_disconnected_alert::alert_type: {
+		auto const* x = static_cast<peer_disconnected_alert const*>(a);
+		d.socket_type = static_cast<int>(x->socket_type);
+		d.operation = rust::String::lossy(operation_name(x->op));
+		d.error = to_error_code(x->error);
+		d.reason = static_cast<int>(x->reason);
+		break;
+	}
+	case invalid_request_alert::alert_type: {
+		auto const* x = static_cast<invalid_request_alert const*>(a);
+		set_request(d, x->request);
+		d.we_have = x->we_have;
+		d.peer_interested = x->peer_interested;
+		d.withheld = x->withheld;
+		break;
+	}
+	case piece_finished_alert::alert_type: {
+		auto const* x = static_cast<piece_finished_alert const*>(a);
+		d.piece = static_cast<int>(x->piece_index);
+		break;
+	}
+	case request_dropped_alert::alert_type: {
+		auto const* x = static_cast<request_dropped_alert const*>(a);
+		d.block = x->block_index;
+		d.piece = static_cast<int>(x->piece_index);
+		break;
+	}
+	case block_timeout_alert::alert_type: {
+		auto const* x = static_cast<block_timeout_alert const*>(a);
+		d.block = x->block_index;
+		d.piece = static_cast<int>(x->piece_index);
+		break;
+	}
+	case block_finished_alert::alert_type: {
+		auto const* x = static_cast<block_finished_alert const*>(a);
+		d.block = x->block_index;
+		d.piece = static_cast<int>(x->piece_index);
+		break;
+	}
+	case block_downloading_alert::alert_type: {
+		auto const* x = static_cast<block_downloading_alert const*>(a);
+		d.block = x->block_index;
+		d.piece = static_cast<int>(x->piece_index);
+		break;
+	}
+	case unwanted_block_alert::alert_type: {
+		auto const* x = static_cast<unwanted_block_alert const*>(a);
+		d.block = x->block_index;
+		d.piece = static_cast<int>(x->piece_index);
+		break;
+	}
+	case block_uploaded_alert::alert_type: {
+		auto const* x = static_cast<block_uploaded_alert const*>(a);
+		d.block = x->block_index;
+		d.piece = static_cast<int>(x->piece_index);
+		break;
+	}
+	case storage_moved_alert::alert_type: {
+		auto const* x = static_cast<storage_moved_alert const*>(a);
+		d.path = rust::String::lossy(x->storage_path());
+		d.old_path = rust::String::lossy(x->old_path());
+		break;
+	}
+	case storage_moved_failed_alert::alert_type: {
+		auto const* x = static_cast<storage_moved_failed_alert const*>(a);
+		d.error = to_error_code(x->error);
+		d.path = rust::String::lossy(x->file_path());
+		d.operation = rust::String::lossy(operation_name(x->op));
+		break;
+	}
+	case torrent_deleted_alert::alert_type: {
+		auto const* x = static_cast<torrent_deleted_alert const*>(a);
+		set_info_hashes(d, x->info_hashes);
+		break;
+	}
+	case torrent_delete_failed_alert::alert_type: {
+		auto const* x = static_cast<torrent_delete_failed_alert const*>(a);
+		d.error = to_error_code(x->error);
+		set_info_hashes(d, x->info_hashes);
+		break;
+	}
+	case save_resume_data_alert::alert_type: {
+		auto const* x = static_cast<save_resume_data_alert const*>(a);
+		set_info_hashes(d, x->params.info_hashes);
+		d.buffer = to_resume_data(x->params);
+		break;
+	}
+	case save_resume_data_failed_alert::alert_type: {
+		auto const* x = static_cast<save_resume_data_failed_alert const*>(a);
+		d.error = to_error_code(x->error);
+		break;
+	}
+	case url_seed_alert::alert_type: {
+		auto const* x = static_cast<url_seed_alert const*>(a);
+		d.error = to_error_code(x->error);
+		d.url = rust::String::lossy(x->server_url());
+		d.text = rust::String::lossy(x->error_message());
+		break;
+	}
+	case file_error_alert::alert_type: {
+		auto const* x = static_cast<file_error_alert const*>(a);
+		d.error = to_error_code(x->error);
+		d.operation = rust::String::lossy(operation_name(x->op));
+		d.path = rust::String::lossy(x->filename());
+		break;
+	}
+	case metadata_failed_alert::alert_type: {
+		auto const* x = static_cast<metadata_failed_alert const*>(a);
+		d.error = to_error_code(x->error);
+		break;
+	}
+	case udp_error_alert::alert_type: {
+		auto const* x = static_cast<udp_error_alert const*>(a);
+		d.endpoint = to_endpoint(x->endpoint);
+		d.operation = rust::String::lossy(operation_name(x->operation));
+		d.error = to_error_code(x->error);
+		break;
+	}
+	case external_ip_alert::alert_type: {
+		auto const* x = static_cast<external_ip_alert const*>(a);
+		d.endpoint = to_endpoint(x->external_address, 0);
+		break;
+	}
+	case listen_failed_alert::alert_type: {
+		auto const* x = static_cast<listen_failed_alert const*>(a);
+		d.text = rust::String::lossy(x->listen_interface());
+		d.error = to_error_code(x->error);
+		d.operation = rust::String::lossy(operation_name(x->op));
+		d.socket_type = static_cast<int>(x->socket_type);
+		d.endpoint = to_endpoint(x->address, x->port);
+		break;
+	}
+	case listen_succeeded_alert::alert_type: {
+		auto const* x = static_cast<listen_succeeded_alert const*>(a);
+		d.endpoint = to_endpoint(x->address, x->port);
+		d.socket_type = static_cast<int>(x->socket_type);
+		break;
+	}
+	case portmap_error_alert::alert_type: {
+		auto const* x = static_cast<portmap_error_alert const*>(a);
+		d.mapping = static_cast<int>(x->mapping);
+		d.transport = static_cast<int>(x->map_transport);
+		d.error = to_error_code(x->error);
+		break;
+	}
+	case portmap_alert::alert_type: {
+		auto const* x = static_cast<portmap_alert const*>(a);
+		d.mapping = static_cast<int>(x->mapping);
+		d.endpoint.port = uint16_t(x->external_port);
+		d.protocol = static_cast<int>(x->map_protocol);
+		d.transport = static_cast<int>(x->map_transport);
+		break;
+	}
+	case portmap_log_alert::alert_type: {
+		auto const* x = static_cast<portmap_log_alert const*>(a);
+		d.transport = static_cast<int>(x->map_transport);
+		d.text = rust::String::lossy(x->log_message());
+		break;
+	}
+	case fastresume_rejected_alert::alert_type: {
+		auto const* x = static_cast<fastresume_rejected_alert const*>(a);
+		d.error = to_error_code(x->error);
+		d.path = rust::String::lossy(x->file_path());
+		d.operation = rust::String::lossy(operation_name(x->op));
+		break;
+	}
+	case peer_blocked_alert::alert_type: {
+		auto const* x = static_cast<peer_blocked_alert const*>(a);
+		d.reason = x->reason;
+		break;
+	}
+	case dht_announce_alert::alert_type: {
+		auto const* x = static_cast<dht_announce_alert const*>(a);
+		d.endpoint = to_endpoint(x->ip, x->port);
+		copy_hash(x->info_hash, d.hash_v1.data());
+		break;
+	}
+	case dht_get_peers_alert::alert_type: {
+		auto const* x = static_cast<dht_get_peers_alert const*>(a);
+		copy_hash(x->info_hash, d.hash_v1.data());
+		break;
+	}
+	case trackerid_alert::alert_type: {
+		auto const* x = static_cast<trackerid_alert const*>(a);
+		d.text = rust::String::lossy(x->tracker_id());
+		break;
+	}
+	case torrent_error_alert::alert_type: {
+		auto const* x = static_cast<torrent_error_alert const*>(a);
+		d.error = to_error_code(x->error);
+		d.path = rust::String::lossy(x->filename());
+		break;
+	}
+	case incoming_connection_alert::alert_type: {
+		auto const* x = static_cast<incoming_connection_alert const*>(a);
+		d.socket_type = static_cast<int>(x->socket_type);
+		d.endpoint = to_endpoint(x->endpoint);
+		break;
+	}
+	case add_torrent_alert::alert_type: {
+		auto const* x = static_cast<add_torrent_alert const*>(a);
+		d.error = to_error_code(x->error);
+		break;
+	}
+	case state_update_alert::alert_type: {
+		auto const* x = static_cast<state_update_alert const*>(a);
+		for (auto const& s : x->status) {
+			d.statuses.push_back(to_torrent_status(s));
+		}
+		break;
+	}
+	case session_stats_alert::alert_type: {
+		auto const* x = static_cast<session_stats_alert const*>(a);
+		for (auto v : x->counters()) {
+			d.counters.push_back(v);
+		}
+		break;
+	}
+	case dht_error_alert::alert_type: {
+		auto const* x = static_cast<dht_error_alert const*>(a);
+		d.error = to_error_code(x->error);
+		d.operation = rust::String::lossy(operation_name(x->op));
+		break;
+	}
+	case dht_immutable_item_alert::alert_type: {
+		auto const* x = static_cast<dht_immutable_item_alert const*>(a);
+		copy_hash(x->target, d.hash_v1.data());
+		bencode(std::back_inserter(d.buffer), x->item);
+		break;
+	}
+	case dht_mutable_item_alert::alert_type: {
+		auto const* x = static_cast<dht_mutable_item_alert const*>(a);
+		copy_bytes(x->key, d.hash_v2.data());
+		d.seq = x->seq;
+		append_bytes(d.salt, x->salt);
+		d.authoritative = x->authoritative;
+		bencode(std::back_inserter(d.buffer), x->item);
+		break;
+	}
+	case dht_put_alert::alert_type: {
+		auto const* x = static_cast<dht_put_alert const*>(a);
+		copy_hash(x->target, d.hash_v1.data());
+		copy_bytes(x->public_key, d.hash_v2.data());
+		d.seq = x->seq;
+		append_bytes(d.salt, x->salt);
+		d.num_peers = x->num_success;
+		break;
+	}
+	case i2p_alert::alert_type: {
+		auto const* x = static_cast<i2p_alert const*>(a);
+		d.error = to_error_code(x->error);
+		break;
+	}
+	case dht_outgoing_get_peers_alert::alert_type: {
+		auto const* x = static_cast<dht_outgoing_get_peers_alert const*>(a);
+		copy_hash(x->info_hash, d.hash_v1.data());
+		d.endpoint = to_endpoint(x->endpoint);
+		break;
+	}
+	case log_alert::alert_type: {
+		auto const* x = static_cast<log_alert const*>(a);
+		d.text = rust::String::lossy(x->log_message());
+		break;
+	}
+	case torrent_log_alert::alert_type: {
+		auto const* x = static_cast<torrent_log_alert const*>(a);
+		d.text = rust::String::lossy(x->log_message());
+		break;
+	}
+	case peer_log_alert::alert_type: {
+		auto const* x = static_cast<peer_log_alert const*>(a);
+		d.direction = static_cast<int>(x->direction);
+		d.path = rust::String::lossy(x->event_type);
+		d.text = rust::String::lossy(x->log_message());
+		break;
+	}
+	case lsd_error_alert::alert_type: {
+		auto const* x = static_cast<lsd_error_alert const*>(a);
+		d.error = to_error_code(x->error);
+		break;
+	}
+	case incoming_request_alert::alert_type: {
+		auto const* x = static_cast<incoming_request_alert const*>(a);
+		set_request(d, x->req);
+		break;
+	}
+	case dht_log_alert::alert_type: {
+		auto const* x = static_cast<dht_log_alert const*>(a);
+		d.module = static_cast<int>(x->module);
+		d.text = rust::String::lossy(x->log_message());
+		break;
+	}
+	case dht_pkt_alert::alert_type: {
+		auto const* x = static_cast<dht_pkt_alert const*>(a);
+		d.direction = static_cast<int>(x->direction);
+		d.endpoint = to_endpoint(x->node);
+		append_bytes(d.buffer, x->pkt_buf());
+		break;
+	}
+	case dht_get_peers_reply_alert::alert_type: {
+		auto const* x = static_cast<dht_get_peers_reply_alert const*>(a);
+		copy_hash(x->info_hash, d.hash_v1.data());
+		for (auto const& ep : x->peers()) {
+			d.endpoints.push_back(to_endpoint(ep));
+		}
+		break;
+	}
+	case dht_direct_response_alert::alert_type: {
+		auto const* x = static_cast<dht_direct_response_alert const*>(a);
+		d.endpoint = to_endpoint(x->endpoint);
+		append_bytes(d.buffer, x->response().data_section());
+		break;
+	}
+	case picker_log_alert::alert_type: {
+		auto const* x = static_cast<picker_log_alert const*>(a);
+		d.reason = static_cast<int>(static_cast<std::uint32_t>(x->picker_flags));
+		break;
+	}
+	case session_error_alert::alert_type: {
+		auto const* x = static_cast<session_error_alert const*>(a);
+		d.error = to_error_code(x->error);
+		break;
+	}
+	case dht_live_nodes_alert::alert_type: {
+		auto const* x = static_cast<dht_live_nodes_alert const*>(a);
+		copy_hash(x->node_id, d.hash_v1.data());
+		for (auto const& n : x->nodes()) {
+			d.endpoints.push_back(to_endpoint(n.second));
+		}
+		break;
+	}
+	case dht_sample_infohashes_alert::alert_type: {
+		auto const* x = static_cast<dht_sample_infohashes_alert const*>(a);
+		d.endpoint = to_endpoint(x->endpoint);
+		d.interval_s = int(total_seconds(x->interval));
+		d.num_peers = x->num_infohashes;
+		for (auto const& h : x->samples()) {
+			append_bytes(d.buffer, span<char const>(h.data(), h.size()));
+		}
+		break;
+	}
+	case socks5_alert::alert_type: {
+		auto const* x = static_cast<socks5_alert const*>(a);
+		d.error = to_error_code(x->error);
+		d.operation = rust::String::lossy(operation_name(x->op));
+		d.endpoint = to_endpoint(x->ip);
+		break;
+	}
+	case file_prio_alert::alert_type: {
+		auto const* x = static_cast<file_prio_alert const*>(a);
+		d.error = to_error_code(x->error);
+		d.operation = rust::String::lossy(operation_name(x->op));
+		break;
+	}
+	default:
+		break;
+	}
+
+	return d;
+}
+
+std::unique_ptr<torrent_handle> alert_get_torrent_handle(const Alert& alert) {
+	if (auto const* ta = dynamic_cast<torrent_alert const*>(alert.inner)) {
+		return std::make_unique<torrent_handle>(ta->handle);
+	}
+	return nullptr;
+}
+
+rust::String alert_type_name(int alert_type) {
+	return rust::String(alert_name(alert_type));
+}
+
 }
@@ -6,51 +6,188 @@
 #include "libtorrent/create_torrent.hpp"
 #include "libtorrent/load_torrent.hpp"
 #include "libtorrent/magnet_uri.hpp"
+#include "libtorrent/read_resume_data.hpp"
 #include "libtorrent/session.hpp"
 #include "libtorrent/session_params.hpp"
 #include "libtorrent/torrent_status.hpp"
 #include "libtorrent/version.hpp"
+#include "libtorrent/write_resume_data.hpp"
 
+#include <exception>
+#include <stdexcept>
 #include <memory>
+#include <string>
+#include <vector>
 
 char const* version();
 
+namespace rust {
+namespace behavior {
+	// Overrides the default exception handler of the bridge so that the
+	// error_code carried by libtorrent's system_error survives the trip to
+	// Rust. The category, value and message are packed into the exception
+	// message behind a marker and unpacked again by
+	// ErrorCode::from_exception, other exceptions pass their message as is.
+	template <typename Try, typename Fail>
+	static void trycatch(Try&& func, Fail&& fail) noexcept try {
+		func();
+	} catch (const lt::system_error& e) {
+		auto const& ec = e.code();
+		std::string s = "\x1flt::system_error\x1f";
+		s += ec.category().name();
+		s += '\x1f';
+		s += std::to_string(ec.value());
+		s += '\x1f';
+		s += ec.message();
+		fail(s);
+	} catch (const std::exception& e) {
+		fail(e.what());
+	}
+}
+}
+
 namespace libtorrent {
     using torrent_state = lt::torrent_status::state_t;
 
     struct Alert;
+    struct AlertCategoryBits;
+    struct AlertData;
+    struct AlertNotify;
     struct AnnounceEntry;
+    struct CreateFlagsBits;
+    struct CreateTorrentParams;
+    struct Endpoint;
+    struct ErrorCode;
+    struct FileFilter;
+    struct FileReader;
+    struct FileFlagsBits;
+    struct FileSlice;
+    struct FileStorageEntry;
+    struct HashProgress;
+    struct HttpHeader;
+    struct InfoHashes;
+    struct MagnetUriParams;
+    struct LoadTorrentLimits;
     struct TorrentInfoNode;
+    struct RenamedFile;
+    struct PeerRequest;
+    struct ResumeDataFlagsBits;
+    struct SaveStateFlagsBits;
+    struct SettingEntry;
+    enum class SettingKind : std::uint8_t;
+    struct StatusFlagsBits;
+    struct TorrentFlagsBits;
     struct TorrentStatus;
+    struct VirtualFileEntry;
+    struct WebSeedEntry;
+    struct WriteTorrentFlagsBits;
 
     std::unique_ptr<session_params> new_session_params();
+    std::unique_ptr<session_params> new_session_params_from_state(rust::Slice<const uint8_t> buf);
     void session_params_set_user_agent(session_params& params, rust::Str user_agent);
     void session_params_set_outgoing_interfaces(session_params& params, rust::Str outgoing_interfaces);
     void session_params_set_listen_interfaces(session_params& params, rust::Str listen_interfaces);
     void session_params_set_alert_mask(session_params& params, int alert_mask);
     void session_params_disable_disk(session_params& params);
+    settings_pack& session_params_get_settings(session_params& params);
+    std::unique_ptr<settings_pack> new_settings_pack();
+    void settings_pack_set_int(settings_pack& pack, rust::Str name, int value);
+    void settings_pack_set_bool(settings_pack& pack, rust::Str name, bool value);
+    void settings_pack_set_str(settings_pack& pack, rust::Str name, rust::Str value);
+    SettingKind setting_kind(rust::Str name);
     std::unique_ptr<session> new_session(std::unique_ptr<session_params> params);
     std::unique_ptr<add_torrent_params> new_add_torrent_params_from_magnet_uri(rust::Str uri);
-    std::unique_ptr<add_torrent_params> new_add_torrent_params_from_torrent_file(rust::Str path);
+    std::unique_ptr<add_torrent_params> new_add_torrent_params_from_torrent_file(rust::Str path, const LoadTorrentLimits& limits);
+    std::unique_ptr<add_torrent_params> new_add_torrent_params_from_torrent_buffer(rust::Slice<const uint8_t> buf, const LoadTorrentLimits& limits);
+    std::unique_ptr<add_torrent_params> new_add_torrent_params_from_resume_data(rust::Slice<const uint8_t> buf);
     void add_torrent_params_set_save_path(add_torrent_params& params, rust::Str path);
     void add_torrent_params_set_trackers(add_torrent_params& params, rust::Slice<const rust::Str> trackers);
     void add_torrent_params_set_torrent_flags(add_torrent_params& params, uint64_t torrent_flags);
+    void add_torrent_params_set_name(add_torrent_params& params, rust::Str name);
+    void add_torrent_params_set_file_priorities(add_torrent_params& params, rust::Slice<const uint8_t> priorities);
+    void add_torrent_params_set_piece_priorities(add_torrent_params& params, rust::Slice<const uint8_t> priorities);
+    void add_torrent_params_set_url_seeds(add_torrent_params& params, rust::Slice<const rust::Str> url_seeds);
+    void add_torrent_params_set_dht_nodes(add_torrent_params& params, rust::Slice<const TorrentInfoNode> nodes);
+    void add_torrent_params_set_peers(add_torrent_params& params, rust::Slice<const Endpoint> peers);
+    void add_torrent_params_set_banned_peers(add_torrent_params& params, rust::Slice<const Endpoint> peers);
+    void add_torrent_params_set_max_connections(add_torrent_params& params, int max_connections);
+    void add_torrent_params_set_max_uploads(add_torrent_params& params, int max_uploads);
+    void add_torrent_params_set_upload_limit(add_torrent_params& params, int upload_limit);
+    void add_torrent_params_set_download_limit(add_torrent_params& params, int download_limit);
+    void add_torrent_params_set_storage_mode(add_torrent_params& params, storage_mode_t storage_mode);
+    void add_torrent_params_set_renamed_files(add_torrent_params& params, rust::Slice<const RenamedFile> renamed_files);
+    rust::Vec<uint8_t> add_torrent_params_write_resume_data(const add_torrent_params& params);
     std::unique_ptr<lt::torrent_handle> session_add_torrent(session& ses, add_torrent_params& params);
     void session_remove_torrent(session& ses, const torrent_handle& hdl);
     void session_pause(session& ses);
+    std::unique_ptr<session_proxy> session_abort(session& ses);
+    void session_apply_settings(session& ses, const settings_pack& pack);
+    std::unique_ptr<std::vector<torrent_handle>> session_get_torrents(const session& ses);
+    rust::Vec<uint8_t> session_save_state(const session& ses, uint32_t flags);
+    rust::Vec<SettingEntry> session_get_settings(const session& ses);
     rust::Vec<Alert> session_get_alerts(session& ses);
+    void session_set_alert_notify(session& ses, rust::Box<AlertNotify> notify);
     rust::String alert_message(const Alert& alert);
+    AlertData alert_data(const Alert& alert);
+    std::unique_ptr<lt::torrent_handle> alert_get_torrent_handle(const Alert& alert);
+    rust::String alert_type_name(int alert_type);
     metadata_received_alert* alert_cast_metadata_received(Alert alert);
     torrent_handle* metadata_received_alert_get_torrent_handle(metadata_received_alert* alert);
     save_resume_data_alert* alert_cast_save_resume_data(Alert alert);
+    rust::Vec<uint8_t> save_resume_data_alert_get_resume_data(save_resume_data_alert* alert);
     save_resume_data_failed_alert* alert_cast_save_resume_data_failed(Alert alert);
+    ErrorCode save_resume_data_failed_alert_get_error(save_resume_data_failed_alert* alert);
     void wait_for_alert(session& ses, uint64_t max_duration);
     bool torrent_has_metadata(const torrent_handle& hdl);
-    rust::Str torrent_get_name(const torrent_handle& hdl);
-    TorrentStatus torrent_get_status(const torrent_handle& hdl);
-    rust::Slice<const uint8_t> torrent_bencode(const torrent_handle& hdl);
-    rust::Vec<uint8_t> create_torrent_for_path(rust::Str path);
-    std::unique_ptr<torrent_info> open_torrent_info(rust::Str path);
+    rust::String torrent_get_name(const torrent_handle& hdl);
+    TorrentStatus torrent_get_status(const torrent_handle& hdl, uint32_t flags);
+    void torrent_save_resume_data(const torrent_handle& hdl, uint8_t flags);
+    std::unique_ptr<torrent_handle> torrent_handle_clone(const torrent_handle& hdl);
+    bool torrent_is_valid(const torrent_handle& hdl);
+    InfoHashes torrent_get_info_hashes(const torrent_handle& hdl);
+    std::unique_ptr<torrent_info> torrent_get_torrent_file(const torrent_handle& hdl);
+    rust::Vec<uint8_t> create_torrent_generate(const CreateTorrentParams& params, FileFilter& filter, HashProgress& progress);
+    rust::Vec<uint8_t> create_torrent_generate_with_libtorrent(const CreateTorrentParams& params);
+    rust::Vec<uint8_t> create_torrent_generate_from_files(const CreateTorrentParams& params, rust::Slice<const VirtualFileEntry> files, const FileReader& reader, HashProgress& progress);
+    std::unique_ptr<torrent_info> open_torrent_info(rust::Str path, const LoadTorrentLimits& limits);
+    std::unique_ptr<torrent_info> new_torrent_info_from_buffer(rust::Slice<const uint8_t> buf, const LoadTorrentLimits& limits);
     rust::Vec<TorrentInfoNode> torrent_info_nodes(const torrent_info& info);
     rust::Vec<AnnounceEntry> torrent_info_trackers(const torrent_info& info);
+    rust::String torrent_info_name(const torrent_info& info);
+    rust::String torrent_info_comment(const torrent_info& info);
+    rust::String torrent_info_creator(const torrent_info& info);
+    int64_t torrent_info_creation_date(const torrent_info& info);
+    int64_t torrent_info_total_size(const torrent_info& info);
+    int torrent_info_piece_length(const torrent_info& info);
+    int torrent_info_num_pieces(const torrent_info& info);
+    bool torrent_info_priv(const torrent_info& info);
+    bool torrent_info_is_i2p(const torrent_info& info);
+    InfoHashes torrent_info_info_hashes(const torrent_info& info);
+    rust::Vec<WebSeedEntry> torrent_info_web_seeds(const torrent_info& info);
+    rust::Vec<uint8_t> torrent_info_similar_torrents(const torrent_info& info);
+    rust::Vec<rust::String> torrent_info_collections(const torrent_info& info);
+    rust::String torrent_info_ssl_cert(const torrent_info& info);
+    rust::Vec<uint8_t> torrent_info_info_section(const torrent_info& info);
+    const file_storage& torrent_info_files(const torrent_info& info);
+    int file_storage_num_files(const file_storage& fs);
+    int file_storage_num_pieces(const file_storage& fs);
+    int64_t file_storage_total_size(const file_storage& fs);
+    int file_storage_piece_size(const file_storage& fs, int piece);
+    int64_t file_storage_file_size(const file_storage& fs, int index);
+    FileStorageEntry file_storage_file_entry(const file_storage& fs, int index);
+    PeerRequest file_storage_map_file(const file_storage& fs, int index, int64_t offset, int size);
+    rust::Vec<FileSlice> file_storage_map_block(const file_storage& fs, int piece, int64_t offset, int64_t size);
+    rust::Vec<uint8_t> torrent_info_write_torrent_file(const torrent_info& info, uint32_t flags);
+    rust::String torrent_info_make_magnet_uri(const torrent_info& info);
+    rust::String torrent_make_magnet_uri(const torrent_handle& hdl);
+    MagnetUriParams magnet_uri_parse(rust::Str uri);
+    AlertCategoryBits alert_category_bits();
+    TorrentFlagsBits torrent_flags_bits();
+    StatusFlagsBits status_flags_bits();
+    SaveStateFlagsBits save_state_flags_bits();
+    ResumeDataFlagsBits resume_data_flags_bits();
+    WriteTorrentFlagsBits write_torrent_flags_bits();
+    FileFlagsBits file_flags_bits();
+    CreateFlagsBits create_flags_bits();
+    LoadTorrentLimits default_load_torrent_limits();
 }
@@ -0,0 +1,85 @@
+use rbtorrent::{ffi, AddTorrentParams, DownloadPriority, Error, TorrentFlags};
+use std::{collections::BTreeMap, path::PathBuf};
+
+const MAGNET: &str = "magnet:?xt=urn:btih:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+#[test]
+fn builder_fields_reach_libtorrent() {
+    let resume = AddTorrentParams::from_magnet_uri(MAGNET.into())
+        .with_save_path(PathBuf::from("/tmp/dl"))
+        .with_name("hello".into())
+        .with_trackers(vec!["http://tr/announce".into()])
+        .with_url_seeds(vec!["http://ws/".into()])
+        .with_torrent_flags(TorrentFlags::PAUSED)
+        .with_file_priorities(vec![DownloadPriority::TOP, DownloadPriority::DONT_DOWNLOAD])
+        .with_piece_priorities(vec![DownloadPriority::LOW])
+        .with_max_connections(42)
+        .with_max_uploads(7)
+        .with_upload_limit(1000)
+        .with_download_limit(2000)
+        .with_storage_mode(ffi::storage_mode_t::storage_mode_allocate)
+        .with_renamed_files(BTreeMap::from([(0, PathBuf::from("renamed"))]))
+        .write_resume_data()
+        .unwrap();
+
+    for needle in [
+        &b"9:save_path7:/tmp/dl"[..],
+        b"4:name5:hello",
+        b"8:trackersll18:http://tr/announceee",
+        b"8:url-listl10:http://ws/e",
+        b"6:pausedi1e",
+        b"12:auto_managedi0e",
+        b"13:file_priorityli7ei0ee",
+        b"14:piece_priority1:\x01",
+        b"15:max_connectionsi42e",
+        b"11:max_uploadsi7e",
+        b"17:upload_rate_limiti1000e",
+        b"19:download_rate_limiti2000e",
+        b"10:allocation8:allocate",
+        b"12:mapped_filesl7:renamede",
+    ] {
+        assert!(
+            contains(&resume, needle),
+            "{} missing from resume data",
+            String::from_utf8_lossy(needle)
+        );
+    }
+}
+
+#[test]
+fn renamed_file_index_out_of_range() {
+    let err = AddTorrentParams::from_magnet_uri(MAGNET.into())
+        .with_renamed_files(BTreeMap::from([(u32::MAX, PathBuf::from("renamed"))]))
+        .write_resume_data()
+        .unwrap_err();
+
+    assert!(matches!(err, Error::InvalidArgument(_)));
+}
+
+#[test]
+fn resume_data_round_trip() {
+    let resume = AddTorrentParams::from_magnet_uri(MAGNET.into())
+        .with_save_path(PathBuf::from("/tmp/dl"))
+        .with_name("hello".into())
+        .with_trackers(vec!["http://tr/announce".into()])
+        .with_max_connections(42)
+        .write_resume_data()
+        .unwrap();
+
+    let restored = AddTorrentParams::from_resume_data(resume.clone())
+        .write_resume_data()
+        .unwrap();
+    assert_eq!(restored, resume);
+
+    // fields set on top of resume data override it
+    let overridden = AddTorrentParams::from_resume_data(resume)
+        .with_name("other".into())
+        .write_resume_data()
+        .unwrap();
+    assert!(contains(&overridden, b"4:name5:other"));
+    assert!(contains(&overridden, b"15:max_connectionsi42e"));
+}
@@ -0,0 +1,160 @@
+use rbtorrent::{alert_types, ffi, AlertKind};
+
+#[test]
+fn alert_types_match_libtorrent() {
+    for (alert_type, name) in [
+        (alert_types::TORRENT_ADDED, "torrent_added"),
+        (alert_types::TORRENT_REMOVED, "torrent_removed"),
+        (alert_types::READ_PIECE, "read_piece"),
+        (alert_types::FILE_COMPLETED, "file_completed"),
+        (alert_types::FILE_RENAMED, "file_renamed"),
+        (alert_types::FILE_RENAME_FAILED, "file_rename_failed"),
+        (alert_types::PERFORMANCE, "performance"),
+        (alert_types::STATE_CHANGED, "state_changed"),
+        (alert_types::TRACKER_ERROR, "tracker_error"),
+        (alert_types::TRACKER_WARNING, "tracker_warning"),
+        (alert_types::SCRAPE_REPLY, "scrape_reply"),
+        (alert_types::SCRAPE_FAILED, "scrape_failed"),
+        (alert_types::TRACKER_REPLY, "tracker_reply"),
+        (alert_types::DHT_REPLY, "dht_reply"),
+        (alert_types::TRACKER_ANNOUNCE, "tracker_announce"),
+        (alert_types::HASH_FAILED, "hash_failed"),
+        (alert_types::PEER_BAN, "peer_ban"),
+        (alert_types::PEER_UNSNUBBED, "peer_unsnubbed"),
+        (alert_types::PEER_SNUBBED, "peer_snubbed"),
+        (alert_types::PEER_ERROR, "peer_error"),
+        (alert_types::PEER_CONNECT, "peer_connect"),
+        (alert_types::PEER_DISCONNECTED, "peer_disconnected"),
+        (alert_types::INVALID_REQUEST, "invalid_request"),
+        (alert_types::TORRENT_FINISHED, "torrent_finished"),
+        (alert_types::PIECE_FINISHED, "piece_finished"),
+        (alert_types::REQUEST_DROPPED, "request_dropped"),
+        (alert_types::BLOCK_TIMEOUT, "block_timeout"),
+        (alert_types::BLOCK_FINISHED, "block_finished"),
+        (alert_types::BLOCK_DOWNLOADING, "block_downloading"),
+        (alert_types::UNWANTED_BLOCK, "unwanted_block"),
+        (alert_types::STORAGE_MOVED, "storage_moved"),
+        (alert_types::STORAGE_MOVED_FAILED, "storage_moved_failed"),
+        (alert_types::TORRENT_DELETED, "torrent_deleted"),
+        (alert_types::TORRENT_DELETE_FAILED, "torrent_delete_failed"),
+        (alert_types::SAVE_RESUME_DATA, "save_resume_data"),
+        (
+            alert_types::SAVE_RESUME_DATA_FAILED,
+            "save_resume_data_failed",
+        ),
+        (alert_types::TORRENT_PAUSED, "torrent_paused"),
+        (alert_types::TORRENT_RESUMED, "torrent_resumed"),
+        (alert_types::TORRENT_CHECKED, "torrent_checked"),
+        (alert_types::URL_SEED, "url_seed"),
+        (alert_types::FILE_ERROR, "file_error"),
+        (alert_types::METADATA_FAILED, "metadata_failed"),
+        (alert_types::METADATA_RECEIVED, "metadata_received"),
+        (alert_types::UDP_ERROR, "udp_error"),
+        (alert_types::EXTERNAL_IP, "external_ip"),
+        (alert_types::LISTEN_FAILED, "listen_failed"),
+        (alert_types::LISTEN_SUCCEEDED, "listen_succeeded"),
+        (alert_types::PORTMAP_ERROR, "portmap_error"),
+        (alert_types::PORTMAP, "portmap"),
+        (alert_types::PORTMAP_LOG, "portmap_log"),
+        (alert_types::FASTRESUME_REJECTED, "fastresume_rejected"),
+        (alert_types::PEER_BLOCKED, "peer_blocked"),
+        (alert_types::DHT_ANNOUNCE, "dht_announce"),
+        (alert_types::DHT_GET_PEERS, "dht_get_peers"),
+        (alert_types::CACHE_FLUSHED, "cache_flushed"),
+        (alert_types::LSD_PEER, "lsd_peer"),
+        (alert_types::TRACKERID, "trackerid"),
+        (alert_types::DHT_BOOTSTRAP, "dht_bootstrap"),
+        (alert_types::TORRENT_ERROR, "torrent_error"),
+        (alert_types::TORRENT_NEED_CERT, "torrent_need_cert"),
+        (alert_types::INCOMING_CONNECTION, "incoming_connection"),
+        (alert_types::ADD_TORRENT, "add_torrent"),
+        (alert_types::STATE_UPDATE, "state_update"),
+        (alert_types::SESSION_STATS, "session_stats"),
+        (alert_types::DHT_ERROR, "dht_error"),
+        (alert_types::DHT_IMMUTABLE_ITEM, "dht_immutable_item"),
+        (alert_types::DHT_MUTABLE_ITEM, "dht_mutable_item"),
+        (alert_types::DHT_PUT, "dht_put"),
+        (alert_types::I2P, "i2p"),
+        (
+            alert_types::DHT_OUTGOING_GET_PEERS,
+            "dht_outgoing_get_peers",
+        ),
+        (alert_types::LOG, "log"),
+        (alert_types::TORRENT_LOG, "torrent_log"),
+        (alert_types::PEER_LOG, "peer_log"),
+        (alert_types::LSD_ERROR, "lsd_error"),
+        (alert_types::DHT_STATS, "dht_stats"),
+        (alert_types::INCOMING_REQUEST, "incoming_request"),
+        (alert_types::DHT_LOG, "dht_log"),
+        (alert_types::DHT_PKT, "dht_pkt"),
+        (alert_types::DHT_GET_PEERS_REPLY, "dht_get_peers_reply"),
+        (alert_types::DHT_DIRECT_RESPONSE, "dht_direct_response"),
+        (alert_types::PICKER_LOG, "picker_log"),
+        (alert_types::SESSION_ERROR, "session_error"),
+        (alert_types::DHT_LIVE_NODES, "dht_live_nodes"),
+        (alert_types::SESSION_STATS_HEADER, "session_stats_header"),
+        (alert_types::DHT_SAMPLE_INFOHASHES, "dht_sample_infohashes"),
+        (alert_types::BLOCK_UPLOADED, "block_uploaded"),
+        (alert_types::ALERTS_DROPPED, "alerts_dropped"),
+        (alert_types::SOCKS5, "socks5"),
+        (alert_types::FILE_PRIO, "file_prio"),
+    ] {
+        assert_eq!(ffi::alert_type_name(alert_type), name);
+    }
+}
+
+#[test]
+fn kind_dispatches_on_alert_type() {
+    let kind = AlertKind::from(ffi::AlertData {
+        alert_type: alert_types::PIECE_FINISHED,
+        piece: 3,
+        ..Default::default()
+    });
+    assert!(matches!(kind, AlertKind::PieceFinished { piece: Some(3) }));
+
+    let kind = AlertKind::from(ffi::AlertData {
+        alert_type: 10000,
+        what: "piece_finished".into(),
+        ..Default::default()
+    });
+    assert!(matches!(
+        kind,
+        AlertKind::Unknown {
+            alert_type: 10000,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn negative_indices_are_none() {
+    let kind = AlertKind::from(ffi::AlertData {
+        alert_type: alert_types::BLOCK_FINISHED,
+        piece: -1,
+        block: -1,
+        ..Default::default()
+    });
+    assert!(matches!(
+        kind,
+        AlertKind::BlockFinished {
+            piece: None,
+            block: None,
+            ..
+        }
+    ));
+
+    let kind = AlertKind::from(ffi::AlertData {
+        alert_type: alert_types::FILE_RENAMED,
+        file: -1,
+        path: "new".into(),
+        ..Default::default()
+    });
+    assert!(matches!(kind, AlertKind::FileRenamed { index: None, .. }));
+
+    let kind = AlertKind::from(ffi::AlertData {
+        alert_type: alert_types::FILE_COMPLETED,
+        file: 2,
+        ..Default::default()
+    });
+    assert!(matches!(kind, AlertKind::FileCompleted { index: Some(2) }));
+}
@@ -0,0 +1,550 @@
+use rbtorrent::{
+    ffi, CancelToken, CreateFlags, CreateTorrent, Error, PadFiles, PathFilter, Sha1Hash,
+    TorrentFormat, TorrentInfo, VirtualFile,
+};
+use std::{
+    fs,
+    io::{self, Read, Seek, SeekFrom},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Creates a fresh directory `name` under the temporary directory, holding
+/// two files
+fn content_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join(format!("rbtorrent-create-{}", std::process::id()))
+        .join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("a.txt"), vec![b'a'; 40000]).unwrap();
+    fs::write(dir.join("sub/b.txt"), b"hello").unwrap();
+    dir
+}
+
+#[test]
+fn generate_sets_metadata() {
+    let dir = content_dir("metadata");
+
+    let torrent = CreateTorrent::new(dir)
+        .with_piece_size(16384)
+        .with_trackers(vec![
+            ("http://a/announce".into(), 0),
+            ("http://b/announce".into(), 1),
+        ])
+        .with_web_seeds(vec!["http://ws/".into()])
+        .with_dht_nodes(vec![("router.example".into(), 6881)])
+        .with_comment("hi".into())
+        .with_creator("test".into())
+        .with_creation_date(UNIX_EPOCH + Duration::from_secs(1_000_000_000))
+        .with_private(true)
+        .with_source("SRC".into())
+        .with_collections(vec!["foo".into()])
+        .with_similar_torrents(vec![Sha1Hash([0xbb; 20])])
+        .generate()
+        .unwrap();
+
+    let info = TorrentInfo::from_bytes(&torrent).unwrap();
+
+    assert_eq!(info.name(), "metadata");
+    assert_eq!(info.piece_length(), 16384);
+    assert!(info.info_hashes().is_hybrid());
+    assert_eq!(info.comment().as_deref(), Some("hi"));
+    assert_eq!(info.creator().as_deref(), Some("test"));
+    assert_eq!(
+        info.creation_date(),
+        Some(UNIX_EPOCH + Duration::from_secs(1_000_000_000))
+    );
+    assert!(info.is_private());
+    assert_eq!(info.collections(), vec!["foo".to_string()]);
+    assert_eq!(info.similar_torrents(), vec![Sha1Hash([0xbb; 20])]);
+    assert_eq!(
+        info.nodes().collect::<Vec<_>>(),
+        vec![("router.example".to_string(), 6881)]
+    );
+    assert_eq!(
+        info.trackers()
+            .into_iter()
+            .map(|t| (t.url, t.tier))
+            .collect::<Vec<_>>(),
+        vec![
+            ("http://a/announce".to_string(), 0),
+            ("http://b/announce".to_string(), 1),
+        ]
+    );
+    assert_eq!(info.web_seeds().len(), 1);
+
+    let info_section = info.info_section();
+    assert!(info_section
+        .windows(b"6:source3:SRC".len())
+        .any(|w| w == b"6:source3:SRC"));
+
+    let mut files = info
+        .files()
+        .iter()
+        .filter(|f| !f.is_pad_file())
+        .map(|f| (f.path, f.size))
+        .collect::<Vec<_>>();
+    files.sort();
+    assert_eq!(
+        files,
+        vec![
+            (PathBuf::from("metadata/a.txt"), 40000),
+            (PathBuf::from("metadata/sub/b.txt"), 5),
+        ]
+    );
+}
+
+#[test]
+fn generate_selects_format() {
+    let dir = content_dir("format");
+
+    for (format, v1, v2) in [
+        (TorrentFormat::Hybrid, true, true),
+        (TorrentFormat::V1Only, true, false),
+        (TorrentFormat::V2Only, false, true),
+    ] {
+        let torrent = CreateTorrent::new(dir.clone())
+            .with_format(format)
+            .generate()
+            .unwrap();
+        let info_hashes = TorrentInfo::from_bytes(&torrent).unwrap().info_hashes();
+
+        assert_eq!(info_hashes.has_v1(), v1, "{format:?}");
+        assert_eq!(info_hashes.has_v2(), v2, "{format:?}");
+    }
+}
+
+#[test]
+fn generate_defaults_creation_date_to_now() {
+    let dir = content_dir("date");
+
+    let before = SystemTime::now() - Duration::from_secs(1);
+    let torrent = CreateTorrent::new(dir.clone()).generate().unwrap();
+    let date = TorrentInfo::from_bytes(&torrent)
+        .unwrap()
+        .creation_date()
+        .unwrap();
+    assert!(date >= before);
+
+    let torrent = CreateTorrent::new(dir)
+        .with_creation_date(UNIX_EPOCH)
+        .generate()
+        .unwrap();
+    assert_eq!(
+        TorrentInfo::from_bytes(&torrent).unwrap().creation_date(),
+        None
+    );
+}
+
+#[test]
+fn generate_reports_progress() {
+    let dir = content_dir("progress");
+
+    let mut reported = Vec::new();
+    CreateTorrent::new(dir)
+        .with_piece_size(16384)
+        .generate_with_progress(
+            |done, total| reported.push((done, total)),
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+    // 40000 bytes padded to 3 pieces, plus one piece for the small file
+    assert_eq!(reported, vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
+}
+
+#[test]
+fn generate_can_be_cancelled() {
+    let dir = content_dir("cancel");
+
+    let cancel = CancelToken::new();
+    let res = CreateTorrent::new(dir.clone())
+        .with_piece_size(16384)
+        .generate_with_progress(|_, _| cancel.cancel(), &cancel);
+    assert_eq!(res, Err(Error::Cancelled));
+
+    let res = CreateTorrent::new(dir).generate_with_progress(|_, _| {}, &cancel);
+    assert_eq!(res, Err(Error::Cancelled));
+}
+
+#[test]
+fn generate_can_be_cancelled_while_hashing_in_parallel() {
+    let dir = content_dir("cancel-threads");
+    fs::write(dir.join("sub/big.bin"), pseudo_random(8 << 20)).unwrap();
+
+    let cancel = CancelToken::new();
+    let mut reported = Vec::new();
+    let res = CreateTorrent::new(dir)
+        .with_piece_size(16384)
+        .with_hashing_threads(4)
+        .generate_with_progress(
+            |done, total| {
+                reported.push((done, total));
+                if done == 2 {
+                    cancel.cancel();
+                }
+            },
+            &cancel,
+        );
+
+    assert_eq!(res, Err(Error::Cancelled));
+    let &(done, total) = reported.last().unwrap();
+    assert!(done < total, "{done}/{total}");
+}
+
+/// Message of the panic that unwound out of `f`
+fn panic_message(f: impl FnOnce()) -> String {
+    let payload = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_err();
+    payload.downcast_ref::<&str>().unwrap().to_string()
+}
+
+#[test]
+fn generate_resumes_callback_panics() {
+    let dir = content_dir("panic");
+    let msg = panic_message(|| {
+        let _ = CreateTorrent::new(dir.clone())
+            .with_piece_size(16384)
+            .generate_with_progress(|_, _| panic!("progress"), &CancelToken::new());
+    });
+    assert_eq!(msg, "progress");
+
+    let msg = panic_message(|| {
+        let _ = CreateTorrent::new(dir)
+            .with_filter(PathFilter::new(|_| panic!("filter")))
+            .generate();
+    });
+    assert_eq!(msg, "filter");
+
+    let files = vec![VirtualFile::new("panics".into(), 100, |_, _| {
+        panic!("reader")
+    })];
+    let msg = panic_message(|| {
+        let _ = CreateTorrent::from_files(files).generate();
+    });
+    assert_eq!(msg, "reader");
+}
+
+/// Paths of the files of `torrent` that are not pad files, sorted
+fn file_paths(torrent: &[u8]) -> Vec<PathBuf> {
+    let info = TorrentInfo::from_bytes(torrent).unwrap();
+    let mut paths = info
+        .files()
+        .iter()
+        .filter(|f| !f.is_pad_file())
+        .map(|f| f.path)
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn generate_applies_glob_filter() {
+    let dir = content_dir("globs");
+    fs::write(dir.join(".DS_Store"), b"junk").unwrap();
+    fs::write(dir.join("sub/Thumbs.db"), b"junk").unwrap();
+    fs::create_dir_all(dir.join(".git")).unwrap();
+    fs::write(dir.join(".git/config"), b"junk").unwrap();
+
+    let exclude = vec![".DS_Store".into(), "Thumbs.db".into(), ".git".into()];
+    let torrent = CreateTorrent::new(dir.clone())
+        .with_filter(PathFilter::globs(vec![], exclude.clone()))
+        .generate()
+        .unwrap();
+    assert_eq!(
+        file_paths(&torrent),
+        vec![
+            PathBuf::from("globs/a.txt"),
+            PathBuf::from("globs/sub/b.txt")
+        ]
+    );
+
+    let torrent = CreateTorrent::new(dir)
+        .with_filter(PathFilter::globs(vec!["b.*".into()], exclude))
+        .generate()
+        .unwrap();
+    assert_eq!(file_paths(&torrent), vec![PathBuf::from("globs/sub/b.txt")]);
+}
+
+#[test]
+fn generate_applies_predicate() {
+    let dir = content_dir("predicate");
+
+    let torrent = CreateTorrent::new(dir)
+        .with_filter(PathFilter::new(|path| !path.ends_with("sub")))
+        .generate()
+        .unwrap();
+    assert_eq!(file_paths(&torrent), vec![PathBuf::from("predicate/a.txt")]);
+}
+
+#[test]
+fn generate_records_modification_times() {
+    let dir = content_dir("mtime");
+
+    for record in [false, true] {
+        let torrent = CreateTorrent::new(dir.clone())
+            .with_modification_times(record)
+            .generate()
+            .unwrap();
+        let info = TorrentInfo::from_bytes(&torrent).unwrap();
+        for file in info.files().iter().filter(|f| !f.is_pad_file()) {
+            assert_eq!(file.mtime.is_some(), record, "{:?}", file.path);
+        }
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn generate_records_attributes_and_symlinks() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let dir = content_dir("attributes");
+    fs::set_permissions(dir.join("a.txt"), fs::Permissions::from_mode(0o755)).unwrap();
+    symlink("a.txt", dir.join("link")).unwrap();
+
+    let torrent = CreateTorrent::new(dir.clone())
+        .with_symlinks(true)
+        .generate()
+        .unwrap();
+    let info = TorrentInfo::from_bytes(&torrent).unwrap();
+    let files = info.files().iter().collect::<Vec<_>>();
+    let a = files.iter().find(|f| f.path.ends_with("a.txt")).unwrap();
+    assert!(a.is_executable());
+    let link = files.iter().find(|f| f.path.ends_with("link")).unwrap();
+    assert!(link.is_symlink());
+    assert!(link.symlink.as_ref().unwrap().ends_with("a.txt"));
+
+    let torrent = CreateTorrent::new(dir)
+        .with_attributes(false)
+        .generate()
+        .unwrap();
+    let info = TorrentInfo::from_bytes(&torrent).unwrap();
+    for file in info.files().iter() {
+        assert!(!file.is_executable(), "{:?}", file.path);
+    }
+}
+
+#[test]
+fn generate_aligns_v1_files() {
+    let dir = content_dir("pad");
+
+    for (pad_files, padded) in [(PadFiles::Default, false), (PadFiles::Aligned, true)] {
+        let torrent = CreateTorrent::new(dir.clone())
+            .with_piece_size(16384)
+            .with_format(TorrentFormat::V1Only)
+            .with_pad_files(pad_files)
+            .generate()
+            .unwrap();
+        let info = TorrentInfo::from_bytes(&torrent).unwrap();
+
+        let has_pad_files = info.files().iter().any(|f| f.is_pad_file());
+        assert_eq!(has_pad_files, padded, "{pad_files:?}");
+    }
+}
+
+#[test]
+fn generate_is_independent_of_hashing_threads() {
+    let dir = content_dir("threads");
+    fs::write(dir.join("sub/big.bin"), pseudo_random(3 << 20)).unwrap();
+
+    for format in [
+        TorrentFormat::Hybrid,
+        TorrentFormat::V1Only,
+        TorrentFormat::V2Only,
+    ] {
+        let create = CreateTorrent::new(dir.clone())
+            .with_piece_size(32768)
+            .with_format(format)
+            .with_creation_date(UNIX_EPOCH + Duration::from_secs(1_000_000_000));
+
+        let single = create.clone().generate().unwrap();
+        let parallel = create.with_hashing_threads(4).generate().unwrap();
+        assert!(single == parallel, "{format:?}");
+    }
+}
+
+#[test]
+fn generate_from_files_is_independent_of_hashing_threads() {
+    let files = vec![
+        VirtualFile::from_bytes("threads/big.bin".into(), pseudo_random(1 << 20)),
+        VirtualFile::from_bytes("threads/a.txt".into(), vec![b'a'; 40000]),
+        VirtualFile::from_bytes("threads/sub/b.txt".into(), b"hello".to_vec()),
+    ];
+
+    for format in [
+        TorrentFormat::Hybrid,
+        TorrentFormat::V1Only,
+        TorrentFormat::V2Only,
+    ] {
+        let create = CreateTorrent::from_files(files.clone())
+            .with_piece_size(16384)
+            .with_format(format)
+            .with_creation_date(UNIX_EPOCH + Duration::from_secs(1_000_000_000));
+
+        let single = create.clone().with_hashing_threads(1).generate().unwrap();
+        let parallel = create.with_hashing_threads(4).generate().unwrap();
+        assert!(single == parallel, "{format:?}");
+    }
+}
+
+/// Bytes that don't repeat within a piece
+fn pseudo_random(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn generate_matches_libtorrent() {
+    let dir = content_dir("libtorrent");
+    fs::write(dir.join("sub/big.bin"), pseudo_random(100_000)).unwrap();
+    let date = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+
+    for format in [
+        TorrentFormat::Hybrid,
+        TorrentFormat::V1Only,
+        TorrentFormat::V2Only,
+    ] {
+        for piece_size in [16384, 65536] {
+            let torrent = CreateTorrent::new(dir.clone())
+                .with_piece_size(piece_size)
+                .with_format(format)
+                .with_pad_files(PadFiles::Aligned)
+                .with_creation_date(date)
+                .generate()
+                .unwrap();
+
+            let params = ffi::CreateTorrentParams {
+                path: dir.to_string_lossy().into_owned(),
+                piece_size: piece_size as i32,
+                flags: (CreateFlags::from(format) | CreateFlags::from(PadFiles::Aligned)).into(),
+                trackers: vec![],
+                web_seeds: vec![],
+                nodes: vec![],
+                comment: String::new(),
+                creator: String::new(),
+                creation_date: 1_000_000_000,
+                is_private: false,
+                source: String::new(),
+                collections: vec![],
+                similar_torrents: vec![],
+                hashing_threads: 0,
+            };
+            let expected = ffi::create_torrent_generate_with_libtorrent(&params).unwrap();
+
+            assert!(torrent == expected, "{format:?} {piece_size}");
+        }
+    }
+}
+
+#[test]
+fn generate_from_files_matches_files_on_disk() {
+    let dir = content_dir("virtual");
+    let big = pseudo_random(100_000);
+    fs::write(dir.join("sub/big.bin"), &big).unwrap();
+    fs::write(dir.join("empty"), b"").unwrap();
+
+    let files = vec![
+        VirtualFile::from_bytes("virtual/sub/big.bin".into(), big),
+        VirtualFile::from_bytes("virtual/a.txt".into(), vec![b'a'; 40000]),
+        VirtualFile::from_bytes("virtual/empty".into(), vec![]),
+        VirtualFile::new("virtual/sub/b.txt".into(), 5, |offset, buf| {
+            let start = offset as usize;
+            buf.copy_from_slice(&b"hello"[start..start + buf.len()]);
+            Ok(())
+        }),
+    ];
+
+    for format in [
+        TorrentFormat::Hybrid,
+        TorrentFormat::V1Only,
+        TorrentFormat::V2Only,
+    ] {
+        for piece_size in [16384, 65536] {
+            let date = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+
+            let on_disk = CreateTorrent::new(dir.clone())
+                .with_piece_size(piece_size)
+                .with_format(format)
+                .with_pad_files(PadFiles::Aligned)
+                .with_creation_date(date)
+                .generate()
+                .unwrap();
+            let virtual_files = CreateTorrent::from_files(files.clone())
+                .with_piece_size(piece_size)
+                .with_format(format)
+                .with_pad_files(PadFiles::Aligned)
+                .with_creation_date(date)
+                .generate()
+                .unwrap();
+
+            assert!(on_disk == virtual_files, "{format:?} {piece_size}");
+        }
+    }
+}
+
+#[test]
+fn generate_from_files_reports_read_errors() {
+    let files = vec![VirtualFile::new("short".into(), 100, |offset, buf| {
+        let mut content = [0; 10].get(offset as usize..).unwrap_or_default();
+        content.read_exact(buf)
+    })];
+    let res = CreateTorrent::from_files(files).generate();
+    assert!(
+        matches!(
+            res,
+            Err(Error::Io {
+                kind: io::ErrorKind::UnexpectedEof,
+                ..
+            })
+        ),
+        "{res:?}"
+    );
+
+    let files = vec![VirtualFile::new("missing".into(), 100, |offset, buf| {
+        let mut file = fs::File::open("/nonexistent/rbtorrent")?;
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(buf)
+    })];
+    let res = CreateTorrent::from_files(files).generate();
+    assert!(
+        matches!(
+            res,
+            Err(Error::Io {
+                kind: io::ErrorKind::NotFound,
+                ..
+            })
+        ),
+        "{res:?}"
+    );
+}
+
+#[test]
+fn generate_from_files_rejects_invalid_paths() {
+    for paths in [
+        vec!["t/a", "t/../b"],
+        vec!["/t/a"],
+        vec!["t/a", "t/b", "t/./a"],
+    ] {
+        let files = paths
+            .iter()
+            .map(|path| VirtualFile::from_bytes(path.into(), b"hello".to_vec()))
+            .collect();
+        let res = CreateTorrent::from_files(files).generate();
+        assert!(matches!(res, Err(Error::InvalidArgument(_))), "{paths:?}");
+    }
+}
+
+#[test]
+fn generate_fails_without_files() {
+    let dir = content_dir("missing").join("nothing-here");
+
+    assert!(CreateTorrent::new(dir).generate().is_err());
+}
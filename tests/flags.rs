@@ -0,0 +1,196 @@
+use rbtorrent::{
+    ffi, AlertCategory, CreateFlags, FileFlags, ResumeDataFlags, SaveStateFlags, StatusFlags,
+    TorrentFlags, WriteTorrentFlags,
+};
+
+/// Checks that each flag has the same bits as its libtorrent counterpart and
+/// that the table covers every flag of the type
+macro_rules! assert_flags {
+    ($ty:ty, $table:expr) => {{
+        let mut all = <$ty>::empty();
+        for (flag, raw) in $table {
+            assert_eq!(flag.bits(), raw, "{flag:?}");
+            assert_eq!(<$ty>::from(raw), flag);
+            all |= flag;
+        }
+        assert_eq!(all, <$ty>::all());
+    }};
+}
+
+#[test]
+fn alert_category_matches_libtorrent() {
+    let bits = ffi::alert_category_bits();
+
+    assert_flags!(
+        AlertCategory,
+        [
+            (AlertCategory::ERROR, bits.error),
+            (AlertCategory::PEER, bits.peer),
+            (AlertCategory::PORT_MAPPING, bits.port_mapping),
+            (AlertCategory::STORAGE, bits.storage),
+            (AlertCategory::TRACKER, bits.tracker),
+            (AlertCategory::CONNECT, bits.connect),
+            (AlertCategory::STATUS, bits.status),
+            (AlertCategory::IP_BLOCK, bits.ip_block),
+            (AlertCategory::PERFORMANCE_WARNING, bits.performance_warning),
+            (AlertCategory::DHT, bits.dht),
+            (AlertCategory::SESSION_LOG, bits.session_log),
+            (AlertCategory::TORRENT_LOG, bits.torrent_log),
+            (AlertCategory::PEER_LOG, bits.peer_log),
+            (AlertCategory::INCOMING_REQUEST, bits.incoming_request),
+            (AlertCategory::DHT_LOG, bits.dht_log),
+            (AlertCategory::DHT_OPERATION, bits.dht_operation),
+            (AlertCategory::PORT_MAPPING_LOG, bits.port_mapping_log),
+            (AlertCategory::PICKER_LOG, bits.picker_log),
+            (AlertCategory::FILE_PROGRESS, bits.file_progress),
+            (AlertCategory::PIECE_PROGRESS, bits.piece_progress),
+            (AlertCategory::UPLOAD, bits.upload),
+            (AlertCategory::BLOCK_PROGRESS, bits.block_progress),
+        ]
+    );
+}
+
+#[test]
+fn torrent_flags_match_libtorrent() {
+    let bits = ffi::torrent_flags_bits();
+
+    assert_flags!(
+        TorrentFlags,
+        [
+            (TorrentFlags::SEED_MODE, bits.seed_mode),
+            (TorrentFlags::UPLOAD_MODE, bits.upload_mode),
+            (TorrentFlags::SHARE_MODE, bits.share_mode),
+            (TorrentFlags::APPLY_IP_FILTER, bits.apply_ip_filter),
+            (TorrentFlags::PAUSED, bits.paused),
+            (TorrentFlags::AUTO_MANAGED, bits.auto_managed),
+            (TorrentFlags::DUPLICATE_IS_ERROR, bits.duplicate_is_error),
+            (TorrentFlags::UPDATE_SUBSCRIBE, bits.update_subscribe),
+            (TorrentFlags::SUPER_SEEDING, bits.super_seeding),
+            (TorrentFlags::SEQUENTIAL_DOWNLOAD, bits.sequential_download),
+            (TorrentFlags::STOP_WHEN_READY, bits.stop_when_ready),
+            (TorrentFlags::OVERRIDE_TRACKERS, bits.override_trackers),
+            (TorrentFlags::OVERRIDE_WEB_SEEDS, bits.override_web_seeds),
+            (TorrentFlags::NEED_SAVE_RESUME, bits.need_save_resume),
+            (TorrentFlags::DISABLE_DHT, bits.disable_dht),
+            (TorrentFlags::DISABLE_LSD, bits.disable_lsd),
+            (TorrentFlags::DISABLE_PEX, bits.disable_pex),
+            (TorrentFlags::NO_VERIFY_FILES, bits.no_verify_files),
+            (
+                TorrentFlags::DEFAULT_DONT_DOWNLOAD,
+                bits.default_dont_download,
+            ),
+        ]
+    );
+
+    assert_eq!(TorrentFlags::DEFAULT_FLAGS.bits(), bits.default_flags);
+}
+
+#[test]
+fn status_flags_match_libtorrent() {
+    let bits = ffi::status_flags_bits();
+
+    assert_flags!(
+        StatusFlags,
+        [
+            (
+                StatusFlags::DISTRIBUTED_COPIES,
+                bits.query_distributed_copies,
+            ),
+            (
+                StatusFlags::ACCURATE_DOWNLOAD_COUNTERS,
+                bits.query_accurate_download_counters,
+            ),
+            (
+                StatusFlags::LAST_SEEN_COMPLETE,
+                bits.query_last_seen_complete,
+            ),
+            (StatusFlags::PIECES, bits.query_pieces),
+            (StatusFlags::VERIFIED_PIECES, bits.query_verified_pieces),
+            (StatusFlags::TORRENT_FILE, bits.query_torrent_file),
+            (StatusFlags::NAME, bits.query_name),
+            (StatusFlags::SAVE_PATH, bits.query_save_path),
+        ]
+    );
+}
+
+#[test]
+fn save_state_flags_match_libtorrent() {
+    let bits = ffi::save_state_flags_bits();
+
+    assert_flags!(
+        SaveStateFlags,
+        [
+            (SaveStateFlags::SETTINGS, bits.save_settings),
+            (SaveStateFlags::DHT_STATE, bits.save_dht_state),
+            (SaveStateFlags::EXTENSION_STATE, bits.save_extension_state),
+            (SaveStateFlags::IP_FILTER, bits.save_ip_filter),
+        ]
+    );
+}
+
+#[test]
+fn resume_data_flags_match_libtorrent() {
+    let bits = ffi::resume_data_flags_bits();
+
+    assert_flags!(
+        ResumeDataFlags,
+        [
+            (ResumeDataFlags::FLUSH_DISK_CACHE, bits.flush_disk_cache),
+            (ResumeDataFlags::SAVE_INFO_DICT, bits.save_info_dict),
+            (ResumeDataFlags::ONLY_IF_MODIFIED, bits.only_if_modified),
+        ]
+    );
+}
+
+#[test]
+fn write_torrent_flags_match_libtorrent() {
+    let bits = ffi::write_torrent_flags_bits();
+
+    assert_flags!(
+        WriteTorrentFlags,
+        [
+            (
+                WriteTorrentFlags::ALLOW_MISSING_PIECE_LAYER,
+                bits.allow_missing_piece_layer,
+            ),
+            (WriteTorrentFlags::NO_HTTP_SEEDS, bits.no_http_seeds),
+            (WriteTorrentFlags::INCLUDE_DHT_NODES, bits.include_dht_nodes),
+        ]
+    );
+}
+
+#[test]
+fn file_flags_match_libtorrent() {
+    let bits = ffi::file_flags_bits();
+
+    assert_flags!(
+        FileFlags,
+        [
+            (FileFlags::PAD_FILE, bits.pad_file),
+            (FileFlags::HIDDEN, bits.hidden),
+            (FileFlags::EXECUTABLE, bits.executable),
+            (FileFlags::SYMLINK, bits.symlink),
+        ]
+    );
+}
+
+#[test]
+fn create_flags_match_libtorrent() {
+    let bits = ffi::create_flags_bits();
+
+    assert_flags!(
+        CreateFlags,
+        [
+            (CreateFlags::MODIFICATION_TIME, bits.modification_time),
+            (CreateFlags::SYMLINKS, bits.symlinks),
+            (CreateFlags::V2_ONLY, bits.v2_only),
+            (CreateFlags::V1_ONLY, bits.v1_only),
+            (CreateFlags::CANONICAL_FILES, bits.canonical_files),
+            (CreateFlags::NO_ATTRIBUTES, bits.no_attributes),
+            (
+                CreateFlags::CANONICAL_FILES_NO_TAIL_PADDING,
+                bits.canonical_files_no_tail_padding,
+            ),
+        ]
+    );
+}
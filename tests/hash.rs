@@ -0,0 +1,109 @@
+use rbtorrent::{InfoHash, ParseHashError, Sha1Hash, Sha256Hash};
+
+const V1_HEX: &str = "c9e15763f722f23e98a29decdfae341b98d53056";
+const V1_BASE32: &str = "ZHQVOY7XELZD5GFCTXWN7LRUDOMNKMCW";
+const V2_HEX: &str = "d8dd32ac93357c368556af3ac1d95c9d76bd0dff6fa9833ecdac3d53134efabb";
+
+#[test]
+fn sha1_hash_parses_hex_and_base32() {
+    let hash: Sha1Hash = V1_HEX.parse().unwrap();
+
+    assert_eq!(hash.to_string(), V1_HEX);
+    assert_eq!(hash.to_base32(), V1_BASE32);
+    assert_eq!(V1_BASE32.parse::<Sha1Hash>().unwrap(), hash);
+    assert_eq!(V1_BASE32.to_lowercase().parse::<Sha1Hash>().unwrap(), hash);
+    assert_eq!(V1_HEX.to_uppercase().parse::<Sha1Hash>().unwrap(), hash);
+}
+
+#[test]
+fn sha256_hash_parses_hex_base32_and_multihash() {
+    let hash: Sha256Hash = V2_HEX.parse().unwrap();
+
+    assert_eq!(hash.to_string(), V2_HEX);
+    assert_eq!(format!("1220{V2_HEX}").parse::<Sha256Hash>().unwrap(), hash);
+    assert_eq!(hash.to_base32().len(), 52);
+    assert_eq!(hash.to_base32().parse::<Sha256Hash>().unwrap(), hash);
+
+    assert_eq!(
+        format!("1320{V2_HEX}").parse::<Sha256Hash>(),
+        Err(ParseHashError::UnsupportedMultihash)
+    );
+}
+
+#[test]
+fn hash_rejects_invalid_input() {
+    assert_eq!(
+        "abc".parse::<Sha1Hash>(),
+        Err(ParseHashError::InvalidLength(3))
+    );
+    assert_eq!(
+        V1_HEX.replace('c', "g").parse::<Sha1Hash>(),
+        Err(ParseHashError::InvalidCharacter('g'))
+    );
+    assert_eq!(
+        V1_BASE32.replace('Z', "1").parse::<Sha1Hash>(),
+        Err(ParseHashError::InvalidCharacter('1'))
+    );
+}
+
+#[test]
+fn info_hash_round_trips() {
+    let v1: Sha1Hash = V1_HEX.parse().unwrap();
+    let v2: Sha256Hash = V2_HEX.parse().unwrap();
+
+    for hash in [InfoHash::v1(v1), InfoHash::v2(v2), InfoHash::hybrid(v1, v2)] {
+        assert_eq!(hash.to_string().parse::<InfoHash>().unwrap(), hash);
+    }
+
+    assert_eq!(InfoHash::v2(v2).to_string(), format!("1220{V2_HEX}"));
+    assert_eq!(V1_BASE32.parse::<InfoHash>().unwrap(), InfoHash::v1(v1));
+    assert!(InfoHash::hybrid(v1, v2).is_hybrid());
+    assert!(InfoHash::default().is_empty());
+    assert_eq!("".parse::<InfoHash>().unwrap(), InfoHash::default());
+}
+
+#[test]
+fn info_hash_rejects_duplicate_versions() {
+    assert_eq!(
+        format!("{V1_HEX},{V1_BASE32}").parse::<InfoHash>(),
+        Err(ParseHashError::DuplicateHash)
+    );
+    assert_eq!(
+        format!("{V2_HEX},1220{V2_HEX}").parse::<InfoHash>(),
+        Err(ParseHashError::DuplicateHash)
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn info_hash_serde_round_trips() {
+    let v1: Sha1Hash = V1_HEX.parse().unwrap();
+    let v2: Sha256Hash = V2_HEX.parse().unwrap();
+
+    for hash in [
+        InfoHash::default(),
+        InfoHash::v1(v1),
+        InfoHash::v2(v2),
+        InfoHash::hybrid(v1, v2),
+    ] {
+        let json = serde_json::to_string(&hash).unwrap();
+        assert_eq!(serde_json::from_str::<InfoHash>(&json).unwrap(), hash);
+    }
+
+    assert_eq!(
+        serde_json::to_string(&InfoHash::hybrid(v1, v2)).unwrap(),
+        format!("\"{V1_HEX},1220{V2_HEX}\"")
+    );
+    assert!(serde_json::from_str::<InfoHash>(&format!("\"{V1_HEX},{V1_HEX}\"")).is_err());
+}
+
+#[test]
+fn hashes_order_by_bytes() {
+    let mut hashes = vec![Sha1Hash([2; 20]), Sha1Hash([0; 20]), Sha1Hash([1; 20])];
+    hashes.sort();
+
+    assert_eq!(
+        hashes,
+        vec![Sha1Hash([0; 20]), Sha1Hash([1; 20]), Sha1Hash([2; 20])]
+    );
+}
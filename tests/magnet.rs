@@ -0,0 +1,175 @@
+use rbtorrent::{ffi, InfoHash, MagnetUri, ParseHashError, ParseMagnetError, Sha1Hash, Sha256Hash};
+use std::net::SocketAddr;
+
+const V1_HEX: &str = "c9e15763f722f23e98a29decdfae341b98d53056";
+const V1_BASE32: &str = "ZHQVOY7XELZD5GFCTXWN7LRUDOMNKMCW";
+const V2_HEX: &str = "d8dd32ac93357c368556af3ac1d95c9d76bd0dff6fa9833ecdac3d53134efabb";
+
+/// Checks that libtorrent extracts the same fields from `uri`
+fn assert_matches_libtorrent(uri: &str, magnet: &MagnetUri) {
+    let lt = ffi::magnet_uri_parse(uri).unwrap();
+
+    assert_eq!(InfoHash::from(lt.info_hashes), magnet.info_hash, "{uri}");
+    assert_eq!(
+        Some(lt.name).filter(|name| !name.is_empty()),
+        magnet.display_name,
+        "{uri}"
+    );
+    assert_eq!(lt.trackers, magnet.trackers, "{uri}");
+    assert_eq!(lt.url_seeds, magnet.web_seeds, "{uri}");
+    assert_eq!(
+        lt.peers
+            .iter()
+            .map(|ep| ep.to_socket_addr().unwrap())
+            .collect::<Vec<_>>(),
+        magnet.peers,
+        "{uri}"
+    );
+
+    let selected = lt
+        .file_priorities
+        .iter()
+        .enumerate()
+        .filter(|(_, &prio)| prio != 0)
+        .map(|(index, _)| index as u32)
+        .collect::<Vec<_>>();
+    let expected = magnet
+        .select_only
+        .iter()
+        .cloned()
+        .flatten()
+        .collect::<Vec<_>>();
+    assert_eq!(selected, expected, "{uri}");
+}
+
+#[test]
+fn parses_like_libtorrent() {
+    for uri in [
+        format!("magnet:?xt=urn:btih:{V1_HEX}"),
+        format!("magnet:?xt=urn:btih:{V1_BASE32}&dn=hello%20world"),
+        format!("magnet:?xt=urn:btmh:1220{V2_HEX}&dn=v2"),
+        format!(
+            "magnet:?xt=urn:btih:{V1_HEX}&xt=urn:btmh:1220{V2_HEX}\
+             &tr=udp%3A%2F%2Ftracker.example%3A6969%2Fannounce\
+             &TR=http%3A%2F%2Ftracker.example%2Fannounce\
+             &ws=http%3A%2F%2Fseed.example%2Ffile"
+        ),
+        format!("magnet:?xt=urn:btih:{V1_HEX}&x.pe=127.0.0.1:6881&x.pe=[::1]:6882&x.pe=bogus"),
+        format!("magnet:?xt=urn:btih:{V1_HEX}&x.pe=127.0.0.1%3A6881"),
+        format!("magnet:?xt=urn:btih:{V1_HEX}&dn=a+b&tr=http%3A%2F%2Ft%2Fa+b"),
+        format!("magnet:?xt=urn:btih:{V1_HEX}&dn=a%zzb&tr=http%3A%2F%2Ft%2&ws=http%3A%2F%2Fw%2F"),
+        format!("magnet:?xt=urn:btih:{V1_HEX}&so=0,2,4-6"),
+        format!("magnet:?xt=urn:btih:{V1_HEX}&so=1,5-3,20000,7"),
+        format!("magnet:?xt=urn:btih:{V1_HEX}&so=1,a"),
+        format!("magnet:?xt=urn:btih:{V1_HEX}&xs=http%3A%2F%2Fexample.com%2Ft.torrent&foo=bar"),
+    ] {
+        let magnet: MagnetUri = uri.parse().unwrap();
+        assert_matches_libtorrent(&uri, &magnet);
+    }
+}
+
+#[test]
+fn parses_every_field() {
+    let uri = format!(
+        "magnet:?xt=urn:btih:{V1_HEX}&xt=urn:btmh:1220{V2_HEX}&dn=a%20b\
+         &tr=http%3A%2F%2Ft%2Fannounce&ws=http%3A%2F%2Fw%2F&x.pe=10.0.0.1:1\
+         &so=1,3-4&xs=http%3A%2F%2Fx%2Ft.torrent"
+    );
+    let magnet: MagnetUri = uri.parse().unwrap();
+
+    assert_eq!(
+        magnet,
+        MagnetUri {
+            info_hash: InfoHash::hybrid(
+                V1_HEX.parse::<Sha1Hash>().unwrap(),
+                V2_HEX.parse::<Sha256Hash>().unwrap(),
+            ),
+            display_name: Some("a b".into()),
+            trackers: vec!["http://t/announce".into()],
+            web_seeds: vec!["http://w/".into()],
+            peers: vec!["10.0.0.1:1".parse::<SocketAddr>().unwrap()],
+            select_only: vec![1..=1, 3..=4],
+            exact_sources: vec!["http://x/t.torrent".into()],
+        }
+    );
+    assert!(magnet.is_selected(3));
+    assert!(!magnet.is_selected(2));
+}
+
+#[test]
+fn renders_parseable_uri() {
+    let magnet = MagnetUri {
+        info_hash: InfoHash::hybrid(V1_HEX.parse().unwrap(), V2_HEX.parse().unwrap()),
+        display_name: Some("a b&c".into()),
+        trackers: vec!["udp://tracker.example:6969/announce".into()],
+        web_seeds: vec!["http://seed.example/file".into()],
+        peers: vec!["[::1]:6881".parse().unwrap()],
+        select_only: vec![0..=0, 2..=5],
+        exact_sources: vec!["http://example.com/t.torrent".into()],
+    };
+
+    let uri = magnet.to_string();
+    assert!(uri.starts_with(&format!(
+        "magnet:?xt=urn:btih:{V1_HEX}&xt=urn:btmh:1220{V2_HEX}"
+    )));
+    assert!(uri.contains("&so=0,2-5"), "{uri}");
+
+    assert_eq!(uri.parse::<MagnetUri>().unwrap(), magnet);
+    assert_matches_libtorrent(&uri, &magnet);
+}
+
+#[test]
+fn rejects_invalid_uri() {
+    assert_eq!(
+        format!("http://example.com/?xt=urn:btih:{V1_HEX}").parse::<MagnetUri>(),
+        Err(ParseMagnetError::NotMagnet)
+    );
+    assert_eq!(
+        "magnet:?dn=name".parse::<MagnetUri>(),
+        Err(ParseMagnetError::MissingInfoHash)
+    );
+    assert!(matches!(
+        "magnet:?xt=urn:btih:1234".parse::<MagnetUri>(),
+        Err(ParseMagnetError::InvalidInfoHash(_))
+    ));
+    assert_eq!(
+        format!("magnet:?xt=urn:btih:{V1_HEX}%zz").parse::<MagnetUri>(),
+        Err(ParseMagnetError::InvalidEncoding)
+    );
+}
+
+#[test]
+fn decodes_non_utf8_values_lossily() {
+    let uri = format!(
+        "magnet:?xt=urn:btih:{V1_HEX}&dn=a%FFb&tr=http%3A%2F%2Ft%2F%FF&ws=http%3A%2F%2Fw%2F%FE"
+    );
+    let magnet = uri.parse::<MagnetUri>().unwrap();
+
+    assert_eq!(magnet.display_name.as_deref(), Some("a\u{FFFD}b"));
+    assert_eq!(magnet.trackers, vec!["http://t/\u{FFFD}".to_string()]);
+    assert_eq!(magnet.web_seeds, vec!["http://w/\u{FFFD}".to_string()]);
+    assert_matches_libtorrent(&uri, &magnet);
+}
+
+#[test]
+fn rejects_btmh_other_than_sha256_multihash() {
+    for (hash, err) in [
+        (V2_HEX.to_string(), ParseHashError::InvalidLength(64)),
+        (
+            format!("1220{}", V2_HEX.parse::<Sha256Hash>().unwrap().to_base32()),
+            ParseHashError::InvalidLength(56),
+        ),
+        (
+            format!("1320{V2_HEX}"),
+            ParseHashError::UnsupportedMultihash,
+        ),
+    ] {
+        let uri = format!("magnet:?xt=urn:btmh:{hash}");
+        assert_eq!(
+            uri.parse::<MagnetUri>(),
+            Err(ParseMagnetError::InvalidInfoHash(err)),
+            "{uri}"
+        );
+        assert!(ffi::magnet_uri_parse(&uri).is_err(), "{uri}");
+    }
+}
@@ -0,0 +1,111 @@
+use rbtorrent::{
+    AddTorrentParams, Error, FsResumeStore, InfoHash, ResumeDataStore, SessionBuilder, Sha1Hash,
+    Sha256Hash, TorrentFlags, RESUME_DATA_FLUSH_TIMEOUT,
+};
+use std::time::{Duration, Instant};
+
+fn load_all(store: &FsResumeStore) -> Vec<Vec<u8>> {
+    let mut all = store
+        .load_all()
+        .unwrap()
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    all.sort();
+    all
+}
+
+#[test]
+fn fs_resume_store_round_trip() {
+    let dir = std::env::temp_dir().join(format!("rbtorrent-resume-{}", std::process::id()));
+    let store = FsResumeStore::new(&dir).unwrap();
+
+    let v1 = InfoHash::v1(Sha1Hash([0xab; 20]));
+    let v2 = InfoHash::v2(Sha256Hash([0xcd; 32]));
+
+    store.save(&v1, b"d4:infoe").unwrap();
+    store.save(&v2, b"d3:fooe").unwrap();
+    // saving again replaces the previous resume data
+    store.save(&v2, b"d3:bare").unwrap();
+
+    assert_eq!(
+        store.path(&v1),
+        dir.join(format!("{}.fastresume", "ab".repeat(20)))
+    );
+    assert_eq!(
+        store.path(&v2),
+        dir.join(format!("{}.fastresume", "cd".repeat(32)))
+    );
+
+    assert_eq!(
+        load_all(&store),
+        vec![b"d3:bare".to_vec(), b"d4:infoe".to_vec()]
+    );
+
+    store.remove(&v1).unwrap();
+    // removing a torrent that isn't stored is not an error
+    store.remove(&v1).unwrap();
+    assert_eq!(load_all(&store), vec![b"d3:bare".to_vec()]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fs_resume_store_reports_unreadable_entries() {
+    let dir = std::env::temp_dir().join(format!("rbtorrent-unreadable-{}", std::process::id()));
+    let store = FsResumeStore::new(&dir).unwrap();
+
+    store
+        .save(&InfoHash::v1(Sha1Hash([0xab; 20])), b"d4:infoe")
+        .unwrap();
+    // reading a directory fails
+    std::fs::create_dir(dir.join("broken.fastresume")).unwrap();
+
+    let mut all = store.load_all().unwrap();
+    all.sort_by_key(|entry| entry.is_ok());
+    assert_eq!(all.len(), 2);
+    assert!(all[0].is_err());
+    assert_eq!(all[1].as_ref().unwrap(), b"d4:infoe");
+
+    let session = SessionBuilder::new()
+        .with_listen_interfaces(vec!["127.0.0.1:0".into()])
+        .set_disable_disk(true)
+        .build()
+        .unwrap();
+    let restored = session.restore_torrents(&store).unwrap();
+    assert_eq!(restored.len(), 2);
+    assert!(restored
+        .iter()
+        .any(|res| matches!(res, Err(Error::Io { .. }))));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn saver_flushes_torrents_on_stop() {
+    let dir = std::env::temp_dir().join(format!("rbtorrent-saver-{}", std::process::id()));
+    let store = FsResumeStore::new(&dir).unwrap();
+
+    let session = SessionBuilder::new()
+        .with_listen_interfaces(vec!["127.0.0.1:0".into()])
+        .set_disable_disk(true)
+        .build()
+        .unwrap();
+    let saver = session
+        .start_resume_data_saver(store.clone(), Duration::from_secs(3600))
+        .unwrap();
+
+    let params = AddTorrentParams::from_magnet_uri(
+        "magnet:?xt=urn:btih:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".into(),
+    )
+    .with_save_path(".".into())
+    .with_torrent_flags(TorrentFlags::PAUSED | TorrentFlags::NEED_SAVE_RESUME);
+    session.add_torrent(params).unwrap();
+
+    let started = Instant::now();
+    saver.stop().unwrap();
+    assert!(started.elapsed() < RESUME_DATA_FLUSH_TIMEOUT);
+    assert_eq!(load_all(&store).len(), 1);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,149 @@
+use rbtorrent::{
+    AddTorrentParams, AlertCategory, AlertKind, AlertStream, Error, SaveStateFlags, Session,
+    SessionBuilder, SettingsPack, StatusFlags, TorrentFlags, TorrentHandle, TorrentHandleTrait,
+};
+use std::time::Duration;
+
+const MAGNET: &str = "magnet:?xt=urn:btih:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+
+fn session() -> Session {
+    SessionBuilder::new()
+        .with_alert_mask(AlertCategory::STATUS | AlertCategory::ERROR)
+        .with_listen_interfaces(vec!["127.0.0.1:0".into()])
+        .set_disable_disk(true)
+        .build()
+        .unwrap()
+}
+
+fn add_paused(session: &Session) -> TorrentHandle {
+    let params = AddTorrentParams::from_magnet_uri(MAGNET.into())
+        .with_save_path(".".into())
+        .with_torrent_flags(TorrentFlags::PAUSED);
+
+    session.add_torrent(params).unwrap()
+}
+
+/// Waits for the alert of the torrent being added and returns its handle
+fn added_handle(alerts: AlertStream) -> Option<TorrentHandle> {
+    alerts
+        .into_blocking_iter()
+        .find(|alert| matches!(alert.kind, AlertKind::AddTorrent { .. }))
+        .and_then(|alert| alert.handle)
+}
+
+#[test]
+fn every_stream_receives_each_alert() {
+    let session = session();
+    let first = session.alerts().unwrap();
+    let second = session.alerts().unwrap();
+
+    let handle = add_paused(&session);
+
+    assert_eq!(added_handle(first).as_ref(), Some(&handle));
+    assert_eq!(added_handle(second).as_ref(), Some(&handle));
+}
+
+#[test]
+fn alerts_carry_non_utf8_names_lossily() {
+    let session = session();
+    let alerts = session.alerts().unwrap();
+
+    let params = AddTorrentParams::from_magnet_uri(format!("{MAGNET}&dn=a%FFb"))
+        .with_save_path(".".into())
+        .with_torrent_flags(TorrentFlags::PAUSED);
+    session.add_torrent(params).unwrap();
+
+    let added = alerts
+        .into_blocking_iter()
+        .find(|alert| matches!(alert.kind, AlertKind::AddTorrent { .. }))
+        .unwrap();
+    assert_eq!(added.torrent_name.as_deref(), Some("a\u{FFFD}b"));
+    assert!(added.message.contains("a\u{FFFD}b"), "{}", added.message);
+}
+
+#[test]
+fn handles_report_non_utf8_names_lossily() {
+    let session = session();
+    let params = AddTorrentParams::from_magnet_uri(format!("{MAGNET}&dn=a%FFb"))
+        .with_save_path(".".into())
+        .with_torrent_flags(TorrentFlags::PAUSED);
+    let handle = session.add_torrent(params).unwrap();
+
+    assert_eq!(handle.get_name().unwrap(), "a\u{FFFD}b");
+    assert_eq!(handle.get_status().unwrap().name, "a\u{FFFD}b");
+}
+
+#[test]
+fn handles_of_the_same_torrent_are_equal() {
+    let session = session();
+    let handle = add_paused(&session);
+
+    assert_eq!(session.torrents().unwrap(), vec![handle]);
+}
+
+#[test]
+fn handle_queries_fail_after_shutdown() {
+    let session = session();
+    let handle = add_paused(&session);
+
+    assert!(session.shutdown(Duration::from_secs(10)).unwrap());
+
+    assert!(!handle.is_valid());
+    assert_eq!(handle.has_metadata(), Err(Error::SessionClosed));
+}
+
+#[test]
+fn queried_pieces_are_reported_before_metadata() {
+    let session = session();
+    let handle = add_paused(&session);
+
+    let status = handle.get_status_with(StatusFlags::PIECES).unwrap();
+    assert_eq!(status.pieces, Some(vec![]));
+    assert_eq!(status.verified_pieces, None);
+}
+
+#[test]
+fn saved_state_restores_settings() {
+    let session = SessionBuilder::new()
+        .with_settings(SettingsPack::new().with_connections_limit(77))
+        .with_listen_interfaces(vec!["127.0.0.1:0".into()])
+        .set_disable_disk(true)
+        .build()
+        .unwrap();
+    let state = session.save_state(SaveStateFlags::SETTINGS).unwrap();
+
+    let restored = SessionBuilder::from_saved_state(&state)
+        .with_listen_interfaces(vec!["127.0.0.1:0".into()])
+        .set_disable_disk(true)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        restored.get_settings().unwrap().connections_limit(),
+        Some(77)
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_handle_queries_run_on_the_session_thread() {
+    use futures::executor::block_on;
+
+    let session = session();
+    let handle = add_paused(&session);
+
+    block_on(session.pause_async()).unwrap();
+    assert_eq!(block_on(handle.has_metadata_async()), Ok(false));
+    assert_eq!(
+        block_on(handle.get_status_with_async(StatusFlags::PIECES))
+            .unwrap()
+            .pieces,
+        Some(vec![])
+    );
+
+    assert!(session.shutdown(Duration::from_secs(10)).unwrap());
+    assert_eq!(
+        block_on(handle.has_metadata_async()),
+        Err(Error::SessionClosed)
+    );
+}
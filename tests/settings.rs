@@ -0,0 +1,94 @@
+use rbtorrent::{
+    ChokingAlgorithm, EncLevel, EncPolicy, Error, ProxySettings, ProxyType, SeedChokingAlgorithm,
+    SessionBuilder, SettingValue, SettingsPack,
+};
+
+fn proxy() -> ProxySettings {
+    ProxySettings {
+        proxy_type: ProxyType::Socks5Pw,
+        hostname: "proxy.example".into(),
+        port: 1080,
+        username: "user".into(),
+        password: "secret".into(),
+        proxy_hostnames: true,
+        proxy_peer_connections: true,
+        proxy_tracker_connections: false,
+    }
+}
+
+#[test]
+fn applied_settings_are_read_back() {
+    let session = SessionBuilder::new()
+        .with_listen_interfaces(vec!["127.0.0.1:0".into()])
+        .set_disable_disk(true)
+        .build()
+        .unwrap();
+
+    let pack = SettingsPack::new()
+        .with_upload_rate_limit(1000)
+        .with_download_rate_limit(2000)
+        .with_connections_limit(30)
+        .with_unchoke_slots_limit(5)
+        .with_choking_algorithm(ChokingAlgorithm::RateBased)
+        .with_seed_choking_algorithm(SeedChokingAlgorithm::AntiLeech)
+        .with_active_downloads(2)
+        .with_active_seeds(3)
+        .with_active_checking(1)
+        .with_active_limit(6)
+        .with_enable_dht(false)
+        .with_enable_lsd(false)
+        .with_enable_upnp(false)
+        .with_enable_natpmp(false)
+        .with_enc_policy(EncPolicy::Forced, EncPolicy::Enabled)
+        .with_allowed_enc_level(EncLevel::Rc4)
+        .with_proxy(proxy())
+        .with_str("user_agent", "test/1.0".into())
+        .unwrap();
+    session.apply_settings(pack.clone()).unwrap();
+
+    let settings = session.get_settings().unwrap();
+    for (name, value) in pack.iter() {
+        assert_eq!(settings.get(name), Some(value), "{name}");
+    }
+
+    assert_eq!(settings.upload_rate_limit(), Some(1000));
+    assert_eq!(settings.download_rate_limit(), Some(2000));
+    assert_eq!(settings.connections_limit(), Some(30));
+    assert_eq!(settings.unchoke_slots_limit(), Some(5));
+    assert_eq!(
+        settings.choking_algorithm(),
+        Some(ChokingAlgorithm::RateBased)
+    );
+    assert_eq!(
+        settings.seed_choking_algorithm(),
+        Some(SeedChokingAlgorithm::AntiLeech)
+    );
+    assert_eq!(settings.active_downloads(), Some(2));
+    assert_eq!(settings.active_seeds(), Some(3));
+    assert_eq!(settings.active_checking(), Some(1));
+    assert_eq!(settings.active_limit(), Some(6));
+    assert_eq!(settings.enable_dht(), Some(false));
+    assert_eq!(settings.enable_lsd(), Some(false));
+    assert_eq!(settings.enable_upnp(), Some(false));
+    assert_eq!(settings.enable_natpmp(), Some(false));
+    assert_eq!(settings.out_enc_policy(), Some(EncPolicy::Forced));
+    assert_eq!(settings.in_enc_policy(), Some(EncPolicy::Enabled));
+    assert_eq!(settings.allowed_enc_level(), Some(EncLevel::Rc4));
+    assert_eq!(settings.proxy(), Some(proxy()));
+    assert_eq!(settings.get_str("user_agent"), Some("test/1.0"));
+}
+
+#[test]
+fn names_are_checked_when_set() {
+    assert!(matches!(
+        SettingsPack::new().with_int("no_such_setting", 1),
+        Err(Error::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        SettingsPack::new().with_int("enable_dht", 1),
+        Err(Error::InvalidArgument(_))
+    ));
+
+    let pack = SettingsPack::new().with_bool("enable_dht", true).unwrap();
+    assert_eq!(pack.get("enable_dht"), Some(&SettingValue::Bool(true)));
+}
@@ -0,0 +1,258 @@
+use rbtorrent::{
+    FileSlice, FileTreeEntry, FileTreeKind, LoadTorrentLimits, PeerRequest, Sha1Hash, TorrentInfo,
+    WebSeed, WebSeedKind, WriteTorrentFlags,
+};
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+/// Info dictionary of a single file torrent with one piece
+fn info_section() -> Vec<u8> {
+    let mut info =
+        b"d11:collectionsl3:fooe6:lengthi5e4:name5:hello12:piece lengthi16384e6:pieces20:".to_vec();
+    info.extend_from_slice(&[0xaa; 20]);
+    info.extend_from_slice(b"7:privatei1e7:similarl20:");
+    info.extend_from_slice(&[0xbb; 20]);
+    info.extend_from_slice(b"ee");
+    info
+}
+
+fn torrent_bytes() -> Vec<u8> {
+    let mut torrent = b"d7:comment2:hi10:created by4:test13:creation datei1000000000e\
+        9:httpseedsl17:http://seed/helloe4:info"
+        .to_vec();
+    torrent.extend_from_slice(&info_section());
+    torrent.extend_from_slice(b"8:url-listl13:http://ws/fooee");
+    torrent
+}
+
+#[test]
+fn from_bytes_parses_torrent() {
+    let info = TorrentInfo::from_bytes(&torrent_bytes()).unwrap();
+
+    assert_eq!(info.name(), "hello");
+    assert_eq!(info.comment().as_deref(), Some("hi"));
+    assert_eq!(info.creator().as_deref(), Some("test"));
+    assert_eq!(
+        info.creation_date(),
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000))
+    );
+    assert_eq!(info.total_size(), 5);
+    assert_eq!(info.piece_length(), 16384);
+    assert_eq!(info.num_pieces(), 1);
+    assert!(info.is_private());
+    assert!(!info.is_i2p());
+    assert!(info.info_hashes().has_v1());
+    assert!(!info.info_hashes().has_v2());
+    assert_eq!(info.similar_torrents(), vec![Sha1Hash([0xbb; 20])]);
+    assert_eq!(info.collections(), vec!["foo".to_string()]);
+    assert_eq!(info.ssl_cert(), None);
+    assert_eq!(info.info_section(), info_section());
+    assert_eq!(info.trackers().len(), 0);
+
+    let mut web_seeds = info.web_seeds();
+    web_seeds.sort_by(|a, b| a.url.cmp(&b.url));
+    assert_eq!(
+        web_seeds,
+        vec![
+            WebSeed {
+                url: "http://seed/hello".into(),
+                kind: WebSeedKind::HttpSeed,
+                auth: None,
+                extra_headers: vec![],
+            },
+            WebSeed {
+                url: "http://ws/foo".into(),
+                kind: WebSeedKind::UrlSeed,
+                auth: None,
+                extra_headers: vec![],
+            },
+        ]
+    );
+}
+
+#[test]
+fn bencoded_torrent_keeps_trackers_and_web_seeds() {
+    let mut torrent = b"d13:announce-listll13:http://a/anne\
+        l13:http://b/ann13:http://c/annee9:httpseedsl17:http://seed/helloe4:info"
+        .to_vec();
+    torrent.extend_from_slice(&info_section());
+    torrent.extend_from_slice(b"8:url-listl13:http://ws/fooee");
+    let info = TorrentInfo::from_bytes(&torrent).unwrap();
+
+    let written = info
+        .to_bencoded_torrent(WriteTorrentFlags::empty())
+        .unwrap();
+    let reread = TorrentInfo::from_bytes(&written).unwrap();
+
+    let trackers = |info: &TorrentInfo| {
+        info.trackers()
+            .into_iter()
+            .map(|ae| (ae.url, ae.tier))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        trackers(&reread),
+        vec![
+            ("http://a/ann".to_string(), 0),
+            ("http://b/ann".to_string(), 1),
+            ("http://c/ann".to_string(), 1),
+        ]
+    );
+    assert_eq!(trackers(&reread), trackers(&info));
+    assert_eq!(reread.web_seeds().len(), 2);
+    assert_eq!(reread.web_seeds(), info.web_seeds());
+}
+
+/// Multi-file torrent of two pieces, with a pad file aligning `root/b/y` to
+/// the second piece
+fn multi_file_torrent_bytes() -> Vec<u8> {
+    let mut torrent = b"d4:infod5:filesl\
+        d6:lengthi3e4:pathl1:a5:x.txtee\
+        d4:attr1:p6:lengthi16381e4:pathl4:.pad5:16381ee\
+        d6:lengthi5e4:pathl1:b1:yee\
+        d4:attr1:x6:lengthi2e5:mtimei1000e4:pathl1:a1:zee\
+        e4:name4:root12:piece lengthi16384e6:pieces40:"
+        .to_vec();
+    torrent.extend_from_slice(&[0xaa; 40]);
+    torrent.extend_from_slice(b"ee");
+    torrent
+}
+
+#[test]
+fn files_lists_torrent_files() {
+    let info = TorrentInfo::from_bytes(&multi_file_torrent_bytes()).unwrap();
+    let files = info.files();
+
+    assert_eq!(files.len(), 4);
+    assert!(files.get(4).is_none());
+
+    let x = files.get(0).unwrap();
+    assert_eq!(x.path, PathBuf::from("root/a/x.txt"));
+    assert_eq!((x.size, x.offset), (3, 0));
+    assert!(x.flags.is_empty());
+    assert_eq!(x.root, None);
+
+    let pad = files.get(1).unwrap();
+    assert!(pad.is_pad_file());
+    assert_eq!((pad.size, pad.offset), (16381, 3));
+
+    let y = files.get(2).unwrap();
+    assert_eq!(y.offset, 16384);
+
+    let z = files.get(3).unwrap();
+    assert!(z.is_executable());
+    assert!(!z.is_symlink());
+    assert_eq!(z.symlink, None);
+    assert_eq!(
+        z.mtime,
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1000))
+    );
+
+    assert_eq!(files.iter().count(), 4);
+}
+
+#[test]
+fn files_map_between_files_and_pieces() {
+    let info = TorrentInfo::from_bytes(&multi_file_torrent_bytes()).unwrap();
+    let files = info.files();
+
+    assert_eq!(files.total_size(), 16391);
+    assert_eq!(files.num_pieces(), 2);
+    assert_eq!(files.piece_size(0), Some(16384));
+    assert_eq!(files.piece_size(1), Some(7));
+    assert_eq!(files.piece_size(2), None);
+    assert_eq!(files.file_size(2), Some(5));
+    assert_eq!(files.file_size(4), None);
+
+    assert_eq!(
+        files.map_file(2, 1, 4),
+        Some(PeerRequest {
+            piece: 1,
+            start: 1,
+            length: 4,
+        })
+    );
+    assert_eq!(files.map_file(4, 0, 1), None);
+    assert_eq!(files.map_file(2, 5, 1), None);
+    assert_eq!(files.map_file(0, u64::MAX, 1), None);
+    // ranges reaching past the end of the file are truncated
+    assert_eq!(
+        files.map_file(2, 4, u32::MAX),
+        Some(PeerRequest {
+            piece: 1,
+            start: 4,
+            length: 1,
+        })
+    );
+
+    assert_eq!(
+        files.map_block(1, 0, 7),
+        Some(vec![
+            FileSlice {
+                file_index: 2,
+                offset: 0,
+                size: 5,
+            },
+            FileSlice {
+                file_index: 3,
+                offset: 0,
+                size: 2,
+            },
+        ])
+    );
+    assert_eq!(files.map_block(2, 0, 1), None);
+    assert_eq!(files.map_block(1, 0, 8), None);
+    assert_eq!(files.map_block(0, 16384, 1), None);
+    assert_eq!(files.map_block(0, u64::MAX, 1), None);
+    assert_eq!(files.map_block(0, 1, u64::MAX), None);
+}
+
+#[test]
+fn files_tree_groups_directories() {
+    let info = TorrentInfo::from_bytes(&multi_file_torrent_bytes()).unwrap();
+
+    let entry = |path: &str, depth, kind| FileTreeEntry {
+        path: path.into(),
+        depth,
+        kind,
+    };
+
+    assert_eq!(
+        info.files().tree().collect::<Vec<_>>(),
+        vec![
+            entry("root", 0, FileTreeKind::Directory),
+            entry("root/a", 1, FileTreeKind::Directory),
+            entry("root/a/x.txt", 2, FileTreeKind::File(0)),
+            entry("root/a/z", 2, FileTreeKind::File(3)),
+            entry("root/b", 1, FileTreeKind::Directory),
+            entry("root/b/y", 2, FileTreeKind::File(2)),
+        ]
+    );
+}
+
+#[test]
+fn from_bytes_rejects_malformed_input() {
+    let err = TorrentInfo::from_bytes(b"d4:infod").err().unwrap();
+    assert!(err.code().is_some(), "{err:?}");
+
+    assert!(TorrentInfo::from_bytes(b"").is_err());
+}
+
+#[test]
+fn from_bytes_enforces_limits() {
+    let torrent = torrent_bytes();
+
+    let limits = LoadTorrentLimits {
+        max_buffer_size: torrent.len() as i32 - 1,
+        ..Default::default()
+    };
+    assert!(TorrentInfo::from_bytes_with_limits(&torrent, &limits).is_err());
+
+    let limits = LoadTorrentLimits {
+        max_decode_depth: 1,
+        ..Default::default()
+    };
+    assert!(TorrentInfo::from_bytes_with_limits(&torrent, &limits).is_err());
+}